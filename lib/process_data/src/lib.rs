@@ -29,6 +29,15 @@ static PAGESIZE: Lazy<usize> = Lazy::new(sysconf::pagesize);
 
 static RE_UID: Lazy<Regex> = Lazy::new(|| Regex::new(r"Uid:\s*(\d+)").unwrap());
 
+static RE_CPUS_ALLOWED_LIST: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"Cpus_allowed_list:\s*(\S+)").unwrap());
+
+static RE_VOLUNTARY_CTXT_SWITCHES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^voluntary_ctxt_switches:\s*(\d+)").unwrap());
+
+static RE_NONVOLUNTARY_CTXT_SWITCHES: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"nonvoluntary_ctxt_switches:\s*(\d+)").unwrap());
+
 static RE_IO_READ: Lazy<Regex> = Lazy::new(|| Regex::new(r"read_bytes:\s*(\d+)").unwrap());
 
 static RE_IO_WRITE: Lazy<Regex> = Lazy::new(|| Regex::new(r"write_bytes:\s*(\d+)").unwrap());
@@ -129,6 +138,9 @@ pub struct GpuUsageStats {
 #[derive(Debug, Default, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ProcessData {
     pub pid: i32,
+    pub ppid: i32,
+    /// One of the `R`, `S`, `D`, `Z`, `T`... states, see man proc(5).
+    pub state: char,
     pub user: String,
     proc_path: PathBuf,
     pub comm: String,
@@ -138,6 +150,13 @@ pub struct ProcessData {
     pub cpu_time_timestamp: u64,
     pub memory_usage: usize,
     pub starttime: u64, // in clock ticks, see man proc(5)!
+    /// CPU number last executed on.
+    pub processor: i32,
+    pub nice: i32,
+    /// The affinity in list format, e.g. `0-3,6`.
+    pub cpus_allowed: Option<String>,
+    pub voluntary_ctxt_switches: Option<u64>,
+    pub nonvoluntary_ctxt_switches: Option<u64>,
     pub cgroup: Option<String>,
     pub containerization: Containerization,
     pub read_bytes: Option<u64>,
@@ -181,9 +200,8 @@ impl ProcessData {
         }
     }
 
-    fn get_uid(proc_path: &Path) -> Result<u32> {
-        let status = std::fs::read_to_string(proc_path.join("status"))?;
-        if let Some(captures) = RE_UID.captures(&status) {
+    fn get_uid(status: &str) -> Result<u32> {
+        if let Some(captures) = RE_UID.captures(status) {
             let first_num_str = captures.get(1).context("no uid found")?;
             first_num_str
                 .as_str()
@@ -207,11 +225,13 @@ impl ProcessData {
         }
     }
 
-    pub fn all_process_data() -> Result<Vec<Self>> {
+    /// Reads every process from `<root>/proc`.
+    pub fn all_process_data(root: &Path) -> Result<Vec<Self>> {
         Self::update_nvidia_stats();
 
+        let pattern = format!("{}/proc/[0-9]*/", Self::glob_root(root)?);
         let mut process_data = vec![];
-        for entry in glob("/proc/[0-9]*/").context("unable to glob")?.flatten() {
+        for entry in glob(&pattern).context("unable to glob")?.flatten() {
             let data = ProcessData::try_from_path(entry);
 
             if let Ok(data) = data {
@@ -222,8 +242,28 @@ impl ProcessData {
         Ok(process_data)
     }
 
+    /// Reads the threads of `pid` from `/proc/<pid>/task`, the `pid` of the
+    /// returned data is the thread id.
+    pub fn all_thread_data(root: &Path, pid: i32) -> Result<Vec<Self>> {
+        let pattern = format!("{}/proc/{pid}/task/[0-9]*/", Self::glob_root(root)?);
+        let mut thread_data = vec![];
+        for entry in glob(&pattern).context("unable to glob")?.flatten() {
+            if let Ok(data) = ProcessData::try_from_path(entry) {
+                thread_data.push(data)
+            }
+        }
+
+        Ok(thread_data)
+    }
+
+    fn glob_root(root: &Path) -> Result<String> {
+        let root = root.to_str().context("root is not valid UTF-8")?;
+        Ok(glob::Pattern::escape(root.trim_end_matches('/')))
+    }
+
     pub fn try_from_path(proc_path: PathBuf) -> Result<Self> {
         let stat = std::fs::read_to_string(proc_path.join("stat"))?;
+        let status = std::fs::read_to_string(proc_path.join("status"))?;
         let statm = std::fs::read_to_string(proc_path.join("statm"))?;
         let comm = std::fs::read_to_string(proc_path.join("comm"))?;
         let commandline = std::fs::read_to_string(proc_path.join("cmdline"))?;
@@ -238,7 +278,7 @@ impl ProcessData {
             .parse()?;
 
         let user = USERS_CACHE
-            .get(&Self::get_uid(&proc_path)?)
+            .get(&Self::get_uid(&status)?)
             .cloned()
             .unwrap_or(String::from("root"));
 
@@ -255,6 +295,8 @@ impl ProcessData {
         let comm = comm.replace('\n', "");
 
         // -2 to accommodate for only collecting after the second item (which is the executable name as mentioned above)
+        let state = stat[2 - 2].chars().next().unwrap_or('?');
+        let ppid = stat[3 - 2].parse()?;
         let user_cpu_time = stat[13 - 2].parse::<u64>()?;
         let system_cpu_time = stat[14 - 2].parse::<u64>()?;
 
@@ -264,6 +306,25 @@ impl ProcessData {
 
         let starttime = stat[21 - 2].parse()?;
 
+        let processor = stat[38 - 2].parse()?;
+
+        let nice = stat[18 - 2].parse()?;
+
+        let cpus_allowed = RE_CPUS_ALLOWED_LIST
+            .captures(&status)
+            .and_then(|captures| captures.get(1))
+            .map(|capture| capture.as_str().to_string());
+
+        let voluntary_ctxt_switches = RE_VOLUNTARY_CTXT_SWITCHES
+            .captures(&status)
+            .and_then(|captures| captures.get(1))
+            .and_then(|capture| capture.as_str().parse::<u64>().ok());
+
+        let nonvoluntary_ctxt_switches = RE_NONVOLUNTARY_CTXT_SWITCHES
+            .captures(&status)
+            .and_then(|captures| captures.get(1))
+            .and_then(|capture| capture.as_str().parse::<u64>().ok());
+
         let cgroup = Self::sanitize_cgroup(cgroup);
 
         let containerization = match &proc_path.join("root").join(".flatpak-info").exists() {
@@ -294,6 +355,8 @@ impl ProcessData {
 
         Ok(Self {
             pid,
+            ppid,
+            state,
             user,
             comm,
             commandline,
@@ -302,6 +365,11 @@ impl ProcessData {
             cpu_time_timestamp,
             memory_usage,
             starttime,
+            processor,
+            nice,
+            cpus_allowed,
+            voluntary_ctxt_switches,
+            nonvoluntary_ctxt_switches,
             cgroup,
             proc_path,
             containerization,
@@ -0,0 +1,409 @@
+//! Threshold alerts from the `alerts` section of the config, e.g.
+//!
+//! ```ini
+//! [alerts]
+//! hot_cpu = cpu.total > 90 for 30s
+//! vm_steal = cpu.steal > 10 for 1m
+//! cpu_power = cpu.power > 25 for 5m
+//! low_mem = mem.available < 1G
+//! full_disk = drive.nvme0n1.used > 95%
+//! low_battery = battery.charge < 15 and discharging
+//! hot_gpu = gpu.0.temp > 85
+//!
+//! [alert_commands]
+//! low_battery = notify-send "Battery low"
+//! ```
+//!
+//! A value is `kind.key` or `kind.name.key`, without a name the rule applies
+//! to every drive, interface, battery or GPU. Thresholds accept the sizes of
+//! the process filter, and `%` compares against the total, e.g. the size of
+//! the drive. Bare words are flags of the same resource.
+//!
+//! While a rule holds its sidebar block is red. When it starts to hold the
+//! terminal bell rings and the command in `alert_commands` runs with
+//! `$RESTOP_ALERT`, `$RESTOP_RULE` and `$RESTOP_RESOURCE` set. A replay
+//! only colors the blocks, neither rings nor runs the commands.
+
+use std::{
+    collections::{HashMap, HashSet},
+    process::{Command, Stdio},
+    str::FromStr,
+    sync::Mutex,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+
+use crate::sensor::{settings::SETTINGS, units::parse_size};
+
+static RULES: Lazy<Vec<Rule>> = Lazy::new(|| {
+    SETTINGS
+        .alert_rules()
+        .into_iter()
+        .filter_map(|(name, rule)| match Rule::parse(&name, &rule) {
+            Ok(rule) => Some(rule),
+            Err(err) => {
+                tracing::error!("invalid alert {}: {}", name, err);
+                None
+            }
+        })
+        .collect()
+});
+
+static STATE: Lazy<Mutex<AlertState>> = Lazy::new(|| Mutex::new(AlertState::default()));
+
+/// Rules are tracked by their index and the id of the resource.
+#[derive(Default)]
+struct AlertState {
+    /// Since when a rule holds, it fires once it held for its duration.
+    pending: HashMap<(usize, String), Instant>,
+    firing: HashSet<(usize, String)>,
+    bell: bool,
+    replaying: bool,
+}
+
+/// What one resource reports to the rules after an update.
+#[derive(Debug, Default)]
+pub struct Readings {
+    /// `cpu`, `mem`, `drive`, `net`, `battery` or `gpu`.
+    kind: &'static str,
+    /// E.g. the block device or the interface.
+    name: Option<String>,
+    /// The key, the value and the total `%` refers to.
+    values: Vec<(&'static str, f64, f64)>,
+    flags: Vec<&'static str>,
+}
+
+impl Readings {
+    pub fn new(kind: &'static str, name: Option<String>) -> Self {
+        Self {
+            kind,
+            name,
+            ..Default::default()
+        }
+    }
+
+    /// A value which is already a percentage.
+    pub fn percent(self, key: &'static str, value: Option<f64>) -> Self {
+        self.share(key, value, Some(100.))
+    }
+
+    pub fn value(self, key: &'static str, value: Option<f64>) -> Self {
+        self.share(key, value, None)
+    }
+
+    /// A value which is part of `total`, `%` thresholds need the total.
+    pub fn share(mut self, key: &'static str, value: Option<f64>, total: Option<f64>) -> Self {
+        if let Some(value) = value.filter(|e| e.is_finite()) {
+            self.values.push((key, value, total.unwrap_or(f64::NAN)));
+        }
+        self
+    }
+
+    pub fn flag(mut self, flag: &'static str, set: bool) -> Self {
+        if set {
+            self.flags.push(flag);
+        }
+        self
+    }
+
+    fn get(&self, key: &str) -> Option<(f64, f64)> {
+        self.values
+            .iter()
+            .find(|(k, _, _)| *k == key)
+            .map(|(_, value, total)| (*value, *total))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl FromStr for Op {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            ">" => Op::Gt,
+            ">=" => Op::Ge,
+            "<" => Op::Lt,
+            "<=" => Op::Le,
+            _ => bail!("unknown operator `{}`", s),
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Compare {
+        key: String,
+        op: Op,
+        threshold: f64,
+        percent: bool,
+    },
+    Flag(String),
+}
+
+impl Condition {
+    fn holds(&self, readings: &Readings) -> bool {
+        match self {
+            Condition::Compare {
+                key,
+                op,
+                threshold,
+                percent,
+            } => {
+                let Some((value, total)) = readings.get(key) else {
+                    return false;
+                };
+                let value = if *percent {
+                    value / total * 100.
+                } else {
+                    value
+                };
+                // NaN, e.g. `%` without a total, never holds
+                match op {
+                    Op::Gt => value > *threshold,
+                    Op::Ge => value >= *threshold,
+                    Op::Lt => value < *threshold,
+                    Op::Le => value <= *threshold,
+                }
+            }
+            Condition::Flag(flag) => readings.flags.iter().any(|e| e == flag),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    name: String,
+    text: String,
+    kind: String,
+    device: Option<String>,
+    conditions: Vec<Condition>,
+    duration: Duration,
+}
+
+impl Rule {
+    /// E.g. `battery.charge < 15 and discharging`.
+    pub fn parse(name: &str, text: &str) -> Result<Self> {
+        let (conditions_text, duration) = match text.rsplit_once(" for ") {
+            Some((conditions, duration)) => (conditions, parse_duration(duration.trim())?),
+            None => (text, Duration::ZERO),
+        };
+
+        let mut scope: Option<(String, Option<String>)> = None;
+        let mut conditions = vec![];
+        for term in conditions_text.split(" and ") {
+            let tokens: Vec<&str> = term.split_whitespace().collect();
+            match tokens.as_slice() {
+                [flag] => conditions.push(Condition::Flag(flag.to_string())),
+                [path, op, threshold] => {
+                    let mut parts: Vec<&str> = path.split('.').collect();
+                    let key = match parts.len() {
+                        2 | 3 => parts.pop().unwrap_or_default(),
+                        _ => bail!("`{}` is not kind.key or kind.name.key", path),
+                    };
+                    let this = (parts[0].to_string(), parts.get(1).map(|e| e.to_string()));
+                    if scope.get_or_insert_with(|| this.clone()) != &this {
+                        bail!("all values of a rule have to be of one resource");
+                    }
+
+                    let percent = threshold.ends_with('%');
+                    let threshold = if percent {
+                        threshold
+                            .trim_end_matches('%')
+                            .parse()
+                            .with_context(|| format!("invalid percentage `{}`", threshold))?
+                    } else {
+                        parse_size(threshold.trim_end_matches("/s"))?
+                    };
+
+                    conditions.push(Condition::Compare {
+                        key: key.to_string(),
+                        op: op.parse()?,
+                        threshold,
+                        percent,
+                    });
+                }
+                _ => bail!("unable to read `{}`", term.trim()),
+            }
+        }
+
+        let Some((kind, device)) = scope else {
+            bail!("the rule compares no value");
+        };
+        Ok(Self {
+            name: name.to_string(),
+            text: text.to_string(),
+            kind,
+            device,
+            conditions,
+            duration,
+        })
+    }
+
+    fn applies_to(&self, readings: &Readings) -> bool {
+        self.kind == readings.kind
+            && self
+                .device
+                .as_ref()
+                .is_none_or(|device| Some(device) == readings.name.as_ref())
+    }
+
+    fn holds(&self, readings: &Readings) -> bool {
+        self.conditions.iter().all(|e| e.holds(readings))
+    }
+}
+
+/// `30s`, `5m`, `1h`, plain numbers are seconds.
+fn parse_duration(value: &str) -> Result<Duration> {
+    let (num, scale) = match value.chars().last() {
+        Some('s') => (&value[..value.len() - 1], 1.),
+        Some('m') => (&value[..value.len() - 1], 60.),
+        Some('h') => (&value[..value.len() - 1], 3600.),
+        _ => (value, 1.),
+    };
+    num.parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs >= 0.)
+        .map(|secs| Duration::from_secs_f64(secs * scale))
+        .with_context(|| format!("invalid duration `{}`", value))
+}
+
+/// Checks the rules against the latest readings of the resource `id`, taken
+/// at `sampled_at`. The duration of a rule is measured in sample time, so it
+/// holds as long in a sped up replay or in the samples of a freeze.
+pub fn evaluate(id: &str, readings: &Readings, sampled_at: Instant) {
+    if RULES.is_empty() {
+        return;
+    }
+    let Ok(mut state) = STATE.lock() else {
+        return;
+    };
+
+    for (idx, rule) in RULES.iter().enumerate() {
+        if !rule.applies_to(readings) {
+            continue;
+        }
+
+        let key = (idx, id.to_string());
+        if !rule.holds(readings) {
+            state.pending.remove(&key);
+            state.firing.remove(&key);
+            continue;
+        }
+
+        let since = *state.pending.entry(key.clone()).or_insert(sampled_at);
+        if sampled_at.saturating_duration_since(since) >= rule.duration && state.firing.insert(key)
+        {
+            tracing::info!("alert {} fired for {}", rule.name, id);
+            if !state.replaying {
+                state.bell = true;
+                run_command(rule, id);
+            }
+        }
+    }
+}
+
+fn run_command(rule: &Rule, id: &str) {
+    let Some(command) = SETTINGS.alert_command(&rule.name) else {
+        return;
+    };
+
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(&command)
+        .env("RESTOP_ALERT", &rule.name)
+        .env("RESTOP_RULE", &rule.text)
+        .env("RESTOP_RESOURCE", id)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+    match child {
+        // reap it without blocking the caller
+        Ok(mut child) => {
+            let _ = thread::Builder::new()
+                .name("alertcommand".to_owned())
+                .spawn(move || child.wait());
+        }
+        Err(err) => {
+            tracing::error!("unable to run the command of alert {}: {}", rule.name, err);
+        }
+    }
+}
+
+/// The readings come from a recording, the alerts are only shown.
+pub fn set_replaying() {
+    if let Ok(mut state) = STATE.lock() {
+        state.replaying = true;
+    }
+}
+
+/// Forgets which rules held, e.g. when a replay starts over.
+pub fn reset() {
+    if let Ok(mut state) = STATE.lock() {
+        state.pending.clear();
+        state.firing.clear();
+    }
+}
+
+/// Whether a rule fires for the resource `id`.
+pub fn is_firing(id: &str) -> bool {
+    STATE
+        .lock()
+        .is_ok_and(|state| state.firing.iter().any(|(_, e)| e == id))
+}
+
+/// True once after a rule started to fire.
+pub fn take_bell() -> bool {
+    STATE
+        .lock()
+        .is_ok_and(|mut state| std::mem::take(&mut state.bell))
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::{Readings, Rule};
+
+    #[test]
+    fn test_rules() {
+        let rule = Rule::parse("hot", "cpu.total > 90 for 30s").unwrap();
+        assert_eq!(rule.duration, Duration::from_secs(30));
+        assert!(rule.holds(&Readings::new("cpu", None).percent("total", Some(95.))));
+        assert!(!rule.holds(&Readings::new("cpu", None).percent("total", Some(50.))));
+        assert!(!rule.holds(&Readings::new("cpu", None)));
+
+        let rule = Rule::parse("mem", "mem.available < 1G").unwrap();
+        let mem = Readings::new("mem", None).share("available", Some(512e6), Some(16e9));
+        assert!(rule.holds(&mem));
+
+        let rule = Rule::parse("disk", "drive.nvme0n1.used > 95%").unwrap();
+        let nvme = Readings::new("drive", Some("nvme0n1".to_string()));
+        let sda = Readings::new("drive", Some("sda".to_string()));
+        assert!(rule.applies_to(&nvme));
+        assert!(!rule.applies_to(&sda));
+        assert!(rule.holds(&nvme.share("used", Some(98.), Some(100.))));
+
+        let rule = Rule::parse("bat", "battery.charge < 15 and discharging").unwrap();
+        let low = || Readings::new("battery", Some("BAT0".to_string())).percent("charge", Some(9.));
+        assert!(rule.applies_to(&low()));
+        assert!(rule.holds(&low().flag("discharging", true)));
+        assert!(!rule.holds(&low().flag("discharging", false)));
+
+        assert!(Rule::parse("gpu", "gpu.0.temp > 85").is_ok());
+        assert!(Rule::parse("x", "discharging").is_err());
+        assert!(Rule::parse("x", "cpu.total >> 90").is_err());
+        assert!(Rule::parse("x", "cpu.total > 90 and mem.used > 1G").is_err());
+        assert!(Rule::parse("x", "cpu.total > 90 for ever").is_err());
+    }
+}
@@ -1,11 +1,15 @@
 use std::{
     cell::Cell,
-    collections::LinkedList,
-    io::{stdout, Stdout},
+    collections::{HashMap, VecDeque},
+    io::{stdin, stdout, BufRead, BufReader, ErrorKind, Stdout, Write},
+    mem::{discriminant, Discriminant},
+    net::TcpListener,
+    path::Path,
     thread,
-    time::{Duration, SystemTime},
+    time::{Duration, Instant, SystemTime},
 };
 
+use anyhow::Context;
 use chin_tools::AResult;
 use crossterm::{
     event::{read, Event, KeyEvent},
@@ -14,17 +18,32 @@ use crossterm::{
 };
 
 use flume::{Receiver, RecvTimeoutError, Sender};
-use ratatui::{backend::CrosstermBackend, layout::Rect, Terminal};
+use ratatui::{backend::CrosstermBackend, layout::Rect, text::Line, Terminal};
 
 use crate::{
+    alert,
+    cli::OutputFormat,
+    headless,
+    metrics::Exporter,
+    record::{Recorder, Replay},
+    remote::{self, Remote, Target},
     resource::{
-        battery::ResBattery, cpu::ResCPU, drive::ResDrive, gpu::ResGPU, memory::ResMEM,
-        network::ResNetwork, process::ResProcess, HardwareWorker, ResourceType, SensorRsp,
+        battery::ResBattery,
+        cpu::ResCPU,
+        drive::ResDrive,
+        gpu::ResGPU,
+        memory::ResMEM,
+        network::ResNetwork,
+        process::{ProcessRsp, ResProcess, PROCESS_SNAPSHOTS},
+        HardwareWorker, ResourceType, SensorRsp,
     },
+    ring,
+    sensor::settings::SETTINGS,
     utils::is_ctrl_c,
     view::{
+        keymap::{self, Action, KeyContext},
         sidebar_and_page::SidebarAndPage,
-        theme::{SharedTheme, Theme},
+        theme::{self, SharedTheme, Theme},
         LayoutType, Navigator, NavigatorArgs,
     },
 };
@@ -46,6 +65,13 @@ pub struct ResTop {
 
     res_tx: Sender<ResourceEvent>,
     res_rx: Receiver<ResourceEvent>,
+
+    theme: SharedTheme,
+    recorder: Option<Recorder>,
+    /// Set when the samples come from a recording instead of the sensors.
+    replay: Option<Replay>,
+    /// Set when the samples come from an agent on another machine.
+    remote: Option<Remote>,
 }
 
 pub enum ResourceEvent {
@@ -56,6 +82,48 @@ pub enum ResourceEvent {
     Quit,
 }
 
+/// As many samples of a resource as its longest rings hold.
+const FROZEN_SAMPLES: usize = 1000;
+
+/// The samples which arrive while frozen with the time they arrived, applied
+/// on unfreeze. Older ones than the rings hold are dropped.
+#[derive(Default)]
+struct FrozenSamples {
+    /// Per kind of sample, the order it arrived in, when it arrived, when it
+    /// was taken and the sample.
+    queues: HashMap<FrozenKind, VecDeque<(usize, Instant, Instant, SensorRsp)>>,
+    arrived: usize,
+}
+
+/// The process table, the load and the uptime are limited on their own.
+type FrozenKind = (String, Option<Discriminant<ProcessRsp>>);
+
+impl FrozenSamples {
+    fn push(&mut self, sampled_at: Instant, rsp: SensorRsp) {
+        let (process, limit) = match &rsp {
+            SensorRsp::Process(process) => (Some(discriminant(process)), PROCESS_SNAPSHOTS),
+            _ => (None, FROZEN_SAMPLES),
+        };
+        let kind = (rsp.get_id().to_owned(), process);
+        let queue = self.queues.entry(kind).or_default();
+        if queue.len() >= limit {
+            queue.pop_front();
+        }
+        queue.push_back((self.arrived, Instant::now(), sampled_at, rsp));
+        self.arrived += 1;
+    }
+
+    /// Takes all the samples in the order they arrived.
+    fn drain(&mut self) -> Vec<(Instant, Instant, SensorRsp)> {
+        let mut samples: Vec<_> = self.queues.drain().flat_map(|(_, queue)| queue).collect();
+        samples.sort_unstable_by_key(|(order, ..)| *order);
+        samples
+            .into_iter()
+            .map(|(_, arrived, sampled_at, rsp)| (arrived, sampled_at, rsp))
+            .collect()
+    }
+}
+
 impl ResTop {
     pub fn new() -> AResult<Self> {
         let theme = SharedTheme::new(Theme::default());
@@ -93,23 +161,348 @@ impl ResTop {
             res_rx: rx,
             focused_index: None,
             layout: LayoutType::SidebarAndPage(SidebarAndPage::default()),
+            theme,
+            recorder: None,
+            replay: None,
+            remote: None,
+        })
+    }
+
+    /// Shows the machine of the agent at `target` instead of this one.
+    pub fn connect(target: &Target) -> AResult<Self> {
+        let theme = SharedTheme::new(Theme::default());
+        let (tx, rx) = flume::unbounded::<ResourceEvent>();
+        let remote = Remote::connect(target)?;
+
+        Ok(ResTop {
+            resources: remote.build_resources(&theme)?,
+            res_tx: tx,
+            res_rx: rx,
+            focused_index: None,
+            layout: LayoutType::SidebarAndPage(SidebarAndPage::default()),
+            theme,
+            recorder: None,
+            replay: None,
+            remote: Some(remote),
         })
     }
 
+    /// Shows the recording at `path` instead of this machine.
+    pub fn replay(path: &Path) -> AResult<Self> {
+        let theme = SharedTheme::new(Theme::default());
+        let (tx, rx) = flume::unbounded::<ResourceEvent>();
+        let replay = Replay::open(path)?;
+        alert::set_replaying();
+
+        Ok(ResTop {
+            resources: replay.build_resources(&theme)?,
+            res_tx: tx,
+            res_rx: rx,
+            focused_index: None,
+            layout: LayoutType::SidebarAndPage(SidebarAndPage::default()),
+            theme,
+            recorder: None,
+            replay: Some(replay),
+            remote: None,
+        })
+    }
+
+    /// Writes every sample to `path` from now on.
+    pub fn record(&mut self, path: &Path) -> AResult<()> {
+        self.recorder
+            .replace(Recorder::create(path, &self.resources)?);
+        Ok(())
+    }
+
     pub fn handle_key(&mut self, key: &KeyEvent) {
-        self.layout.handle_event(
+        let handled = self.layout.handle_event(
             &crate::view::NavigatorEvent::KeyEvent(*key),
             NavigatorArgs {
                 resources: &mut self.resources,
+                replay: self.replay.is_some(),
             },
-        )
+        );
+        if handled {
+            return;
+        }
+
+        let handled = self
+            .replay
+            .as_mut()
+            .is_some_and(|replay| replay.handle_key(key));
+        if !handled && keymap::lookup(key, KeyContext::Global) == Some(Action::Quit) {
+            let _ = self.res_tx.send(ResourceEvent::Quit);
+        }
+    }
+
+    /// Starts over with fresh resources if the replay was moved backwards,
+    /// the frames up to the new position are then played at once.
+    fn rewind_replay(&mut self) -> bool {
+        let Some(replay) = self.replay.as_mut() else {
+            return false;
+        };
+        if !replay.take_rewound() {
+            return false;
+        }
+        match replay.build_resources(&self.theme) {
+            Ok(resources) => self.resources = resources,
+            Err(err) => tracing::error!("unable to rewind the replay: {}", err),
+        }
+        alert::reset();
+        true
+    }
+
+    fn record_data(&mut self, rsp: &SensorRsp) {
+        if let Some(recorder) = self.recorder.as_mut() {
+            if let Err(err) = recorder.write(rsp) {
+                tracing::error!("unable to record, stop recording: {}", err);
+                self.recorder.take();
+            }
+        }
+    }
+
+    fn finish_recording(&mut self) -> AResult<()> {
+        match self.recorder.take() {
+            Some(recorder) => recorder.finish(),
+            None => Ok(()),
+        }
+    }
+
+    /// Asks for the resources whose interval has passed, returns how long
+    /// until the next one is due.
+    fn fetch_due(
+        &self,
+        worker: &HardwareWorker,
+        last_fetch_ts: &mut [Option<Instant>],
+    ) -> Duration {
+        let mut next_fetch = Duration::from_millis(200);
+        for (ele, last_fetch) in self.resources.iter().zip(last_fetch_ts.iter_mut()) {
+            let interval = ele.refresh_interval();
+            let elapsed = last_fetch.map(|ts| ts.elapsed());
+            if elapsed.is_none_or(|elapsed| elapsed >= interval) {
+                ele.fetch_data(&worker.tx);
+                last_fetch.replace(Instant::now());
+                next_fetch = next_fetch.min(interval);
+            } else if let Some(elapsed) = elapsed {
+                next_fetch = next_fetch.min(interval.saturating_sub(elapsed));
+            }
+        }
+        next_fetch
+    }
+
+    /// Returns the index of the resource the reading belongs to.
+    fn update_data(&mut self, rsp: &SensorRsp, sampled_at: Instant) -> Option<usize> {
+        self.resources
+            .iter_mut()
+            .position(|ele| ele.updata_data(rsp, sampled_at))
+    }
+
+    /// Shows a sample taken at `sampled_at`, or keeps it while frozen. False
+    /// if it was kept.
+    fn receive(&mut self, frozen: &mut FrozenSamples, sampled_at: Instant, rsp: SensorRsp) -> bool {
+        self.record_data(&rsp);
+        if ring::is_frozen() && rsp.is_sample() {
+            frozen.push(sampled_at, rsp);
+            false
+        } else {
+            self.update_data(&rsp, sampled_at);
+            true
+        }
+    }
+
+    /// Runs the same sensor pipelines as `run`, but writes every reading to
+    /// stdout instead of drawing it. With `once` the first round of every
+    /// resource is only used to prime the counters and the second one is
+    /// printed.
+    pub fn run_headless(&mut self, format: OutputFormat, once: bool) -> AResult<()> {
+        let hardware_worker = HardwareWorker::spawn(&self.res_tx);
+        let mut out = stdout().lock();
+        let result = self.write_headless(&hardware_worker, &mut out, format, once);
+        self.finish_recording()?;
+
+        match result {
+            // the reader went away, e.g. `restop --json | head`
+            Err(err)
+                if err
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) =>
+            {
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    fn write_headless(
+        &mut self,
+        hardware_worker: &HardwareWorker,
+        out: &mut impl Write,
+        format: OutputFormat,
+        once: bool,
+    ) -> AResult<()> {
+        headless::write_header(out, format)?;
+
+        let len = self.resources.len();
+        let mut last_fetch_ts: Vec<Option<Instant>> = vec![None; len];
+        let mut rounds = vec![0; len];
+        let mut printed = vec![false; len];
+        loop {
+            // a resource which did not answer its second round in time is
+            // left out
+            if once && (0..len).all(|idx| printed[idx] || rounds[idx] > 2) {
+                break;
+            }
+
+            let fetched = last_fetch_ts.clone();
+            let next_fetch = self.fetch_due(hardware_worker, &mut last_fetch_ts);
+            for (round, (old, new)) in rounds.iter_mut().zip(fetched.iter().zip(&last_fetch_ts)) {
+                if old != new {
+                    *round += 1;
+                }
+            }
+
+            match self.res_rx.recv_timeout(next_fetch) {
+                Ok(ResourceEvent::SensorRsp(rsp)) => {
+                    self.record_data(&rsp);
+                    let idx = self.update_data(&rsp, Instant::now());
+                    if once {
+                        match idx {
+                            Some(idx) if rounds[idx] == 2 && !printed[idx] => printed[idx] = true,
+                            _ => continue,
+                        }
+                    }
+                    headless::write_record(out, format, &rsp)?;
+                    out.flush()?;
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        Ok(())
+    }
+
+    /// Samples like `run_headless`, but keeps the latest readings for the
+    /// scrapers of the OpenMetrics endpoint on `addr`.
+    pub fn serve_metrics(&mut self, addr: &str) -> AResult<()> {
+        let exporter = Exporter::bind(addr)?;
+        eprintln!(
+            "Serving metrics on http://{}/metrics",
+            exporter.local_addr()
+        );
+
+        let hardware_worker = HardwareWorker::spawn(&self.res_tx);
+        let mut last_fetch_ts: Vec<Option<Instant>> = vec![None; self.resources.len()];
+        loop {
+            let next_fetch = self.fetch_due(&hardware_worker, &mut last_fetch_ts);
+            match self.res_rx.recv_timeout(next_fetch) {
+                Ok(ResourceEvent::SensorRsp(rsp)) => {
+                    exporter.update(&rsp);
+                    self.record_data(&rsp);
+                    self.update_data(&rsp, Instant::now());
+                }
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        self.finish_recording()
+    }
+
+    /// Streams the readings to the client of `restop --connect`, on stdio or
+    /// to one TCP client at a time on `listen`. `allow_actions` lets the
+    /// client signal and renice processes.
+    pub fn serve_agent(&mut self, listen: Option<&str>, allow_actions: bool) -> AResult<()> {
+        // GPUs can not be rebuilt on the client
+        self.resources
+            .retain(|rt| !matches!(rt, ResourceType::GPU(_)));
+        let hardware_worker = HardwareWorker::spawn(&self.res_tx);
+
+        let result = match listen {
+            None => {
+                match self.agent_session(
+                    &hardware_worker,
+                    BufReader::new(stdin()),
+                    stdout(),
+                    allow_actions,
+                ) {
+                    // the client went away
+                    Err(err)
+                        if err
+                            .downcast_ref::<std::io::Error>()
+                            .is_some_and(|e| e.kind() == ErrorKind::BrokenPipe) =>
+                    {
+                        Ok(())
+                    }
+                    result => result,
+                }
+            }
+            Some(addr) => {
+                let token = remote::token()
+                    .context("set RESTOP_TOKEN to a secret shared with the clients")?;
+                let listener = TcpListener::bind(addr)
+                    .with_context(|| format!("unable to listen on {}", addr))?;
+                eprintln!("Waiting for clients on {}", listener.local_addr()?);
+                for stream in listener.incoming() {
+                    let stream = stream?;
+                    let peer = stream.peer_addr()?;
+                    eprintln!("{} connected", peer);
+                    stream.set_nodelay(true)?;
+                    let mut reader = BufReader::new(stream.try_clone()?);
+                    if !remote::authenticate(&stream, &mut reader, &token)? {
+                        eprintln!("{} sent a wrong token", peer);
+                        continue;
+                    }
+                    if let Err(err) =
+                        self.agent_session(&hardware_worker, reader, stream, allow_actions)
+                    {
+                        tracing::error!("lost {}: {}", peer, err);
+                    }
+                    eprintln!("{} disconnected", peer);
+                }
+                Ok(())
+            }
+        };
+        self.finish_recording()?;
+
+        result
+    }
+
+    fn agent_session(
+        &mut self,
+        hardware_worker: &HardwareWorker,
+        reader: impl BufRead + Send + 'static,
+        mut out: impl Write,
+        allow_actions: bool,
+    ) -> AResult<()> {
+        // leftovers of the previous client
+        self.res_rx.drain();
+        remote::reset_worker();
+        remote::write_hello(&mut out, &self.resources)?;
+        remote::read_requests(reader, allow_actions, self.res_tx.clone())?;
+
+        let started = Instant::now();
+        let mut last_fetch_ts: Vec<Option<Instant>> = vec![None; self.resources.len()];
+        loop {
+            let next_fetch = self.fetch_due(hardware_worker, &mut last_fetch_ts);
+            match self.res_rx.recv_timeout(next_fetch) {
+                Ok(ResourceEvent::SensorRsp(rsp)) => {
+                    self.record_data(&rsp);
+                    remote::write_rsp(&mut out, started, &rsp)?;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+                Ok(_) | Err(RecvTimeoutError::Timeout) => {}
+            }
+        }
+        Ok(())
     }
 
     pub fn run(&mut self, term: &mut Terminal<CrosstermBackend<Stdout>>) -> AResult<()> {
         let mut event_enum = RedrawEventEnum::all();
 
-        let hardware_worker = HardwareWorker::spawn(&self.res_tx);
-        let worker_tx = &hardware_worker.tx;
+        let hardware_worker = (self.replay.is_none() && self.remote.is_none())
+            .then(|| HardwareWorker::spawn(&self.res_tx));
+        if let Some(remote) = self.remote.as_mut() {
+            remote.start(&self.res_tx)?;
+        }
 
         {
             let tx = self.res_tx.clone();
@@ -135,29 +528,36 @@ impl ResTop {
                 .unwrap();
         }
 
-        let last_sync_ts = Cell::new(SystemTime::UNIX_EPOCH);
+        let mut last_fetch_ts: Vec<Option<Instant>> = vec![None; self.resources.len()];
         let lasy_draw_ts = Cell::new(SystemTime::UNIX_EPOCH);
+        let mut frozen_rsps = FrozenSamples::default();
 
         Ok(loop {
             let now = SystemTime::now();
-            let sync_diff = now
-                .duration_since(last_sync_ts.get())
-                .unwrap_or(Duration::from_millis(300));
 
-            if sync_diff > Duration::from_secs(1) {
-                for ele in self.resources.iter() {
-                    ele.fetch_data(worker_tx);
+            // every resource is sampled at its own interval, wake up for the closest one
+            let mut next_fetch = Duration::from_millis(200);
+            if let Some(replay) = self.replay.as_mut() {
+                for (sampled_at, rsp) in replay.due() {
+                    if self.receive(&mut frozen_rsps, sampled_at, rsp) {
+                        event_enum = event_enum.union(RedrawEventEnum::SENSOR);
+                    }
                 }
-                last_sync_ts.set(now);
+            }
+            if let Some(worker) = hardware_worker.as_ref() {
+                next_fetch = self.fetch_due(worker, &mut last_fetch_ts);
             }
 
+            let redraw_interval = Duration::from_millis(300).min(Duration::from_secs_f32(
+                SETTINGS.refresh_speed().ui_refresh_interval(),
+            ));
             if event_enum.contains(RedrawEventEnum::TERM)
                 || event_enum.contains(RedrawEventEnum::SENSOR)
                 || (event_enum.contains(RedrawEventEnum::INTERVAL)
                     && now
                         .duration_since(lasy_draw_ts.get())
                         .unwrap_or(Duration::from_secs(1))
-                        > Duration::from_millis(300))
+                        > redraw_interval)
             {
                 lasy_draw_ts.set(now);
 
@@ -168,6 +568,24 @@ impl ResTop {
                         self.layout
                             .render(f, &mut self.resources, self.focused_index.clone());
 
+                        let status = match (self.replay.as_ref(), self.remote.as_ref()) {
+                            (Some(replay), _) => Some(format!(" REPLAY {} ", replay.status())),
+                            (_, Some(remote)) => Some(format!(" REMOTE {} ", remote.status())),
+                            _ => None,
+                        };
+                        if let Some(status) = status {
+                            let status = Line::raw(status)
+                                .style(theme::palette().banner())
+                                .right_aligned();
+                            f.render_widget(
+                                status,
+                                Rect {
+                                    height: 1,
+                                    ..f.area()
+                                },
+                            );
+                        }
+
                         Ok(())
                     })();
 
@@ -183,7 +601,12 @@ impl ResTop {
                 event_enum = RedrawEventEnum::empty();
             }
 
-            match self.res_rx.recv_timeout(Duration::from_millis(200)) {
+            if alert::take_bell() {
+                let mut out = stdout();
+                let _ = out.write_all(b"\x07").and_then(|_| out.flush());
+            }
+
+            match self.res_rx.recv_timeout(next_fetch) {
                 Ok(rsp) => match rsp {
                     ResourceEvent::Resize(w, h) => {
                         self.layout.update_layout(Rect {
@@ -196,17 +619,24 @@ impl ResTop {
                     }
                     ResourceEvent::KeyEvent(key) => {
                         self.handle_key(&key);
+                        if self.rewind_replay() {
+                            frozen_rsps.queues.clear();
+                        }
+                        if !ring::is_frozen() {
+                            // catch up with what was sampled while frozen
+                            for (arrived, sampled_at, rsp) in frozen_rsps.drain() {
+                                ring::sampled_at(arrived, || self.update_data(&rsp, sampled_at));
+                            }
+                        }
                         event_enum = event_enum.union(RedrawEventEnum::TERM);
                     }
                     ResourceEvent::SensorRsp(rsp) => {
-                        for ele in &mut self.resources {
-                            if ele.updata_data(&rsp) {
-                                break;
-                            }
+                        if self.receive(&mut frozen_rsps, Instant::now(), rsp) {
+                            event_enum = event_enum.union(RedrawEventEnum::SENSOR);
                         }
-                        event_enum = event_enum.union(RedrawEventEnum::SENSOR);
                     }
                     ResourceEvent::Quit => {
+                        self.finish_recording()?;
                         break;
                     }
                     ResourceEvent::FocusedIndex(focused_index) => {
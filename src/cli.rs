@@ -0,0 +1,188 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Context};
+use chin_tools::AResult;
+
+use crate::{remote::Target, sensor::settings::RefreshSpeed};
+
+const USAGE: &str = "\
+Usage: restop [OPTIONS]
+       restop agent [--listen <ADDR>] [--allow-actions] [OPTIONS]
+
+Commands:
+  agent         Stream the readings of this machine to a restop started with
+                --connect or --ssh, on stdio or with --listen over TCP. A bare port
+                listens on 127.0.0.1 only, e.g. 9185. Over TCP both sides need the
+                same secret in $RESTOP_TOKEN. Only with --allow-actions the client
+                may signal and renice processes, --ssh passes it
+
+Options:
+      --json    Print every sensor reading as a JSON line instead of starting the TUI
+      --csv     Print every sensor reading as CSV rows instead of starting the TUI
+      --once    Print one round of readings and exit, implies --json without --csv
+      --serve-metrics <ADDR>
+                Serve the readings in the OpenMetrics format at http://ADDR/metrics
+                instead of starting the TUI, e.g. 127.0.0.1:9184
+  -r, --refresh-speed <SPEED>
+                Sample every resource at this speed for this session: very-slow (3s),
+                slow (2s), normal (1s), fast (0.5s) or very-fast (0.25s)
+      --root <DIR>
+                Read /proc and /sys below DIR, e.g. a mounted container or chroot
+      --record <FILE>
+                Save every sample to FILE (gzip compressed JSON lines)
+      --replay <FILE>
+                Play a recording back in the TUI instead of reading this machine,
+                GPUs are not recorded. ? lists the playback keys
+      --connect <ADDR>
+                Show the machine of the agent at host:port instead of this one, or
+                read-only of the agent piped into stdin with `-`, e.g.
+                `ssh -n box restop agent | restop --connect -`. GPUs are not sent
+      --ssh <DEST>
+                Show the machine of `ssh DEST restop agent` instead of this one
+  -h, --help    Print this help
+
+Press ? in the TUI for the key bindings, they are set in the `keys` section
+of the config.
+";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Json,
+    Csv,
+}
+
+/// The command line options of restop.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// Runs without the TUI if set.
+    pub output: Option<OutputFormat>,
+    pub once: bool,
+    /// Runs without the TUI if set.
+    pub serve_metrics: Option<String>,
+    pub refresh_speed: Option<RefreshSpeed>,
+    pub root: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    /// Runs without the TUI if set.
+    pub agent: bool,
+    pub listen: Option<String>,
+    pub allow_actions: bool,
+    pub connect: Option<Target>,
+}
+
+impl Args {
+    pub fn parse() -> AResult<Self> {
+        Self::parse_from(std::env::args().skip(1))
+    }
+
+    pub fn parse_from(args: impl Iterator<Item = String>) -> AResult<Self> {
+        let mut parsed = Self::default();
+
+        let mut args = args.peekable();
+        if args.next_if(|arg| arg == "agent").is_some() {
+            parsed.agent = true;
+        }
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--json" => parsed.output = Some(OutputFormat::Json),
+                "--csv" => parsed.output = Some(OutputFormat::Csv),
+                "--once" => parsed.once = true,
+                "--serve-metrics" => {
+                    let value = args.next().context("missing value for --serve-metrics")?;
+                    parsed.serve_metrics = Some(value);
+                }
+                "-r" | "--refresh-speed" => {
+                    let value = args.next().context("missing value for --refresh-speed")?;
+                    let speed = value
+                        .replace(['-', '_'], "")
+                        .parse()
+                        .ok()
+                        .with_context(|| {
+                            format!("invalid refresh speed: {}\n\n{}", value, USAGE)
+                        })?;
+                    parsed.refresh_speed = Some(speed);
+                }
+                "--root" => {
+                    let value = args.next().context("missing value for --root")?;
+                    parsed.root = Some(value.into());
+                }
+                "--record" => {
+                    let value = args.next().context("missing value for --record")?;
+                    parsed.record = Some(value.into());
+                }
+                "--replay" => {
+                    let value = args.next().context("missing value for --replay")?;
+                    parsed.replay = Some(value.into());
+                }
+                "--listen" if parsed.agent => {
+                    let value = args.next().context("missing value for --listen")?;
+                    // only this machine unless a host is given
+                    parsed.listen = Some(match value.parse::<u16>() {
+                        Ok(port) => format!("127.0.0.1:{}", port),
+                        Err(_) => value,
+                    });
+                }
+                "--allow-actions" if parsed.agent => parsed.allow_actions = true,
+                "--connect" => {
+                    let value = args.next().context("missing value for --connect")?;
+                    parsed.connect = Some(Target::parse_connect(&value));
+                }
+                "--ssh" => {
+                    let value = args.next().context("missing value for --ssh")?;
+                    parsed.connect = Some(Target::Ssh(value));
+                }
+                "-h" | "--help" => {
+                    print!("{}", USAGE);
+                    std::process::exit(0);
+                }
+                _ => bail!("unknown argument: {}\n\n{}", arg, USAGE),
+            }
+        }
+
+        if parsed.serve_metrics.is_some() && (parsed.output.is_some() || parsed.once) {
+            bail!(
+                "--serve-metrics cannot be used with --json, --csv or --once\n\n{}",
+                USAGE
+            );
+        }
+        if parsed.replay.is_some() {
+            if parsed.record.is_some() {
+                bail!("--replay cannot be used with --record\n\n{}", USAGE);
+            }
+            if parsed.output.is_some() || parsed.serve_metrics.is_some() {
+                bail!("--replay only works in the TUI\n\n{}", USAGE);
+            }
+        }
+
+        if parsed.agent
+            && (parsed.output.is_some()
+                || parsed.once
+                || parsed.serve_metrics.is_some()
+                || parsed.replay.is_some()
+                || parsed.connect.is_some())
+        {
+            bail!(
+                "agent cannot be used with --json, --csv, --once, --serve-metrics, --replay, --connect or --ssh\n\n{}",
+                USAGE
+            );
+        }
+        if parsed.connect.is_some() {
+            if parsed.replay.is_some() {
+                bail!(
+                    "--replay cannot be used with --connect or --ssh\n\n{}",
+                    USAGE
+                );
+            }
+            if parsed.output.is_some() || parsed.serve_metrics.is_some() {
+                bail!("--connect and --ssh only work in the TUI\n\n{}", USAGE);
+            }
+        }
+
+        if parsed.once && parsed.output.is_none() {
+            parsed.output = Some(OutputFormat::Json);
+        }
+
+        Ok(parsed)
+    }
+}
@@ -21,6 +21,8 @@ pub struct GroupedLines<'a> {
     theme: SharedTheme,
     focused: bool,
     active: bool,
+    /// Drawn in the alert color, an alert fires for the resource.
+    alert: bool,
 }
 
 impl<'a> GroupedLines<'a> {
@@ -37,6 +39,7 @@ impl<'a> GroupedLines<'a> {
             theme: theme.clone(),
             focused: false,
             active: false,
+            alert: false,
         }
     }
 
@@ -47,6 +50,10 @@ impl<'a> GroupedLines<'a> {
         Self { active, ..self }
     }
 
+    pub fn alert(self, alert: bool) -> Self {
+        Self { alert, ..self }
+    }
+
     pub fn start(self, start: Option<u16>) -> Self {
         Self { start, ..self }
     }
@@ -84,7 +91,12 @@ impl<'a> Widget for GroupedLines<'a> {
         let start = self.start.unwrap_or(0);
         let end = self.end.unwrap_or(u16::MAX);
 
-        let fg = self.theme.fg();
+        let palette = self.theme.palette();
+        let border = if self.alert {
+            palette.alert()
+        } else {
+            palette.border(self.focused || self.active)
+        };
 
         let offset = start;
 
@@ -131,7 +143,7 @@ impl<'a> Widget for GroupedLines<'a> {
                 }
                 s.push(tr.into());
 
-                Line::from(s).render(
+                Line::from(s).style(border).render(
                     Rect {
                         x: area.x,
                         y,
@@ -148,7 +160,7 @@ impl<'a> Widget for GroupedLines<'a> {
                 }
                 s.push_str(br);
 
-                Line::styled(s, fg).render(
+                Line::styled(s, border).render(
                     Rect {
                         x: area.x,
                         y,
@@ -160,7 +172,7 @@ impl<'a> Widget for GroupedLines<'a> {
 
                 break;
             } else {
-                Span::styled(ver, fg).render(
+                Span::styled(ver, border).render(
                     Rect {
                         x: area.x,
                         y,
@@ -181,7 +193,7 @@ impl<'a> Widget for GroupedLines<'a> {
                     buf,
                 );
 
-                Span::styled(ver, fg).render(
+                Span::styled(ver, border).render(
                     Rect {
                         x: area.right().saturating_sub(1),
                         y,
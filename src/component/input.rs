@@ -35,6 +35,16 @@ impl Input {
         }
     }
 
+    /// Starts with `input` already filled in, the cursor placed at its end.
+    pub fn with_input(input: &str) -> Input {
+        Input {
+            input: input.to_string(),
+            cursor_position: input.len(),
+            show_start: 0,
+            input_move: InputMove::Right,
+        }
+    }
+
     pub fn get_input(&self) -> String {
         self.input.to_string()
     }
@@ -6,7 +6,11 @@ use ratatui::{
     text::{Line, Span},
 };
 
-use crate::ring::Ring;
+use crate::{
+    ring::Ring,
+    sensor::settings::{SidebarMeterType, SETTINGS},
+    view::theme,
+};
 use chin_tools::utils::string_util::split_by_len;
 use ratatui::style::Style;
 
@@ -70,29 +74,16 @@ pub fn s_percent_graph(
     let percent = (value * 100. / total) as u16;
     let graph_width = width.saturating_sub(3);
 
-    let mut colors = [
-        Color::Green,
-        Color::Cyan,
-        Color::Blue,
-        Color::Yellow,
-        Color::Magenta,
-        Color::Red,
-    ];
-
-    if high_is_good {
-        colors.reverse();
-    }
-
-    let value_width = graph_width as usize * percent as usize / 100;
-    let color = percent / (100 / colors.len() as u16);
-
-    let color = if color <= 0 {
-        colors[0]
-    } else if color as usize >= colors.len() {
-        *colors.last().unwrap()
+    let palette = theme::palette();
+    let fill = if high_is_good {
+        100u16.saturating_sub(percent)
     } else {
-        colors[color as usize]
+        percent
     };
+    let color = palette.gradient(fill as f64);
+    let legend_color = palette.usage(fill as f64);
+
+    let value_width = graph_width as usize * percent as usize / 100;
 
     let legent_width = if percent < 10 {
         1
@@ -120,7 +111,7 @@ pub fn s_percent_graph(
     vec![
         Span::raw("["),
         Span::styled(graph, Style::new().fg(color)),
-        Span::raw(format!("{}%", percent)),
+        Span::raw(format!("{}%", percent)).fg(legend_color),
         Span::raw("]"),
     ]
 }
@@ -228,6 +219,79 @@ pub fn ls_history_graph<'r>(
         .collect()
 }
 
+/// A history graph of values stacked on each other, one sample per column.
+/// `layers` go from the bottom to the top.
+pub fn ls_stacked_graph(
+    width: u16,
+    layers: &[(&Ring<f64>, Color)],
+    max_value: f64,
+    line_height: u16,
+) -> Vec<Line<'static>> {
+    const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let values: Vec<Vec<f64>> = layers
+        .iter()
+        .map(|(ring, _)| ring.new_to_old_iter().take(width as usize).copied().collect())
+        .collect();
+    let cell = max_value / line_height.max(1) as f64;
+
+    (0..line_height)
+        .rev()
+        .map(|row| {
+            let low = row as f64 * cell;
+            let high = low + cell;
+            let mut spans: Vec<Span<'static>> = vec![];
+            for column in (0..width as usize).rev() {
+                // the part of every layer within this cell
+                let mut bottom = 0.;
+                let mut top = 0.;
+                let mut color = None;
+                let mut most = 0.;
+                for (layer, (_, layer_color)) in values.iter().zip(layers.iter()) {
+                    let value = layer.get(column).copied().unwrap_or(0.).max(0.);
+                    top = bottom + value;
+                    let share = top.min(high) - bottom.max(low);
+                    if share > most {
+                        most = share;
+                        color = Some(*layer_color);
+                    }
+                    bottom = top;
+                }
+
+                let eighths = (((top - low) / cell).clamp(0., 1.) * 8.).round() as usize;
+                let symbol = BLOCKS[eighths];
+                let color = color.unwrap_or(Color::Reset);
+                match spans.last_mut() {
+                    Some(last) if last.style.fg == Some(color) || symbol == ' ' => {
+                        last.content.to_mut().push(symbol)
+                    }
+                    _ => spans.push(Span::raw(symbol.to_string()).fg(color)),
+                }
+            }
+            Line::from(spans)
+        })
+        .collect()
+}
+
+/// The meter of a sidebar block, a history graph or a progress bar of the
+/// newest value depending on the settings.
+pub fn ls_sidebar_meter(
+    width: u16,
+    ring: &Ring<f64>,
+    max_value: f64,
+    min_value: f64,
+    color: Color,
+) -> Vec<Line<'static>> {
+    match SETTINGS.sidebar_meter_type() {
+        SidebarMeterType::Graph => ls_history_graph(width, ring, max_value, min_value, 3, color),
+        SidebarMeterType::ProgressBar => {
+            let value = ring.newest().map_or(0., |e| *e - min_value).max(0.);
+            let total = (max_value - min_value).max(f64::EPSILON);
+            vec![s_percent_graph(value.min(total), total, width.saturating_sub(2), false).into()]
+        }
+    }
+}
+
 pub fn s_label(label: &str, style: Style) -> Span<'static> {
     Span::styled(String::from(label), style)
 }
@@ -288,7 +352,7 @@ pub fn render_border(
     let right = area.right().saturating_sub(1);
     let bot = area.bottom().saturating_sub(1);
     let left = area.left();
-    let style = Style::new();
+    let style = theme::palette().border(focused);
 
     buf.set_string(left, top, tl, style);
     buf.set_string(right, top, tr, style);
@@ -24,6 +24,10 @@ impl LinesVerticalState {
         self.show_start.saturating_add(self.view_height.into())
     }
 
+    pub fn cur_line(&self) -> usize {
+        self.cur_line
+    }
+
     pub fn focus_next(&mut self) {
         self.cur_line = self.cur_line.saturating_add(1).clamp(0, self.end);
         if self.cur_line >= self.show_end().saturating_sub(3) {
@@ -212,6 +216,16 @@ impl<'a> StatefulGroupedLines<'a> {
         }
     }
 
+    /// Focuses the block `index` and scrolls it into view.
+    pub fn focus_index(&mut self, index: usize) {
+        if let Some((range, _)) = self.blocks.get(index) {
+            self.cur_line = range.start;
+            if range.end > self.show_end() {
+                self.show_start = range.end.saturating_sub(self.view_height.into());
+            }
+        }
+    }
+
     pub fn update_blocks(&mut self, blocks: Vec<GroupedLines<'a>>) {
         let mut visited: usize = 0;
         let mut new_blocks = vec![];
@@ -0,0 +1,94 @@
+use std::io::Write;
+
+use chin_tools::AResult;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{cli::OutputFormat, resource::SensorRsp};
+
+/// One line of the `--json` output.
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Unix timestamp in milliseconds.
+    timestamp: i64,
+    id: &'a str,
+    #[serde(flatten)]
+    rsp: &'a SensorRsp,
+}
+
+pub fn write_header(out: &mut impl Write, format: OutputFormat) -> AResult<()> {
+    if format == OutputFormat::Csv {
+        writeln!(out, "timestamp,resource,id,key,value")?;
+    }
+    Ok(())
+}
+
+/// Writes `rsp` as a single JSON line, or in CSV as one `key,value` row for
+/// every leaf of the reading, nested keys are joined with dots.
+pub fn write_record(out: &mut impl Write, format: OutputFormat, rsp: &SensorRsp) -> AResult<()> {
+    let record = Record {
+        timestamp: chrono::Local::now().timestamp_millis(),
+        id: rsp.get_id(),
+        rsp,
+    };
+
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer(&mut *out, &record)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let value = serde_json::to_value(&record)?;
+            let resource = value["resource"].as_str().unwrap_or_default();
+            let mut leaves = vec![];
+            flatten("", &value["data"], &mut leaves);
+            for (key, leaf) in leaves {
+                writeln!(
+                    out,
+                    "{},{},{},{},{}",
+                    record.timestamp,
+                    csv_escape(resource),
+                    csv_escape(record.id),
+                    csv_escape(&key),
+                    csv_escape(&leaf)
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn flatten(prefix: &str, value: &Value, leaves: &mut Vec<(String, String)>) {
+    let join = |key: &str| {
+        if prefix.is_empty() {
+            key.to_string()
+        } else {
+            format!("{prefix}.{key}")
+        }
+    };
+
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten(&join(key), value, leaves);
+            }
+        }
+        Value::Array(values) => {
+            for (idx, value) in values.iter().enumerate() {
+                flatten(&join(&idx.to_string()), value, leaves);
+            }
+        }
+        Value::Null => leaves.push((prefix.to_string(), String::new())),
+        Value::String(s) => leaves.push((prefix.to_string(), s.clone())),
+        _ => leaves.push((prefix.to_string(), value.to_string())),
+    }
+}
+
+fn csv_escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,5 +1,11 @@
+pub mod alert;
 pub mod app;
+pub mod cli;
 pub mod component;
+pub mod headless;
+pub mod metrics;
+pub mod record;
+pub mod remote;
 pub mod resource;
 pub mod ring;
 pub mod sensor;
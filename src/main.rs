@@ -3,7 +3,11 @@ use crossterm::{
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use restop::app::ResTop;
+use restop::{
+    app::ResTop,
+    cli::Args,
+    sensor::{self, settings::SETTINGS},
+};
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(debug_assertions)]
@@ -13,6 +17,47 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg(debug_assertions)]
     tracing_subscriber::fmt().with_writer(non_blocking).init();
 
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    if let Some(speed) = args.refresh_speed {
+        SETTINGS.override_refresh_speed(speed);
+    }
+    if let Some(root) = args.root.as_ref() {
+        if !root.is_dir() {
+            eprintln!("{} is not a directory", root.display());
+            std::process::exit(2);
+        }
+        sensor::set_root(root);
+    }
+
+    let mut res_top = match (args.replay.as_ref(), args.connect.as_ref()) {
+        (Some(path), _) => ResTop::replay(path)?,
+        (_, Some(target)) => ResTop::connect(target)?,
+        _ => ResTop::new()?,
+    };
+    if let Some(path) = args.record.as_ref() {
+        res_top.record(path)?;
+    }
+
+    if args.agent {
+        res_top.serve_agent(args.listen.as_deref(), args.allow_actions)?;
+        return Ok(());
+    }
+
+    if let Some(addr) = args.serve_metrics.as_ref() {
+        res_top.serve_metrics(addr)?;
+        return Ok(());
+    }
+    if let Some(format) = args.output {
+        res_top.run_headless(format, args.once)?;
+        return Ok(());
+    }
+
     let backend = CrosstermBackend::new(std::io::stdout());
     let mut term = Terminal::new(backend)?;
 
@@ -23,8 +68,6 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     )?;
     enable_raw_mode()?;
 
-    let mut res_top = ResTop::new()?;
-
     match res_top.run(&mut term) {
         Ok(_) => {}
         Err(err) => {
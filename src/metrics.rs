@@ -0,0 +1,651 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use chin_tools::AResult;
+
+use crate::{
+    resource::{process::ProcessRsp, SensorRsp},
+    sensor::cpu::{CpuData, CpuTimes, CPU_MODES},
+};
+
+const CONTENT_TYPE: &str = "application/openmetrics-text; version=1.0.0; charset=utf-8";
+/// How long a scrape may take, a slow client only holds up its own thread.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+/// The most bytes read of a request, a scrape is a few hundred.
+const MAX_REQUEST: u64 = 16 * 1024;
+
+/// Only the processes using the most CPU are exported, every process would
+/// be a new series in the scraper.
+const TOP_PROCESSES: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Gauge,
+    /// Its samples get the `_total` suffix.
+    Counter,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Family {
+    name: &'static str,
+    kind: Kind,
+    unit: Option<&'static str>,
+    help: &'static str,
+}
+
+impl Family {
+    const fn gauge(name: &'static str, unit: Option<&'static str>, help: &'static str) -> Self {
+        Self {
+            name,
+            kind: Kind::Gauge,
+            unit,
+            help,
+        }
+    }
+
+    const fn counter(name: &'static str, unit: Option<&'static str>, help: &'static str) -> Self {
+        Self {
+            name,
+            kind: Kind::Counter,
+            unit,
+            help,
+        }
+    }
+}
+
+static CPU_USAGE: Family = Family::gauge(
+    "restop_cpu_usage_ratio",
+    Some("ratio"),
+    "Busy time of a logical CPU since the previous sample, cpu=\"total\" for all of them.",
+);
+
+static CPU_MODE: Family = Family::gauge(
+    "restop_cpu_mode_ratio",
+    Some("ratio"),
+    "Share of the time of a logical CPU spent in a mode since the previous sample.",
+);
+
+static CPU_FREQUENCY: Family = Family::gauge(
+    "restop_cpu_frequency_hertz",
+    Some("hertz"),
+    "Current frequency of a logical CPU.",
+);
+
+static CPU_TEMPERATURE: Family = Family::gauge(
+    "restop_cpu_temperature_celsius",
+    Some("celsius"),
+    "Temperature of the CPU package.",
+);
+
+static CPU_POWER: Family = Family::gauge(
+    "restop_cpu_power_watts",
+    Some("watts"),
+    "Power drawn by a RAPL domain since the previous sample.",
+);
+
+static CPU_ENERGY: Family = Family::counter(
+    "restop_cpu_energy_joules",
+    Some("joules"),
+    "Energy used by a RAPL domain, wraps around at the range of the hardware counter.",
+);
+
+static LOAD_AVERAGE: Family = Family::gauge(
+    "restop_load_average",
+    None,
+    "Load average over the last minutes.",
+);
+
+static UPTIME: Family = Family::gauge("restop_uptime_seconds", Some("seconds"), "Time since boot.");
+
+static MEMORY_TOTAL: Family =
+    Family::gauge("restop_memory_total_bytes", Some("bytes"), "Usable memory.");
+
+static MEMORY_AVAILABLE: Family = Family::gauge(
+    "restop_memory_available_bytes",
+    Some("bytes"),
+    "Memory available for new allocations.",
+);
+
+static SWAP_TOTAL: Family = Family::gauge(
+    "restop_swap_total_bytes",
+    Some("bytes"),
+    "Size of all swap spaces.",
+);
+
+static SWAP_FREE: Family = Family::gauge(
+    "restop_swap_free_bytes",
+    Some("bytes"),
+    "Unused swap space.",
+);
+
+static DRIVE_READ: Family = Family::counter(
+    "restop_drive_read_bytes",
+    Some("bytes"),
+    "Bytes read from a drive, use rate() for the throughput.",
+);
+
+static DRIVE_WRITTEN: Family = Family::counter(
+    "restop_drive_written_bytes",
+    Some("bytes"),
+    "Bytes written to a drive, use rate() for the throughput.",
+);
+
+static DRIVE_CAPACITY: Family = Family::gauge(
+    "restop_drive_capacity_bytes",
+    Some("bytes"),
+    "Capacity of a drive.",
+);
+
+static NETWORK_RECEIVED: Family = Family::counter(
+    "restop_network_received_bytes",
+    Some("bytes"),
+    "Bytes received by an interface.",
+);
+
+static NETWORK_SENT: Family = Family::counter(
+    "restop_network_sent_bytes",
+    Some("bytes"),
+    "Bytes sent by an interface.",
+);
+
+static BATTERY_CHARGE: Family = Family::gauge(
+    "restop_battery_charge_ratio",
+    Some("ratio"),
+    "Charge of a battery.",
+);
+
+static BATTERY_POWER: Family = Family::gauge(
+    "restop_battery_power_watts",
+    Some("watts"),
+    "Power drawn from or fed into a battery.",
+);
+
+static BATTERY_HEALTH: Family = Family::gauge(
+    "restop_battery_health_ratio",
+    Some("ratio"),
+    "Full capacity of a battery compared to its design capacity.",
+);
+
+static GPU_USAGE: Family =
+    Family::gauge("restop_gpu_usage_ratio", Some("ratio"), "Usage of a GPU.");
+
+static GPU_VRAM_USED: Family = Family::gauge(
+    "restop_gpu_vram_used_bytes",
+    Some("bytes"),
+    "Used video memory of a GPU.",
+);
+
+static GPU_VRAM_TOTAL: Family = Family::gauge(
+    "restop_gpu_vram_total_bytes",
+    Some("bytes"),
+    "Video memory of a GPU.",
+);
+
+static GPU_POWER: Family = Family::gauge(
+    "restop_gpu_power_watts",
+    Some("watts"),
+    "Power usage of a GPU.",
+);
+
+static GPU_TEMPERATURE: Family = Family::gauge(
+    "restop_gpu_temperature_celsius",
+    Some("celsius"),
+    "Temperature of a GPU.",
+);
+
+static PROCESS_CPU: Family = Family::gauge(
+    "restop_process_cpu_usage_ratio",
+    Some("ratio"),
+    "CPU usage of the busiest processes, relative to all CPUs.",
+);
+
+static PROCESS_MEMORY: Family = Family::gauge(
+    "restop_process_memory_bytes",
+    Some("bytes"),
+    "Memory usage of the busiest processes.",
+);
+
+/// The order of the families in the output.
+static FAMILIES: &[&Family] = &[
+    &CPU_USAGE,
+    &CPU_MODE,
+    &CPU_FREQUENCY,
+    &CPU_TEMPERATURE,
+    &CPU_POWER,
+    &CPU_ENERGY,
+    &LOAD_AVERAGE,
+    &UPTIME,
+    &MEMORY_TOTAL,
+    &MEMORY_AVAILABLE,
+    &SWAP_TOTAL,
+    &SWAP_FREE,
+    &DRIVE_READ,
+    &DRIVE_WRITTEN,
+    &DRIVE_CAPACITY,
+    &NETWORK_RECEIVED,
+    &NETWORK_SENT,
+    &BATTERY_CHARGE,
+    &BATTERY_POWER,
+    &BATTERY_HEALTH,
+    &GPU_USAGE,
+    &GPU_VRAM_USED,
+    &GPU_VRAM_TOTAL,
+    &GPU_POWER,
+    &GPU_TEMPERATURE,
+    &PROCESS_CPU,
+    &PROCESS_MEMORY,
+];
+
+#[derive(Debug)]
+struct Sample {
+    family: &'static Family,
+    labels: Vec<(&'static str, String)>,
+    value: f64,
+}
+
+impl Sample {
+    fn new(family: &'static Family, value: impl Into<f64>) -> Self {
+        Self {
+            family,
+            labels: vec![],
+            value: value.into(),
+        }
+    }
+
+    fn label(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.labels.push((key, value.to_string()));
+        self
+    }
+}
+
+#[derive(Default)]
+struct State {
+    /// The samples of the latest reading of every resource.
+    samples: BTreeMap<String, Vec<Sample>>,
+    /// The CPU usage is the difference to the previous reading.
+    last_cpu: Option<CpuData>,
+}
+
+impl State {
+    fn update(&mut self, rsp: &SensorRsp) {
+        let key = match rsp {
+            SensorRsp::Process(ProcessRsp::LoadAvg(_)) => "loadavg",
+            SensorRsp::Process(ProcessRsp::Uptime(_)) => "uptime",
+            _ => rsp.get_id(),
+        };
+        let samples = match rsp {
+            SensorRsp::CPU(data) => {
+                let samples = cpu_samples(data, self.last_cpu.as_ref());
+                self.last_cpu.replace(data.clone());
+                samples
+            }
+            SensorRsp::Memory(data) => vec![
+                Sample::new(&MEMORY_TOTAL, data.total_mem as f64),
+                Sample::new(&MEMORY_AVAILABLE, data.available_mem as f64),
+                Sample::new(&SWAP_TOTAL, data.total_swap as f64),
+                Sample::new(&SWAP_FREE, data.free_swap as f64),
+            ],
+            SensorRsp::GPU(Ok(data)) => [
+                (&GPU_USAGE, data.usage_fraction),
+                (&GPU_VRAM_USED, data.used_vram as f64),
+                (&GPU_VRAM_TOTAL, data.total_vram as f64),
+                (&GPU_POWER, data.power_usage),
+                (&GPU_TEMPERATURE, data.temp),
+            ]
+            .into_iter()
+            .map(|(family, value)| Sample::new(family, value).label("gpu", &data.id))
+            .collect(),
+            SensorRsp::GPU(Err(_)) => return,
+            SensorRsp::Drive(rsp) => {
+                let data = &rsp.data;
+                let sectors = |key: &str| data.disk_stats.get(key).map(|e| (*e * 512) as f64);
+                [
+                    (&DRIVE_READ, sectors("read_sectors")),
+                    (&DRIVE_WRITTEN, sectors("write_sectors")),
+                    (
+                        &DRIVE_CAPACITY,
+                        data.capacity.as_ref().ok().map(|e| *e as f64),
+                    ),
+                ]
+                .into_iter()
+                .filter_map(|(family, value)| {
+                    Some(Sample::new(family, value?).label("device", &data.inner.block_device))
+                })
+                .collect()
+            }
+            SensorRsp::Network(data) => {
+                let interface = Path::new(&data.sysfs_path)
+                    .file_name()
+                    .map_or(data.sysfs_path.clone(), |e| e.to_string_lossy().to_string());
+                [
+                    (&NETWORK_RECEIVED, &data.received_bytes),
+                    (&NETWORK_SENT, &data.sent_bytes),
+                ]
+                .into_iter()
+                .filter_map(|(family, value)| {
+                    let value = *value.as_ref().ok()? as f64;
+                    Some(Sample::new(family, value).label("interface", &interface))
+                })
+                .collect()
+            }
+            SensorRsp::Battery(data) => [
+                (&BATTERY_CHARGE, &data.charge),
+                (&BATTERY_POWER, &data.power_usage),
+                (&BATTERY_HEALTH, &data.health),
+            ]
+            .into_iter()
+            .filter_map(|(family, value)| {
+                let value = *value.as_ref().ok()?;
+                Some(Sample::new(family, value).label("battery", &data.inner.supply_name))
+            })
+            .collect(),
+            SensorRsp::Process(ProcessRsp::Processes(rows)) => {
+                let mut items: Vec<_> = rows
+                    .iter()
+                    .filter(|e| e.app.is_none())
+                    .map(|e| &e.item)
+                    .collect();
+                items.sort_by(|a, b| b.cpu_time_ratio.total_cmp(&a.cpu_time_ratio));
+                items
+                    .into_iter()
+                    .take(TOP_PROCESSES)
+                    .flat_map(|item| {
+                        [
+                            Sample::new(&PROCESS_CPU, exact(item.cpu_time_ratio)),
+                            Sample::new(&PROCESS_MEMORY, item.memory_usage as f64),
+                        ]
+                        .map(|e| {
+                            e.label("pid", item.pid)
+                                .label("name", &item.display_name)
+                                .label("user", &item.user)
+                        })
+                    })
+                    .collect()
+            }
+            SensorRsp::Process(ProcessRsp::LoadAvg(load)) => {
+                [("1m", load.last1), ("5m", load.last5), ("15m", load.last15)]
+                    .into_iter()
+                    .map(|(period, value)| {
+                        Sample::new(&LOAD_AVERAGE, exact(value)).label("period", period)
+                    })
+                    .collect()
+            }
+            SensorRsp::Process(ProcessRsp::Uptime(uptime)) => {
+                vec![Sample::new(&UPTIME, *uptime as f64)]
+            }
+            SensorRsp::Process(_) => return,
+        };
+        self.samples.insert(key.to_string(), samples);
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        for family in FAMILIES {
+            let mut samples = self
+                .samples
+                .values()
+                .flatten()
+                .filter(|e| e.family == *family)
+                .peekable();
+            if samples.peek().is_none() {
+                continue;
+            }
+
+            let kind = match family.kind {
+                Kind::Gauge => "gauge",
+                Kind::Counter => "counter",
+            };
+            let _ = writeln!(out, "# TYPE {} {}", family.name, kind);
+            if let Some(unit) = family.unit {
+                let _ = writeln!(out, "# UNIT {} {}", family.name, unit);
+            }
+            let _ = writeln!(out, "# HELP {} {}", family.name, escape(family.help));
+
+            for sample in samples {
+                out.push_str(family.name);
+                if family.kind == Kind::Counter {
+                    out.push_str("_total");
+                }
+                if !sample.labels.is_empty() {
+                    let labels: Vec<String> = sample
+                        .labels
+                        .iter()
+                        .map(|(key, value)| format!("{}=\"{}\"", key, escape(value)))
+                        .collect();
+                    let _ = write!(out, "{{{}}}", labels.join(","));
+                }
+                let _ = writeln!(out, " {}", format_value(sample.value));
+            }
+        }
+        out.push_str("# EOF\n");
+        out
+    }
+}
+
+fn cpu_samples(data: &CpuData, last: Option<&CpuData>) -> Vec<Sample> {
+    fn usage(
+        samples: &mut Vec<Sample>,
+        cpu: impl ToString + Clone,
+        new: &CpuTimes,
+        old: &CpuTimes,
+    ) {
+        let delta = new.since(old);
+        let total = delta.total();
+        if total == 0 {
+            return;
+        }
+        samples.push(
+            Sample::new(&CPU_USAGE, delta.busy() as f64 / total as f64).label("cpu", cpu.clone()),
+        );
+        for (mode, ticks) in CPU_MODES.iter().zip(delta.modes()) {
+            samples.push(
+                Sample::new(&CPU_MODE, ticks as f64 / total as f64)
+                    .label("cpu", cpu.clone())
+                    .label("mode", mode),
+            );
+        }
+    }
+
+    let mut samples = vec![];
+    if let Some(last) = last {
+        usage(
+            &mut samples,
+            "total",
+            &data.new_total_usage,
+            &last.new_total_usage,
+        );
+        for (idx, (new, old)) in data
+            .new_thread_usages
+            .iter()
+            .zip(last.new_thread_usages.iter())
+            .enumerate()
+        {
+            usage(&mut samples, idx, new, old);
+        }
+    }
+    for (idx, freq) in data.frequencies.iter().enumerate() {
+        if let Some(freq) = freq {
+            samples.push(Sample::new(&CPU_FREQUENCY, *freq as f64).label("cpu", idx));
+        }
+    }
+    if let Some(temperature) = data.temperature {
+        samples.push(Sample::new(&CPU_TEMPERATURE, exact(temperature)));
+    }
+    let time_passed = last.map(|last| {
+        data.sampled_at
+            .saturating_duration_since(last.sampled_at)
+            .as_secs_f64()
+    });
+    for energy in data.energies.iter() {
+        let sample = |family: &'static Family, value: f64| {
+            let sample = Sample::new(family, value).label("domain", &energy.domain);
+            match energy.package {
+                Some(package) => sample.label("package", package),
+                None => sample,
+            }
+        };
+        samples.push(sample(&CPU_ENERGY, energy.energy as f64 / 1_000_000.));
+        let watts = last
+            .and_then(|last| {
+                last.energies
+                    .iter()
+                    .find(|e| e.domain == energy.domain && e.package == energy.package)
+            })
+            .and_then(|old| energy.since(old))
+            .zip(time_passed.filter(|e| *e > 0.))
+            .map(|(uj, secs)| uj as f64 / 1_000_000. / secs);
+        if let Some(watts) = watts {
+            samples.push(sample(&CPU_POWER, watts));
+        }
+    }
+    samples
+}
+
+/// The shortest decimal form of `value`, `0.52f32 as f64` would be written
+/// as 0.5199999809265137.
+fn exact(value: f32) -> f64 {
+    value.to_string().parse().unwrap_or(f64::NAN)
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0. { "+Inf" } else { "-Inf" }.to_string()
+    } else {
+        value.to_string()
+    }
+}
+
+/// Serves the latest readings at `/metrics` over plain HTTP.
+pub struct Exporter {
+    state: Arc<Mutex<State>>,
+    local_addr: SocketAddr,
+}
+
+impl Exporter {
+    pub fn bind(addr: &str) -> AResult<Self> {
+        let listener =
+            TcpListener::bind(addr).with_context(|| format!("unable to listen on {}", addr))?;
+        let local_addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let server_state = state.clone();
+        thread::Builder::new()
+            .name("metrics".to_owned())
+            .spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let state = server_state.clone();
+                    let spawned = thread::Builder::new()
+                        .name("metricsrequest".to_owned())
+                        .spawn(move || {
+                            if let Err(err) = handle(stream, &state) {
+                                tracing::warn!("unable to answer a metrics request: {}", err);
+                            }
+                        });
+                    if let Err(err) = spawned {
+                        tracing::warn!("unable to answer a metrics request: {}", err);
+                    }
+                }
+            })?;
+
+        Ok(Self { state, local_addr })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    pub fn update(&self, rsp: &SensorRsp) {
+        self.state.lock().unwrap().update(rsp);
+    }
+}
+
+fn handle(mut stream: TcpStream, state: &Mutex<State>) -> AResult<()> {
+    let deadline = Instant::now() + REQUEST_TIMEOUT;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+    stream.set_write_timeout(Some(REQUEST_TIMEOUT))?;
+
+    let mut reader = BufReader::new(&stream).take(MAX_REQUEST);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // the headers are not needed, but have to be read before answering
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        if Instant::now() > deadline {
+            bail!("the request took longer than {:?}", REQUEST_TIMEOUT);
+        }
+        header.clear();
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+
+    let (status, content_type, body) = match (method, path) {
+        ("GET", "/metrics") | ("GET", "/") => {
+            ("200 OK", CONTENT_TYPE, state.lock().unwrap().render())
+        }
+        ("GET", _) => ("404 Not Found", "text/plain", "not found\n".to_string()),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain",
+            "method not allowed\n".to_string(),
+        ),
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        content_type,
+        body.len(),
+        body
+    )?;
+    stream.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{resource::SensorRsp, sensor::memory::MemoryData};
+
+    use super::State;
+
+    #[test]
+    fn test_render() {
+        let mut state = State::default();
+        state.update(&SensorRsp::Memory(MemoryData {
+            total_mem: 16 << 30,
+            available_mem: 8 << 30,
+            total_swap: 0,
+            free_swap: 0,
+        }));
+
+        let text = state.render();
+        assert!(text.starts_with("# TYPE restop_memory_total_bytes gauge\n"));
+        assert!(text.contains("\nrestop_memory_total_bytes 17179869184\n"));
+        assert!(text.contains("\nrestop_swap_free_bytes 0\n"));
+        assert!(text.ends_with("# EOF\n"));
+    }
+}
@@ -0,0 +1,370 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, ErrorKind, Write},
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use chin_tools::AResult;
+use crossterm::event::KeyEvent;
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    resource::{
+        battery::ResBattery, cpu::ResCPU, drive::ResDrive, memory::ResMEM, network::ResNetwork,
+        process::ResProcess, ResourceType, SensorRsp,
+    },
+    sensor::{
+        battery::Battery, cpu::CpuInfo, drive::Drive, memory::MemoryDevice,
+        network::NetworkInterface, units::convert_seconds,
+    },
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme::SharedTheme,
+    },
+};
+
+const RECORD_VERSION: u32 = 2;
+
+/// Unsynced samples are flushed to the file at most this late, so a capture
+/// survives a crash of restop.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(5);
+
+const SPEEDS: [f32; 7] = [0.25, 0.5, 1., 2., 4., 8., 16.];
+
+/// What is needed to rebuild a resource on another machine. GPUs are left
+/// out, their pages query the device directly.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum RecordedResource {
+    CPU(Box<CpuInfo>),
+    Memory(Vec<MemoryDevice>),
+    Drive(Drive, Option<u64>),
+    Network(NetworkInterface),
+    Battery(Battery),
+    Process,
+}
+
+impl RecordedResource {
+    pub(crate) fn of(resource: &ResourceType) -> Option<Self> {
+        match resource {
+            ResourceType::CPU(rt) => Some(Self::CPU(Box::new(rt.info().clone()))),
+            ResourceType::Memory(rt) => Some(Self::Memory(rt.info().to_vec())),
+            ResourceType::GPU(_) => None,
+            ResourceType::Drive(rt) => Some(Self::Drive(rt.info().clone(), rt.capacity())),
+            ResourceType::Network(rt) => Some(Self::Network(rt.info().clone())),
+            ResourceType::Battery(rt) => Some(Self::Battery(rt.info().clone())),
+            ResourceType::Process(_) => Some(Self::Process),
+        }
+    }
+
+    pub(crate) fn build(&self, theme: &SharedTheme) -> AResult<ResourceType> {
+        Ok(match self {
+            Self::CPU(info) => {
+                ResourceType::CPU(ResCPU::from_info(theme.clone(), info.as_ref().clone())?)
+            }
+            Self::Memory(info) => {
+                ResourceType::Memory(ResMEM::from_info(theme.clone(), info.clone()))
+            }
+            Self::Drive(info, capacity) => {
+                ResourceType::Drive(ResDrive::from_info(theme.clone(), info.clone(), *capacity))
+            }
+            Self::Network(info) => {
+                ResourceType::Network(ResNetwork::from_info(theme.clone(), info.clone()))
+            }
+            Self::Battery(info) => {
+                ResourceType::Battery(ResBattery::from_info(theme.clone(), info.clone()))
+            }
+            Self::Process => ResourceType::Process(ResProcess::new(theme.clone())),
+        })
+    }
+}
+
+/// The first line of a recording.
+#[derive(Serialize, Deserialize)]
+struct Header {
+    version: u32,
+    /// Unix timestamp in milliseconds.
+    started: i64,
+    resources: Vec<RecordedResource>,
+}
+
+/// A sample with the time it was taken, also what an agent sends.
+#[derive(Serialize)]
+pub(crate) struct FrameRef<'a> {
+    /// Milliseconds since the start of the recording.
+    pub t: u64,
+    pub rsp: &'a SensorRsp,
+}
+
+#[derive(Deserialize)]
+pub(crate) struct Frame {
+    pub t: u64,
+    pub rsp: SensorRsp,
+}
+
+#[derive(Deserialize)]
+struct FrameTime {
+    t: u64,
+}
+
+/// Writes a gzip compressed JSON lines file: the header, then one line for
+/// every sample.
+pub struct Recorder {
+    out: GzEncoder<BufWriter<File>>,
+    started: Instant,
+    last_flush: Instant,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, resources: &[ResourceType]) -> AResult<Self> {
+        let file =
+            File::create(path).with_context(|| format!("unable to create {}", path.display()))?;
+        let mut out = GzEncoder::new(BufWriter::new(file), Compression::default());
+
+        let header = Header {
+            version: RECORD_VERSION,
+            started: chrono::Local::now().timestamp_millis(),
+            resources: resources.iter().filter_map(RecordedResource::of).collect(),
+        };
+        serde_json::to_writer(&mut out, &header)?;
+        writeln!(out)?;
+
+        Ok(Self {
+            out,
+            started: Instant::now(),
+            last_flush: Instant::now(),
+        })
+    }
+
+    /// Only the samples are kept, answers to user actions are skipped.
+    pub fn write(&mut self, rsp: &SensorRsp) -> AResult<()> {
+        if !rsp.is_sample() {
+            return Ok(());
+        }
+
+        let frame = FrameRef {
+            t: self.started.elapsed().as_millis() as u64,
+            rsp,
+        };
+        serde_json::to_writer(&mut self.out, &frame)?;
+        writeln!(self.out)?;
+
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL {
+            self.out.flush()?;
+            self.last_flush = Instant::now();
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> AResult<()> {
+        self.out.finish()?.flush()?;
+        Ok(())
+    }
+}
+
+/// Plays a recording back on a virtual clock that can be paused, sped up and
+/// moved around.
+pub struct Replay {
+    resources: Vec<RecordedResource>,
+    /// The time of every frame and its still unparsed line.
+    frames: Vec<(Duration, String)>,
+    next: usize,
+    position: Duration,
+    playing: bool,
+    speed: usize,
+    last_tick: Instant,
+    /// Replayed samples are stamped as if they were taken at `origin + t`,
+    /// which keeps the rates right at any speed.
+    origin: Instant,
+    rewound: bool,
+}
+
+impl Replay {
+    pub fn open(path: &Path) -> AResult<Self> {
+        let file =
+            File::open(path).with_context(|| format!("unable to open {}", path.display()))?;
+        let mut lines = BufReader::new(GzDecoder::new(file)).lines();
+
+        let header: Header = match lines.next() {
+            Some(line) => serde_json::from_str(&line?).context("not a restop recording")?,
+            None => bail!("{} is empty", path.display()),
+        };
+        if header.version != RECORD_VERSION {
+            bail!("unsupported recording version {}", header.version);
+        }
+
+        let mut frames = vec![];
+        for line in lines {
+            let line = match line {
+                Ok(line) => line,
+                // the recording was cut off, e.g. restop was killed
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+                Err(err) => Err(err)?,
+            };
+            match serde_json::from_str::<FrameTime>(&line) {
+                Ok(frame) => frames.push((Duration::from_millis(frame.t), line)),
+                Err(err) => {
+                    tracing::warn!("skipping a broken frame: {}", err);
+                }
+            }
+        }
+
+        Ok(Self {
+            resources: header.resources,
+            frames,
+            next: 0,
+            position: Duration::ZERO,
+            playing: true,
+            speed: 2,
+            last_tick: Instant::now(),
+            origin: Instant::now(),
+            rewound: false,
+        })
+    }
+
+    /// Fresh resources as they were on the recorded machine.
+    pub fn build_resources(&self, theme: &SharedTheme) -> AResult<Vec<ResourceType>> {
+        self.resources.iter().map(|e| e.build(theme)).collect()
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.frames.last().map_or(Duration::ZERO, |(t, _)| *t)
+    }
+
+    /// Advances the clock and returns the samples that are due with the time
+    /// they are stamped with.
+    pub fn due(&mut self) -> Vec<(Instant, SensorRsp)> {
+        if self.playing {
+            let elapsed = self.last_tick.elapsed().mul_f32(SPEEDS[self.speed]);
+            self.position = (self.position + elapsed).min(self.duration());
+        }
+        self.last_tick = Instant::now();
+
+        let mut rsps = vec![];
+        while let Some((t, line)) = self.frames.get(self.next) {
+            if *t > self.position {
+                break;
+            }
+            self.next += 1;
+            match serde_json::from_str::<Frame>(line) {
+                Ok(mut frame) => {
+                    let sampled_at = self.origin + Duration::from_millis(frame.t);
+                    stamp(&mut frame.rsp, sampled_at);
+                    rsps.push((sampled_at, frame.rsp));
+                }
+                Err(err) => {
+                    tracing::warn!("skipping a broken frame: {}", err);
+                }
+            }
+        }
+        rsps
+    }
+
+    /// True once after seeking backwards, the resources have to be rebuilt
+    /// before the frames are played again from the start.
+    pub fn take_rewound(&mut self) -> bool {
+        std::mem::take(&mut self.rewound)
+    }
+
+    fn seek(&mut self, delta_secs: i64) {
+        let delta = Duration::from_secs(delta_secs.unsigned_abs());
+        if delta_secs < 0 {
+            self.position = self.position.saturating_sub(delta);
+            self.next = 0;
+            self.rewound = true;
+        } else {
+            self.position = (self.position + delta).min(self.duration());
+        }
+    }
+
+    /// Returns false if the key is not a replay control.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        match keymap::lookup(key, KeyContext::Replay) {
+            Some(Action::PlayPause) => self.playing = !self.playing,
+            Some(Action::SeekForward) => self.seek(10),
+            Some(Action::SeekBack) => self.seek(-10),
+            Some(Action::SeekForwardFar) => self.seek(60),
+            Some(Action::SeekBackFar) => self.seek(-60),
+            Some(Action::ReplayFaster) => self.speed = (self.speed + 1).min(SPEEDS.len() - 1),
+            Some(Action::ReplaySlower) => self.speed = self.speed.saturating_sub(1),
+            _ => return false,
+        }
+        true
+    }
+
+    /// E.g. `▶ 00:01:20 / 00:10:00 ×2`.
+    pub fn status(&self) -> String {
+        format!(
+            "{} {} / {} ×{}",
+            if self.playing { "▶" } else { "⏸" },
+            convert_seconds(self.position.as_secs()),
+            convert_seconds(self.duration().as_secs()),
+            SPEEDS[self.speed]
+        )
+    }
+}
+
+/// Restores the sample time, it is not serialized.
+pub(crate) fn stamp(rsp: &mut SensorRsp, sampled_at: Instant) {
+    match rsp {
+        SensorRsp::Drive(rsp) => rsp.data.sampled_at = sampled_at,
+        SensorRsp::Network(data) => data.sampled_at = sampled_at,
+        SensorRsp::CPU(data) => data.sampled_at = sampled_at,
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, thread};
+
+    use crate::sensor::{
+        fixture,
+        network::{NetworkData, NetworkInterface},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let paths = NetworkInterface::get_sysfs_paths(&fixture("laptop")).unwrap();
+        let interface = NetworkInterface::from_sysfs(&paths[0]);
+        let path = std::env::temp_dir().join(format!("restop-{}.jsonl.gz", std::process::id()));
+
+        let mut recorder = Recorder::create(&path, &[]).unwrap();
+        for _ in 0..3 {
+            recorder
+                .write(&SensorRsp::Network(NetworkData::new(&interface)))
+                .unwrap();
+            thread::sleep(Duration::from_millis(20));
+        }
+        recorder.finish().unwrap();
+
+        let mut replay = Replay::open(&path).unwrap();
+        assert_eq!(replay.frames.len(), 3);
+        assert!(replay.frames[1].0 >= replay.frames[0].0 + Duration::from_millis(20));
+        assert!(replay.frames[2].0 >= replay.frames[1].0 + Duration::from_millis(20));
+
+        replay.seek(60);
+        let due = replay.due();
+        assert_eq!(due.len(), 3);
+        for ((sampled_at, rsp), (t, _)) in due.iter().zip(&replay.frames) {
+            assert_eq!(*sampled_at, replay.origin + *t);
+            let SensorRsp::Network(data) = rsp else {
+                panic!("not the recorded sample");
+            };
+            assert_eq!(data.sampled_at, *sampled_at);
+            assert_eq!(*data.received_bytes.as_ref().unwrap(), 123456789);
+        }
+        assert!(replay.due().is_empty());
+
+        // cut off the gzip trailer as if restop was killed
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+        let replay = Replay::open(&path);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(replay.unwrap().frames.len(), 3);
+    }
+}
@@ -0,0 +1,416 @@
+//! The connection between `restop agent` and a TUI attached to it.
+//!
+//! Both sides write JSON lines. The agent starts with a `Hello` describing its
+//! resources and then sends every `SensorRsp` in a frame with the time it was
+//! taken, the client sends back the messages for the process worker, e.g.
+//! filters or signals.
+//!
+//! Over TCP the client first sends an `Auth` with the token both sides read
+//! from `$RESTOP_TOKEN`. Signals and priorities are only carried out by an
+//! agent started with `--allow-actions`.
+
+use std::{
+    io::{stdin, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    process::{Child, Command, Stdio},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use chin_tools::AResult;
+use flume::Sender;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::ResourceEvent,
+    record::{stamp, Frame, FrameRef, RecordedResource},
+    resource::{
+        process::{self, ActionFailure, ProcessMsg, ProcessRsp, ProcessView},
+        ResourceType, SensorRsp,
+    },
+    sensor::{root, rooted},
+    view::theme::SharedTheme,
+};
+
+const PROTOCOL_VERSION: u32 = 4;
+
+/// How long a TCP client may take to authenticate, in total.
+const AUTH_TIMEOUT: Duration = Duration::from_secs(5);
+/// The longest `Auth` line read, a token is far shorter.
+const MAX_AUTH: u64 = 4096;
+
+/// The first line sent by a TCP client.
+#[derive(Serialize, Deserialize)]
+struct Auth {
+    token: String,
+}
+
+/// The first line sent by the agent.
+#[derive(Serialize, Deserialize)]
+struct Hello {
+    version: u32,
+    hostname: String,
+    resources: Vec<RecordedResource>,
+}
+
+/// Where the agent runs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// `host:port` of `restop agent --listen`.
+    Tcp(String),
+    /// Runs `restop agent` on this ssh destination.
+    Ssh(String),
+    /// The output of an agent piped into stdin, e.g. `ssh -n box restop agent
+    /// | restop --connect -`. Nothing can be sent back.
+    Stdin,
+}
+
+impl Target {
+    /// `-` reads stdin, everything else is a TCP address.
+    pub fn parse_connect(value: &str) -> Self {
+        match value {
+            "-" => Target::Stdin,
+            addr => Target::Tcp(addr.to_string()),
+        }
+    }
+}
+
+/// The client side of a connection to an agent.
+pub struct Remote {
+    hostname: String,
+    resources: Vec<RecordedResource>,
+    reader: Option<Box<dyn BufRead + Send>>,
+    /// `None` if the connection only goes one way.
+    writer: Option<Box<dyn Write + Send>>,
+    connected: Arc<AtomicBool>,
+    ssh: Option<Child>,
+}
+
+impl Remote {
+    /// Opens the connection and waits for the agent to introduce itself.
+    pub fn connect(target: &Target) -> AResult<Self> {
+        let mut ssh = None;
+        let (mut reader, writer): (Box<dyn BufRead + Send>, Option<Box<dyn Write + Send>>) =
+            match target {
+                Target::Tcp(addr) => {
+                    let token = token().context("set RESTOP_TOKEN to the token of the agent")?;
+                    let mut stream = TcpStream::connect(addr)
+                        .with_context(|| format!("unable to connect to {}", addr))?;
+                    stream.set_nodelay(true)?;
+                    write_line(&mut stream, &Auth { token })?;
+                    (
+                        Box::new(BufReader::new(stream.try_clone()?)),
+                        Some(Box::new(stream)),
+                    )
+                }
+                Target::Ssh(dest) => {
+                    let mut child = Command::new("ssh")
+                        // ssh already checked who is connecting
+                        .args(["-T", dest, "restop", "agent", "--allow-actions"])
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .spawn()
+                        .context("unable to run ssh")?;
+                    let reader = BufReader::new(child.stdout.take().context("no ssh stdout")?);
+                    let writer = child.stdin.take().context("no ssh stdin")?;
+                    ssh.replace(child);
+                    (Box::new(reader), Some(Box::new(writer)))
+                }
+                Target::Stdin => (Box::new(BufReader::new(stdin())), None),
+            };
+
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            match target {
+                Target::Tcp(_) => bail!("the agent closed the connection, check RESTOP_TOKEN"),
+                _ => bail!("the agent closed the connection"),
+            }
+        }
+        let hello: Hello = serde_json::from_str(&line).context("not a restop agent")?;
+        if hello.version != PROTOCOL_VERSION {
+            bail!(
+                "the agent speaks version {}, expected {}",
+                hello.version,
+                PROTOCOL_VERSION
+            );
+        }
+
+        Ok(Self {
+            hostname: hello.hostname,
+            resources: hello.resources,
+            reader: Some(reader),
+            writer,
+            connected: Arc::new(AtomicBool::new(true)),
+            ssh,
+        })
+    }
+
+    /// Fresh resources as they are on the machine of the agent.
+    pub fn build_resources(&self, theme: &SharedTheme) -> AResult<Vec<ResourceType>> {
+        self.resources.iter().map(|e| e.build(theme)).collect()
+    }
+
+    /// Passes the readings of the agent to `tx` and the messages for the
+    /// process worker to the agent.
+    pub fn start(&mut self, tx: &Sender<ResourceEvent>) -> AResult<()> {
+        let Some(reader) = self.reader.take() else {
+            return Ok(());
+        };
+
+        let rsp_tx = tx.clone();
+        let connected = self.connected.clone();
+        thread::Builder::new()
+            .name("remote".to_owned())
+            .spawn(move || {
+                // the clock of the agent started at this local time
+                let mut origin: Option<Instant> = None;
+                for line in reader.lines() {
+                    let Ok(line) = line else {
+                        break;
+                    };
+                    match serde_json::from_str::<Frame>(&line) {
+                        Ok(Frame { t, mut rsp }) => {
+                            let t = Duration::from_millis(t);
+                            let origin = *origin.get_or_insert_with(|| {
+                                Instant::now().checked_sub(t).unwrap_or_else(Instant::now)
+                            });
+                            stamp(&mut rsp, origin + t);
+                            if rsp_tx.send(ResourceEvent::SensorRsp(rsp)).is_err() {
+                                break;
+                            }
+                        }
+                        Err(err) => {
+                            tracing::warn!("skipping a broken message: {}", err);
+                        }
+                    }
+                }
+                connected.store(false, Ordering::Relaxed);
+            })?;
+
+        let mut writer = self.writer.take();
+        let rsp_tx = tx.clone();
+        thread::Builder::new()
+            .name("remoterequests".to_owned())
+            .spawn(move || {
+                for msg in process::worker_requests().iter() {
+                    match writer.as_mut() {
+                        Some(out) => {
+                            if let Err(err) = write_line(out, &msg) {
+                                tracing::error!("unable to reach the agent: {}", err);
+                                break;
+                            }
+                        }
+                        None => {
+                            if let Some(rsp) =
+                                refuse(msg, "the connection to the agent is read-only")
+                            {
+                                let _ = rsp_tx.send(ResourceEvent::SensorRsp(rsp));
+                            }
+                        }
+                    }
+                }
+            })?;
+
+        Ok(())
+    }
+
+    /// E.g. `box` or `box (disconnected)`.
+    pub fn status(&self) -> String {
+        if self.connected.load(Ordering::Relaxed) {
+            self.hostname.clone()
+        } else {
+            format!("{} (disconnected)", self.hostname)
+        }
+    }
+}
+
+impl Drop for Remote {
+    fn drop(&mut self) {
+        if let Some(mut child) = self.ssh.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+/// The answer to an action which can not be carried out, e.g. over a one way
+/// connection, so that it fails right away instead of waiting.
+fn refuse(msg: ProcessMsg, reason: &str) -> Option<SensorRsp> {
+    let failure = ActionFailure {
+        message: reason.to_string(),
+        permission_denied: false,
+    };
+    let rsp = match msg {
+        ProcessMsg::Signal { target, action, .. } => {
+            ProcessRsp::Action(target, action, Err(failure))
+        }
+        ProcessMsg::Tune { pid, .. } => ProcessRsp::Tune(pid, Err(failure)),
+        _ => return None,
+    };
+    Some(SensorRsp::Process(rsp))
+}
+
+/// The shared secret of the agent and its TCP clients.
+pub fn token() -> Option<String> {
+    std::env::var("RESTOP_TOKEN").ok().filter(|e| !e.is_empty())
+}
+
+/// Waits for the `Auth` of a TCP client, false if it sent a wrong token or
+/// nothing in time.
+pub fn authenticate(stream: &TcpStream, reader: &mut impl BufRead, token: &str) -> AResult<bool> {
+    let deadline = Instant::now() + AUTH_TIMEOUT;
+    let mut reader = reader.take(MAX_AUTH);
+    let mut line = vec![];
+    let complete = loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            break false;
+        }
+        // every read only waits for what is left of the deadline
+        stream.set_read_timeout(Some(left))?;
+        let Ok(buf) = reader.fill_buf() else {
+            break false;
+        };
+        if buf.is_empty() {
+            break false;
+        }
+        // the bytes after the line already belong to the session
+        match buf.iter().position(|e| *e == b'\n') {
+            Some(end) => {
+                line.extend_from_slice(&buf[..end]);
+                reader.consume(end + 1);
+                break true;
+            }
+            None => {
+                let len = buf.len();
+                line.extend_from_slice(buf);
+                reader.consume(len);
+            }
+        }
+    };
+    stream.set_read_timeout(None)?;
+
+    let sent = complete
+        .then(|| serde_json::from_slice::<Auth>(&line).ok())
+        .flatten();
+    Ok(sent.is_some_and(|auth| same_token(&auth.token, token)))
+}
+
+/// Takes as long for every wrong token of the same length.
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn write_line<T: Serialize>(out: &mut impl Write, value: &T) -> AResult<()> {
+    serde_json::to_writer(&mut *out, value)?;
+    writeln!(out)?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Introduces the agent to a new client, GPUs are left out.
+pub fn write_hello(out: &mut impl Write, resources: &[ResourceType]) -> AResult<()> {
+    let hostname = std::fs::read_to_string(rooted(&root(), "/proc/sys/kernel/hostname"))
+        .map(|e| e.trim().to_string())
+        .unwrap_or_default();
+    let hello = Hello {
+        version: PROTOCOL_VERSION,
+        hostname,
+        resources: resources.iter().filter_map(RecordedResource::of).collect(),
+    };
+    write_line(out, &hello)
+}
+
+/// Sends `rsp` with the time since `started`, the rates on the client are
+/// computed from it rather than from when the line arrived.
+pub fn write_rsp(out: &mut impl Write, started: Instant, rsp: &SensorRsp) -> AResult<()> {
+    let frame = FrameRef {
+        t: started.elapsed().as_millis() as u64,
+        rsp,
+    };
+    write_line(out, &frame)
+}
+
+/// Puts the process worker back to its defaults for a new client.
+pub fn reset_worker() {
+    process::send_to_worker(ProcessMsg::Filter(None));
+    process::send_to_worker(ProcessMsg::Sort(None));
+    process::send_to_worker(ProcessMsg::ToggleView(ProcessView::Flat));
+    process::send_to_worker(ProcessMsg::Detail(None));
+    process::send_to_worker(ProcessMsg::Events(false));
+}
+
+/// Hands the messages of the client to the process worker. The requests may
+/// end early, e.g. with `ssh -n`, the client is only gone once the readings
+/// can not be written anymore. Without `allow_actions` signals and
+/// priorities are refused through `tx`.
+pub fn read_requests(
+    reader: impl BufRead + Send + 'static,
+    allow_actions: bool,
+    tx: Sender<ResourceEvent>,
+) -> AResult<()> {
+    thread::Builder::new()
+        .name("agentrequests".to_owned())
+        .spawn(move || {
+            for line in reader.lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                match serde_json::from_str::<ProcessMsg>(&line) {
+                    // sampling is up to the agent
+                    Ok(ProcessMsg::Detect) => {}
+                    Ok(msg @ (ProcessMsg::Signal { .. } | ProcessMsg::Tune { .. }))
+                        if !allow_actions =>
+                    {
+                        if let Some(rsp) = refuse(msg, "the agent does not allow actions") {
+                            let _ = tx.send(ResourceEvent::SensorRsp(rsp));
+                        }
+                    }
+                    Ok(msg) => process::send_to_worker(msg),
+                    Err(err) => {
+                        tracing::warn!("skipping a broken request: {}", err);
+                    }
+                }
+            }
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::resource::{
+        process::{ProcessMsg, ProcessRsp},
+        SensorRsp,
+    };
+
+    use super::{refuse, same_token};
+
+    #[test]
+    fn test_refuse() {
+        let msg: ProcessMsg = serde_json::from_str(
+            r#"{"Signal":{"target":{"Process":42},"action":"KILL","elevated":false}}"#,
+        )
+        .unwrap();
+        let Some(SensorRsp::Process(ProcessRsp::Action(_, _, Err(failure)))) =
+            refuse(msg, "read-only")
+        else {
+            panic!("the signal was not refused");
+        };
+        assert!(!failure.permission_denied);
+
+        assert!(refuse(ProcessMsg::Events(true), "read-only").is_none());
+
+        assert!(same_token("secret", "secret"));
+        assert!(!same_token("secret", "secreT"));
+        assert!(!same_token("secret", "secret2"));
+    }
+}
@@ -1,16 +1,18 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
 use chin_tools::AResult;
 use ratatui::layout::Rect;
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
         s_percent_graph,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
     },
     sensor::{
-        battery::{Battery, BatteryData},
+        battery::{Battery, BatteryData, State},
+        root,
         units::convert_energy,
         Sensor,
     },
@@ -32,20 +34,28 @@ pub struct ResBattery {
 
 impl ResBattery {
     pub fn new(theme: SharedTheme) -> AResult<Vec<Self>> {
-        let paths = Battery::get_sysfs_paths()?;
+        let paths = Battery::get_sysfs_paths(&root())?;
         let bs = paths
             .into_iter()
-            .map(|path| ResBattery {
-                info: Battery::from_sysfs(&path),
-                data: None,
-                theme: theme.clone(),
-                path: Arc::new(path),
-                viewer_state: Default::default(),
-            })
+            .map(|path| Self::from_info(theme.clone(), Battery::from_sysfs(&path)))
             .collect();
 
         Ok(bs)
     }
+
+    pub fn from_info(theme: SharedTheme, info: Battery) -> Self {
+        ResBattery {
+            path: Arc::new(info.sysfs_path.clone()),
+            info,
+            data: None,
+            theme,
+            viewer_state: Default::default(),
+        }
+    }
+
+    pub fn info(&self) -> &Battery {
+        &self.info
+    }
 }
 
 impl Resource for ResBattery {
@@ -151,6 +161,26 @@ impl Resource for ResBattery {
         self.data.replace(data.clone());
     }
 
+    fn alert_readings(&self) -> Option<Readings> {
+        let data = self.data.as_ref()?;
+        let state = data.state.as_ref().ok();
+        Some(
+            Readings::new("battery", Some(self.get_name()))
+                .percent("charge", data.charge.as_ref().ok().map(|e| e * 100.))
+                .percent("health", data.health.as_ref().ok().map(|e| e * 100.))
+                .value("power", data.power_usage.as_ref().ok().copied())
+                .flag("charging", state == Some(&State::Charging))
+                .flag("discharging", state == Some(&State::Discharging))
+                .flag("full", state == Some(&State::Full))
+                .flag("empty", state == Some(&State::Empty)),
+        )
+    }
+
+    /// Charge levels change slowly, no need to follow the refresh speed.
+    fn refresh_interval(&self) -> Option<Duration> {
+        Some(Duration::from_secs(10))
+    }
+
     fn cached_page_state<'b>(&'b mut self) -> StatefulLinesType<'static, 'b> {
         StatefulLinesType::Groups(&mut self.viewer_state)
     }
@@ -1,23 +1,43 @@
-use std::cell::{Cell, RefCell};
+use std::{
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    path::Path,
+    time::Instant,
+};
 
-use anyhow::Context;
+use anyhow::{anyhow, Context};
 use chin_tools::AResult;
-use ratatui::text::{Line, Span};
+use ratatui::{
+    style::{Color, Stylize},
+    text::{Line, Span},
+};
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
-        ls_history_graph, s_history_graph,
+        ls_sidebar_meter, ls_stacked_graph, s_history_graph,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
-        PaddingH,
     },
     ring::Ring,
     sensor::{
-        cpu::{cpu_info, CpuData, CpuInfo},
-        units::{convert_frequency, convert_temperature},
+        cpu::{
+            cpu_info,
+            freq::{set_policy, PolicyKnob},
+            power::has_energy_counters,
+            topology::format_cpu_list,
+            CpuData, CpuInfo, CpuPolicy, CpuTemperature, CpuTimes, EnergyCounter, CPU_MODES,
+        },
+        root,
+        settings::SETTINGS,
+        units::{convert_frequency, convert_power, convert_storage, convert_temperature},
     },
     tarits::{NaNDefault, None2NaN, None2NaNDef},
-    view::{theme::SharedTheme, OverviewArg, PageArg},
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme::SharedTheme,
+        NavigatorEvent, OverviewArg, PageArg,
+    },
 };
 
 use super::{Resource, SensorResultType, SensorRsp};
@@ -26,8 +46,8 @@ use super::{Resource, SensorResultType, SensorRsp};
 pub struct ResCPU {
     info: CpuInfo,
 
-    old_total_usage: Cell<(u64, u64)>,
-    old_thread_usages: RefCell<Vec<(u64, u64)>>,
+    old_total_usage: Cell<CpuTimes>,
+    old_thread_usages: RefCell<Vec<CpuTimes>>,
     logical_cpus_amount: Cell<usize>,
 
     // Show
@@ -35,15 +55,47 @@ pub struct ResCPU {
 
     total_history: Ring<f64>,
     thread_history: Vec<Ring<f64>>,
+    /// The share of every category of `CPU_MODES`, in total and per thread.
+    total_modes: Vec<Ring<f64>>,
+    thread_modes: Vec<Vec<Ring<f64>>>,
 
     frequences: Option<Vec<Option<u64>>>,
     tempurature: Option<f32>,
+    /// Every input of the package, cores and CCDs with its history.
+    temperatures: Vec<CpuTemperature>,
+    temperature_history: Vec<Ring<f64>>,
+    /// The newest RAPL counters and the watts of every domain.
+    energies: Vec<EnergyCounter>,
+    energy_sampled_at: Option<Instant>,
+    power_history: Vec<Ring<f64>>,
+    policies: Vec<CpuPolicy>,
+    boost: Option<bool>,
+    /// Only the CPU of this machine is tuned, not a remote or recorded one.
+    local: bool,
+    /// The outcome of the last switch of the governor or the preference.
+    tuning: Option<AResult<String>>,
     viewer_state: StatefulGroupedLines<'static>,
 }
 
 impl ResCPU {
     pub fn new(theme: SharedTheme) -> AResult<Self> {
-        let cpu_info = cpu_info()?;
+        let mut cpu = Self::from_info(theme, cpu_info(&root())?)?;
+        cpu.local = true;
+        cpu.tuning = Self::foreign_root().err().map(Err);
+        Ok(cpu)
+    }
+
+    /// The files below `--root` are a snapshot or belong to the host of a
+    /// container, like the processes they are not changed.
+    fn foreign_root() -> AResult<()> {
+        let root = root();
+        if root == Path::new("/") {
+            return Ok(());
+        }
+        Err(anyhow!("the CPU below {} can not be tuned", root.display()))
+    }
+
+    pub fn from_info(theme: SharedTheme, cpu_info: CpuInfo) -> AResult<Self> {
         let logic_size = cpu_info
             .logical_cpus
             .context("Unable to get logical core size.")?;
@@ -56,11 +108,346 @@ impl ResCPU {
             total_history: Ring::new(1000).name("CCPU"),
             theme,
             tempurature: None,
+            temperatures: vec![],
+            temperature_history: vec![],
+            energies: vec![],
+            energy_sampled_at: None,
+            power_history: vec![],
             thread_history: vec![],
+            total_modes: CPU_MODES.iter().map(|_| Ring::new(1000)).collect(),
+            thread_modes: vec![],
             frequences: None,
+            policies: vec![],
+            boost: None,
+            local: false,
+            tuning: None,
             viewer_state: Default::default(),
         })
     }
+
+    pub fn info(&self) -> &CpuInfo {
+        &self.info
+    }
+
+    /// Switches every CPU to the value after the current one.
+    fn cycle_policy(&mut self, knob: PolicyKnob) {
+        let (values, current) = match knob {
+            PolicyKnob::Governor => (
+                &self.info.freq.governors,
+                self.policies.first().and_then(|e| e.governor.as_ref()),
+            ),
+            PolicyKnob::Epp => (
+                &self.info.freq.epps,
+                self.policies.first().and_then(|e| e.epp.as_ref()),
+            ),
+        };
+        let result = if !self.local {
+            Err(anyhow!("only the CPU of this machine can be tuned"))
+        } else if let Err(err) = Self::foreign_root() {
+            Err(err)
+        } else if !SETTINGS.cpu_tuning() {
+            Err(anyhow!("the CPU governor keys are off in the settings"))
+        } else if values.is_empty() {
+            Err(anyhow!("the driver offers nothing to choose"))
+        } else {
+            let idx = current
+                .and_then(|current| values.iter().position(|e| e == current))
+                .map_or(0, |idx| (idx + 1) % values.len());
+            let cpus: Vec<usize> = (0..self.logical_cpus_amount.get()).collect();
+            set_policy(&root(), &cpus, knob, &values[idx]).map(|_| values[idx].clone())
+        };
+        self.tuning = Some(result);
+    }
+
+    /// The distinct values of every CPU, counted if they differ.
+    fn summarize<'a>(values: impl Iterator<Item = Option<&'a String>>) -> String {
+        let mut counts: Vec<(&String, usize)> = vec![];
+        for value in values.flatten() {
+            match counts.iter_mut().find(|(e, _)| *e == value) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((value, 1)),
+            }
+        }
+        match counts.len() {
+            0 => "N/A".to_string(),
+            1 => counts[0].0.clone(),
+            _ => counts
+                .iter()
+                .map(|(value, count)| format!("{} ({})", value, count))
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+
+    /// The throttle counters of every core and package once, siblings
+    /// report the same ones.
+    fn throttles(&self) -> (Option<u64>, Option<u64>) {
+        let counter = |id: usize, core: bool| {
+            self.policies.get(id).and_then(|e| {
+                if core {
+                    e.core_throttles
+                } else {
+                    e.package_throttles
+                }
+            })
+        };
+        let sum = |counts: Vec<Option<u64>>| counts.into_iter().flatten().reduce(|a, b| a + b);
+        let firsts = |cores: &BTreeMap<usize, Vec<usize>>| {
+            cores
+                .values()
+                .filter_map(|threads| threads.first().copied())
+                .collect::<Vec<_>>()
+        };
+        let groups = self.info.topology.groups();
+        let cores = groups
+            .values()
+            .flat_map(firsts)
+            .map(|id| counter(id, true))
+            .collect();
+        let packages = groups
+            .values()
+            .filter_map(|cores| firsts(cores).first().copied())
+            .map(|id| counter(id, false))
+            .collect();
+        (sum(cores), sum(packages))
+    }
+
+    fn frequency_block(&self, width: u16, active: bool) -> AResult<GroupedLines<'static>> {
+        let freq = &self.info.freq;
+        let range = |min: Option<u64>, max: Option<u64>| match (min, max) {
+            (None, None) => "N/A".to_string(),
+            _ => format!(
+                "{} - {}",
+                min.or_nan(|e| convert_frequency(*e as f64)),
+                max.or_nan(|e| convert_frequency(*e as f64))
+            ),
+        };
+        let (core_throttles, package_throttles) = self.throttles();
+
+        let mut builder =
+            GroupedLines::builder(width, &self.theme).kv("Driver", freq.driver.or_unk_def());
+        if let Some(pstate) = freq.pstate.as_ref() {
+            builder = builder.kv("P-State", pstate.clone());
+        }
+        builder = builder
+            .kv(
+                "Governor",
+                Self::summarize(self.policies.iter().map(|e| e.governor.as_ref())),
+            )
+            .kv(
+                "Energy Preference",
+                Self::summarize(self.policies.iter().map(|e| e.epp.as_ref())),
+            )
+            .kv(
+                "Scaling Range",
+                range(
+                    self.policies.iter().filter_map(|e| e.min).min(),
+                    self.policies.iter().filter_map(|e| e.max).max(),
+                ),
+            )
+            .kv("Hardware Range", range(freq.min, freq.max));
+        if !freq.frequencies.is_empty() {
+            builder = builder.kv(
+                "Available",
+                freq.frequencies
+                    .iter()
+                    .map(|e| convert_frequency(*e as f64))
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+        }
+        builder = builder
+            .kv(
+                "Boost",
+                self.boost
+                    .or_nan(|e| if *e { "On" } else { "Off" }.to_string()),
+            )
+            .kv(
+                "Throttled",
+                match (core_throttles, package_throttles) {
+                    (None, None) => "N/A".to_string(),
+                    _ => format!(
+                        "cores {} times, packages {} times",
+                        core_throttles.or_nan(|e| e.to_string()),
+                        package_throttles.or_nan(|e| e.to_string())
+                    ),
+                },
+            );
+
+        if self.local && !(freq.governors.is_empty() && freq.epps.is_empty()) {
+            let palette = self.theme.palette();
+            let line = match self.tuning.as_ref() {
+                Some(Ok(value)) => {
+                    Line::raw(format!("Switched to {}", value)).style(palette.hint())
+                }
+                Some(Err(err)) => Line::raw(format!("{:#}", err)).style(palette.alert()),
+                None if SETTINGS.cpu_tuning() => Line::raw(format!(
+                    "{} next governor  {} next energy preference",
+                    keymap::keys(Action::Governor),
+                    keymap::keys(Action::EnergyPreference)
+                ))
+                .style(palette.hint()),
+                None => Line::raw("Turn on the CPU governor keys in the settings to switch them")
+                    .style(palette.hint()),
+            };
+            builder = builder.line(line);
+        }
+
+        builder.active(active).build("Frequency")
+    }
+
+    /// The watts of every RAPL domain on the scale of the highest one.
+    fn power_block(&self, width: u16, active: bool) -> AResult<GroupedLines<'static>> {
+        let mut builder = GroupedLines::builder(width, &self.theme);
+        if self.energies.is_empty() {
+            builder = builder.line(
+                Line::raw("The energy counters are only readable by root")
+                    .style(self.theme.palette().hint()),
+            );
+        }
+
+        let peak = self
+            .power_history
+            .iter()
+            .flat_map(|e| e.new_to_old_iter())
+            .fold(1., |peak: f64, e| peak.max(*e));
+        let packages = self
+            .energies
+            .iter()
+            .filter(|e| e.domain == "package")
+            .count();
+        let labels: Vec<String> = self.energies.iter().map(|e| e.label(packages)).collect();
+        let label_width = labels.iter().map(|e| e.chars().count()).max().unwrap_or(0) + 1;
+        for (label, ring) in labels.iter().zip(self.power_history.iter()) {
+            let mut spans = vec![Span::raw(format!("{:<label_width$}", label))];
+            spans.extend(s_history_graph(
+                width.saturating_sub(label_width as u16 + 13),
+                ring,
+                peak,
+                0.,
+                1,
+                self.theme.palette().cpu,
+            ));
+            spans.push(Span::raw(format!(
+                " {:>8}",
+                ring.newest().or_nan(|e| convert_power(**e))
+            )));
+            builder = builder.line(Line::from(spans));
+        }
+
+        builder.active(active).build("Power")
+    }
+
+    /// A stacked graph of `modes` and the newest share of busy time.
+    fn mode_graph(&self, width: u16, modes: &[Ring<f64>], line_height: u16) -> Vec<Line<'static>> {
+        let palette = self.theme.palette();
+        let layers: Vec<_> = modes
+            .iter()
+            .enumerate()
+            .map(|(idx, ring)| (ring, palette.cpu_mode(idx)))
+            .collect();
+        ls_stacked_graph(width, &layers, 100., line_height)
+    }
+
+    /// The newest share of every category, colored like the graph.
+    fn mode_legend(&self, width: u16) -> Vec<Line<'static>> {
+        let palette = self.theme.palette();
+        let mut lines = vec![];
+        let mut spans = vec![];
+        let mut used = 0;
+        for (idx, (name, ring)) in CPU_MODES.iter().zip(self.total_modes.iter()).enumerate() {
+            let text = format!(
+                "{} {}  ",
+                name,
+                ring.newest().or_nan(|e| format!("{:.1}%", e))
+            );
+            let len = text.chars().count();
+            if used > 0 && used + len > width as usize {
+                lines.push(Line::from(std::mem::take(&mut spans)));
+                used = 0;
+            }
+            used += len;
+            spans.push(Span::raw("█ ").fg(palette.cpu_mode(idx)));
+            spans.push(Span::raw(text));
+            used += 2;
+        }
+        if !spans.is_empty() {
+            lines.push(Line::from(spans));
+        }
+        lines
+    }
+
+    /// The graph of a logical CPU with its frequency and usage, and the
+    /// temperature of its core on the line of the first thread.
+    fn thread_line(
+        &self,
+        width: u16,
+        label: String,
+        id: usize,
+        temperature: Option<&CpuTemperature>,
+    ) -> Line<'static> {
+        let mut spans = vec![Span::raw(format!("{}{:02} ", label, id))];
+        let modes = self.thread_modes.get(id).map(Vec::as_slice).unwrap_or(&[]);
+        let reserved = if self.temperatures.iter().any(|e| e.core.is_some()) {
+            26
+        } else {
+            17
+        };
+        spans.extend(
+            self.mode_graph(
+                width.saturating_sub(reserved + label.chars().count() as u16),
+                modes,
+                1,
+            )
+            .into_iter()
+            .flat_map(|line| line.spans),
+        );
+        let freq = match self.frequences.as_ref().map(|e| e.get(id)) {
+            Some(Some(Some(o))) => Some(o),
+            _ => None,
+        };
+        spans.push(Span::raw(format!(
+            " {:>5} ",
+            freq.or_nan(|e| format!("{:.1}G", (**e as f64) / 1e9))
+        )));
+        spans.push(Span::raw(
+            self.thread_history
+                .get(id)
+                .and_then(|ring| ring.newest())
+                .or_nan(|e| format!("{:<4}", format!("{:.0}%", **e))),
+        ));
+        if let Some(temperature) = temperature {
+            spans.push(
+                Span::raw(format!(
+                    " {:>7}",
+                    convert_temperature(temperature.value as f64)
+                ))
+                .fg(self.temperature_color(temperature)),
+            );
+        }
+        Line::from(spans)
+    }
+
+    /// `high` from the critical temperature on, `medium` from the maximum.
+    fn temperature_color(&self, temperature: &CpuTemperature) -> Color {
+        let palette = self.theme.palette();
+        let reached = |limit: Option<f32>| limit.is_some_and(|e| temperature.value >= e);
+        if reached(temperature.crit) {
+            palette.high
+        } else if reached(temperature.max) {
+            palette.medium
+        } else {
+            palette.fg
+        }
+    }
+
+    /// A row of the breakdown table, the newest share of every category.
+    fn mode_row(name: String, modes: &[Ring<f64>]) -> String {
+        modes.iter().fold(format!("{:<5}", name), |mut row, ring| {
+            row.push_str(&ring.newest().or_nan(|e| format!("{:>8.1}", e)));
+            row
+        })
+    }
 }
 
 impl Resource for ResCPU {
@@ -77,59 +464,146 @@ impl Resource for ResCPU {
     }
 
     fn do_sensor(req: Self::Req) -> AResult<SensorResultType> {
-        let data = CpuData::fetch(req)?;
+        let data = CpuData::fetch(&root(), req)?;
         Ok(SensorResultType::SyncResult(SensorRsp::CPU(data)))
     }
 
     fn update_data(&mut self, data: &Self::Rsp) {
-        fn delta_percent(new: &(u64, u64), old: &(u64, u64)) -> f64 {
-            let idle_delta = new.0.saturating_sub(old.0);
-            let sum_delta = new.1.saturating_sub(old.1);
-            let work_time = sum_delta.saturating_sub(idle_delta);
-
-            let fraction = ((work_time as f64) / (sum_delta as f64)).nan_default(0.0);
+        fn delta_percent(delta: &CpuTimes) -> f64 {
+            let fraction = ((delta.busy() as f64) / (delta.total() as f64)).nan_default(0.0);
 
             fraction * 100.
         }
 
+        fn insert_modes(history: &mut [Ring<f64>], delta: &CpuTimes) {
+            let total = delta.total() as f64;
+            for (ring, ticks) in history.iter_mut().zip(delta.modes()) {
+                ring.insert_at_first((ticks as f64 / total).nan_default(0.0) * 100.);
+            }
+        }
+
         let CpuData {
             new_total_usage,
             new_thread_usages,
             temperature,
+            temperatures,
             frequencies,
+            policies,
+            boost,
+            energies,
+            sampled_at,
         } = data;
 
         if self.thread_history.len() != new_thread_usages.len() {
             self.thread_history = new_thread_usages.iter().map(|_| Ring::new(300)).collect();
+            self.thread_modes = new_thread_usages
+                .iter()
+                .map(|_| CPU_MODES.iter().map(|_| Ring::new(300)).collect())
+                .collect();
         }
 
-        let total_percentage = delta_percent(new_total_usage, &self.old_total_usage.get());
+        let total_delta = new_total_usage.since(&self.old_total_usage.get());
         self.old_thread_usages
             .borrow()
             .iter()
             .enumerate()
-            .map(|(index, old)| {
-                (
-                    index,
-                    new_thread_usages
-                        .get(index)
-                        .map(|new| delta_percent(new, old)),
-                )
+            .filter_map(|(index, old)| {
+                new_thread_usages
+                    .get(index)
+                    .map(|new| (index, new.since(old)))
             })
-            .for_each(|(index, percentage)| {
+            .for_each(|(index, delta)| {
                 if let Some(history) = self.thread_history.get_mut(index) {
-                    if let Some(percentage) = percentage {
-                        history.insert_at_first(percentage);
-                    }
+                    history.insert_at_first(delta_percent(&delta));
+                }
+                if let Some(history) = self.thread_modes.get_mut(index) {
+                    insert_modes(history, &delta);
                 }
             });
 
-        self.total_history.insert_at_first(total_percentage);
+        self.total_history
+            .insert_at_first(delta_percent(&total_delta));
+        insert_modes(&mut self.total_modes, &total_delta);
 
         self.old_total_usage.set(*new_total_usage);
         self.old_thread_usages.replace(new_thread_usages.clone());
         self.tempurature = temperature.clone();
+
+        let key = |e: &CpuTemperature| format!("{}:{}", e.package.unwrap_or(0), e.label);
+        if self.temperature_history.len() != temperatures.len()
+            || self
+                .temperature_history
+                .iter()
+                .zip(temperatures.iter())
+                .any(|(ring, e)| ring.name != key(e))
+        {
+            self.temperature_history = temperatures
+                .iter()
+                .map(|e| Ring::new(300).name(&key(e)))
+                .collect();
+        }
+        for (ring, e) in self.temperature_history.iter_mut().zip(temperatures.iter()) {
+            ring.insert_at_first(e.value as f64);
+        }
+        self.temperatures = temperatures.clone();
         self.frequences.replace(frequencies.clone());
+        self.policies = policies.clone();
+        self.boost = *boost;
+
+        let key = |e: &EnergyCounter| format!("{}:{}", e.package.unwrap_or(0), e.domain);
+        if self.power_history.len() != energies.len()
+            || self
+                .power_history
+                .iter()
+                .zip(energies.iter())
+                .any(|(ring, e)| ring.name != key(e))
+        {
+            self.power_history = energies
+                .iter()
+                .map(|e| Ring::new(1000).name(&key(e)))
+                .collect();
+        }
+        if let Some(old_time) = self.energy_sampled_at {
+            let time_passed = sampled_at.saturating_duration_since(old_time).as_secs_f64();
+            if time_passed > 0. {
+                for (ring, new) in self.power_history.iter_mut().zip(energies.iter()) {
+                    let watts = self
+                        .energies
+                        .iter()
+                        .find(|old| key(old) == ring.name)
+                        .and_then(|old| new.since(old))
+                        .map(|uj| uj as f64 / 1_000_000. / time_passed);
+                    if let Some(watts) = watts {
+                        ring.insert_at_first(watts);
+                    }
+                }
+            }
+        }
+        self.energies = energies.clone();
+        self.energy_sampled_at.replace(*sampled_at);
+    }
+
+    fn alert_readings(&self) -> Option<Readings> {
+        let readings = Readings::new("cpu", None)
+            .percent("total", self.total_history.newest().copied())
+            .value("temp", self.tempurature.map(f64::from))
+            .value(
+                "power",
+                self.energies
+                    .iter()
+                    .zip(self.power_history.iter())
+                    .filter(|(e, _)| e.domain == "package")
+                    .filter_map(|(_, ring)| ring.newest().copied())
+                    .reduce(|a, b| a + b),
+            );
+        Some(
+            CPU_MODES
+                .iter()
+                .zip(self.total_modes.iter())
+                .fold(readings, |readings, (name, ring)| {
+                    readings.percent(name, ring.newest().copied())
+                }),
+        )
     }
 
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
@@ -145,13 +619,12 @@ impl Resource for ResCPU {
                     self.tempurature.or_nan(|e| convert_temperature(*e as f64))
                 ),
             )
-            .lines(ls_history_graph(
+            .lines(ls_sidebar_meter(
                 width,
                 &self.total_history,
                 100.,
                 0.,
-                3,
-                ratatui::style::Color::Red,
+                self.theme.palette().cpu,
             ))
             .active(args.focused)
             .build("CPU")?;
@@ -164,58 +637,147 @@ impl Resource for ResCPU {
         let mut result = vec![];
         let info = &self.info;
 
-        let graphs: Vec<Line<'static>> = self
-            .thread_history
-            .iter()
-            .enumerate()
-            .map(|(id, ring)| {
-                (
-                    id,
-                    s_history_graph(
-                        width.saturating_sub(15),
-                        ring,
-                        100.,
-                        0.,
-                        1,
-                        ratatui::style::Color::Red,
-                    )
-                    .padding(),
-                    ring.newest(),
-                    match self.frequences.as_ref().map(|e| e.get(id)) {
-                        Some(Some(Some(o))) => Some(o),
-                        _ => None,
-                    },
-                )
-            })
-            .map(|(index, mut spans, newest, freq)| {
-                Line::from({
-                    spans.insert(0, Span::raw(format!("{:02}", index)));
-                    spans.push(Span::raw(
-                        freq.or_nan(|e| format!("{:.1}G ", (**e as f64) / 1e9)),
-                    ));
-                    spans.push(Span::raw(newest.or_nan(|e| format!("{:<.0}%", **e))));
-                    spans
+        let mut total = self.mode_graph(width.saturating_sub(4), &self.total_modes, 4);
+        total.extend(self.mode_legend(width.saturating_sub(4)));
+        let total = GroupedLines::builder(width, &self.theme)
+            .lines(total)
+            .active(args.active)
+            .build("Total")?;
+        result.push(total);
+
+        let groups = info.topology.groups();
+        if groups.is_empty() {
+            let graphs = (0..self.thread_modes.len())
+                .map(|id| self.thread_line(width, String::new(), id, None))
+                .collect();
+            let graphs = GroupedLines::builder(width, &self.theme)
+                .lines(graphs)
+                .active(args.active)
+                .build("Usage")?;
+            result.push(graphs);
+        }
+        for ((package, die), cores) in groups.iter() {
+            let graphs = cores
+                .iter()
+                .flat_map(|(core, threads)| {
+                    let temperature = self
+                        .temperatures
+                        .iter()
+                        .find(|e| e.core == Some(*core) && e.package.unwrap_or(0) == *package);
+                    threads.iter().enumerate().map(move |(idx, id)| {
+                        if idx == 0 {
+                            (format!("c{:<3}", core), *id, temperature)
+                        } else {
+                            (" ".repeat(4), *id, None)
+                        }
+                    })
                 })
-            })
-            .collect();
+                .map(|(label, id, temperature)| self.thread_line(width, label, id, temperature))
+                .collect();
+            let title = if groups.len() == 1 {
+                "Usage".to_string()
+            } else if info.dies > info.sockets {
+                format!("Package {} Die {}", package, die)
+            } else {
+                format!("Package {}", package)
+            };
+            let graphs = GroupedLines::builder(width, &self.theme)
+                .lines(graphs)
+                .active(args.active)
+                .build(title)?;
+            result.push(graphs);
+        }
+
+        if !self.energies.is_empty() || (self.local && has_energy_counters()) {
+            result.push(self.power_block(width, args.active)?);
+        }
 
-        let graphs = GroupedLines::builder(width, &self.theme)
-            .lines(graphs)
+        let header = CPU_MODES
+            .iter()
+            .fold(format!("{:<5}", "%"), |mut row, name| {
+                row.push_str(&format!("{:>8}", name));
+                row
+            });
+        let mut rows = vec![Line::raw(header).style(self.theme.palette().header())];
+        rows.push(Line::raw(Self::mode_row(
+            "all".to_string(),
+            &self.total_modes,
+        )));
+        rows.extend(
+            self.thread_modes
+                .iter()
+                .enumerate()
+                .map(|(id, modes)| Line::raw(Self::mode_row(format!("{:02}", id), modes))),
+        );
+        let breakdown = GroupedLines::builder(width, &self.theme)
+            .lines(rows)
             .active(args.active)
-            .build("Usage")?;
-        result.push(graphs);
+            .build("Breakdown")?;
+        result.push(breakdown);
 
-        let sensors = GroupedLines::builder(width, &self.theme)
-            .kv_sep(
+        let mut sensors = GroupedLines::builder(width, &self.theme);
+        if self.temperatures.is_empty() {
+            sensors = sensors.kv_sep(
                 "Temperature",
                 self.tempurature.or_nan(|e| convert_temperature(*e as f64)),
-            )
-            .active(args.active);
+            );
+        }
+        let label_width = self
+            .temperatures
+            .iter()
+            .map(|e| e.label.chars().count())
+            .max()
+            .unwrap_or(0)
+            + 1;
+        for (temperature, ring) in self
+            .temperatures
+            .iter()
+            .zip(self.temperature_history.iter())
+        {
+            let mut spans = vec![Span::raw(format!("{:<label_width$}", temperature.label))];
+            spans.extend(s_history_graph(
+                width.saturating_sub(label_width as u16 + 12),
+                ring,
+                temperature.crit.unwrap_or(100.) as f64,
+                0.,
+                1,
+                self.theme.palette().cpu,
+            ));
+            spans.push(
+                Span::raw(format!(
+                    " {:>7}",
+                    convert_temperature(temperature.value as f64)
+                ))
+                .fg(self.temperature_color(temperature)),
+            );
+            sensors = sensors.line(Line::from(spans));
+        }
+        let limits: Vec<String> = self
+            .temperatures
+            .iter()
+            .filter(|e| e.core.is_none())
+            .filter_map(|e| {
+                let limit = |name: &str, value: Option<f32>| {
+                    value.map(|e| format!("{} {}", name, convert_temperature(e as f64)))
+                };
+                let limits: Vec<String> = [limit("max", e.max), limit("crit", e.crit)]
+                    .into_iter()
+                    .flatten()
+                    .collect();
+                (!limits.is_empty()).then(|| format!("{}: {}", e.label, limits.join(", ")))
+            })
+            .collect();
+        if !limits.is_empty() {
+            sensors = sensors.line(Line::raw(limits.join("  ")).style(self.theme.palette().hint()));
+        }
+
+        result.push(sensors.active(args.active).build("Sensors")?);
 
-        result.push(sensors.build("Sensors")?);
+        result.push(self.frequency_block(width, args.active)?);
 
         let properties = GroupedLines::builder(width, &self.theme)
             .kv_sep("Model", info.model_name.or_unk_def())
+            .kv_sep("Vendor", info.vendor.or_unk_def())
             .kv_sep(
                 "Max Frequency",
                 self.info
@@ -237,11 +799,61 @@ impl Resource for ResCPU {
                 info.virtualization.or_nan(|e| e.to_owned()),
             )
             .kv_sep("Architecture", info.architecture.or_nan(|e| e.to_owned()))
+            .kv_sep("Microcode", info.microcode.or_nan(|e| e.to_owned()))
             .active(args.active)
             .build("Properties")?;
 
         result.push(properties);
 
+        let topology = &info.topology;
+        let mut builder = GroupedLines::builder(width, &self.theme)
+            .kv_sep("Packages", topology.packages().to_string())
+            .kv_sep("Dies", topology.dies().to_string())
+            .kv_sep("Cores", topology.cores().to_string())
+            .kv_sep(
+                "Threads per Core",
+                (topology.threads.len() / topology.cores().max(1)).to_string(),
+            );
+        for (name, size, instances, shared) in topology.cache_summary() {
+            builder = builder.kv_sep(
+                &name,
+                match shared {
+                    1 => format!("{} x {}", convert_storage(size as f64, true), instances),
+                    _ => format!(
+                        "{} x {}, shared by {} threads",
+                        convert_storage(size as f64, true),
+                        instances,
+                        shared
+                    ),
+                },
+            );
+        }
+        for (node, cpus) in topology.nodes.iter() {
+            builder = builder.kv_sep(&format!("NUMA Node {}", node), format_cpu_list(cpus));
+        }
+        result.push(builder.active(args.active).build("Topology")?);
+
+        if !info.vulnerabilities.is_empty() {
+            let vulnerabilities = info
+                .vulnerabilities
+                .iter()
+                .fold(
+                    GroupedLines::builder(width, &self.theme),
+                    |builder, (name, state)| builder.kv(name, state.clone()),
+                )
+                .active(args.active)
+                .build("Vulnerabilities")?;
+            result.push(vulnerabilities);
+        }
+
+        if !info.flags.is_empty() {
+            let flags = GroupedLines::builder(width, &self.theme)
+                .value(info.flags.join(" "))
+                .active(args.active)
+                .build("Flags")?;
+            result.push(flags);
+        }
+
         self.viewer_state.update_blocks(result);
 
         Ok(self
@@ -262,4 +874,18 @@ impl Resource for ResCPU {
     fn get_name(&self) -> String {
         "".to_string()
     }
+
+    fn handle_navi_event(&mut self, event: &NavigatorEvent) -> bool {
+        let NavigatorEvent::KeyEvent(ke) = event;
+        match keymap::lookup(ke, KeyContext::Cpu) {
+            Some(Action::Governor) => self.cycle_policy(PolicyKnob::Governor),
+            Some(Action::EnergyPreference) => self.cycle_policy(PolicyKnob::Epp),
+            _ => return false,
+        }
+        true
+    }
+
+    fn key_context(&self) -> Option<KeyContext> {
+        Some(KeyContext::Cpu)
+    }
 }
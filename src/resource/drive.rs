@@ -3,21 +3,24 @@ use std::{
     collections::HashMap,
     path::PathBuf,
     sync::Arc,
-    time::SystemTime,
+    time::Instant,
 };
 
 use chin_tools::AResult;
 use ratatui::text::{Line, Span};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
-        ls_history_graph, s_percent_graph,
+        ls_history_graph, ls_sidebar_meter, s_percent_graph,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
     },
     ring::Ring,
     sensor::{
         drive::{Drive, DriveData, Partition},
+        root,
         units::{convert_speed, convert_storage},
         Sensor,
     },
@@ -52,7 +55,7 @@ pub struct ResDrive {
     removeable: Option<bool>,
     old_stats: RefCell<HashMap<String, usize>>,
 
-    last_timestamp: Cell<SystemTime>,
+    last_timestamp: Cell<Option<Instant>>,
 
     partitions: Vec<Partition>,
 
@@ -63,7 +66,7 @@ impl ResDrive {
     const SECTOR_SIZE: usize = 512;
 
     pub fn new(theme: SharedTheme) -> AResult<Vec<Self>> {
-        let drive_paths = Drive::get_sysfs_paths().unwrap_or_default();
+        let drive_paths = Drive::get_sysfs_paths(&root()).unwrap_or_default();
         Ok(drive_paths
             .iter()
             .filter_map(|dp| {
@@ -72,32 +75,44 @@ impl ResDrive {
                     None
                 } else {
                     let capacity = d.inner.capacity().ok();
-                    Some(Self {
-                        supply_name: d.inner.block_device.clone(),
-                        id: d.inner.sysfs_path.as_path().to_string_lossy().to_string(),
-                        theme: theme.clone(),
-                        activity_history: Ring::new(1000),
-                        info: d.inner,
-                        is_virtual: None,
-                        writiable: None,
-                        removeable: None,
-                        last_timestamp: Cell::new(SystemTime::now()),
-                        old_stats: RefCell::new(HashMap::new()),
-                        read_speed_history: Ring::new(200),
-                        read_highest: Default::default(),
-                        read_total: Default::default(),
-                        write_speed_history: Ring::new(200),
-                        write_highest: Default::default(),
-                        write_total: Default::default(),
-                        capacity,
-                        partitions: vec![],
-                        viewer_state: Default::default(),
-                    })
+                    Some(Self::from_info(theme.clone(), d.inner, capacity))
                 }
             })
             .collect())
     }
 
+    pub fn from_info(theme: SharedTheme, info: Drive, capacity: Option<u64>) -> Self {
+        Self {
+            supply_name: info.block_device.clone(),
+            id: info.sysfs_path.as_path().to_string_lossy().to_string(),
+            theme,
+            activity_history: Ring::new(1000),
+            info,
+            is_virtual: None,
+            writiable: None,
+            removeable: None,
+            last_timestamp: Cell::new(None),
+            old_stats: RefCell::new(HashMap::new()),
+            read_speed_history: Ring::new(200),
+            read_highest: Default::default(),
+            read_total: Default::default(),
+            write_speed_history: Ring::new(200),
+            write_highest: Default::default(),
+            write_total: Default::default(),
+            capacity,
+            partitions: vec![],
+            viewer_state: Default::default(),
+        }
+    }
+
+    pub fn info(&self) -> &Drive {
+        &self.info
+    }
+
+    pub fn capacity(&self) -> Option<u64> {
+        self.capacity
+    }
+
     fn activity_graph(&self, width: u16) -> Vec<Line<'static>> {
         ls_history_graph(
             width,
@@ -105,7 +120,7 @@ impl ResDrive {
             100.,
             0.,
             3,
-            ratatui::style::Color::Green,
+            self.theme.palette().drive,
         )
     }
 
@@ -117,15 +132,22 @@ impl ResDrive {
             removable,
             disk_stats,
             capacity: _,
+            sampled_at,
         } = data;
 
         self.is_virtual.replace(*is_virtual);
         self.writiable = writable.as_ref().ok().copied();
         self.removeable = removable.as_ref().ok().copied();
 
-        let time_passed = SystemTime::now()
-            .duration_since(self.last_timestamp.get())
-            .map_or(1.0f64, |timestamp| timestamp.as_secs_f64());
+        let time_passed = self
+            .last_timestamp
+            .replace(Some(*sampled_at))
+            .map(|ts| sampled_at.saturating_duration_since(ts).as_secs_f64())
+            .filter(|secs| *secs > 0.);
+        let Some(time_passed) = time_passed else {
+            self.old_stats.replace(disk_stats.clone());
+            return;
+        };
 
         if let (Some(read_ticks), Some(write_ticks), Some(old_read_ticks), Some(old_write_ticks)) = (
             disk_stats.get("read_ticks"),
@@ -189,7 +211,7 @@ impl ResDrive {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ResDriveRsp {
     pub data: DriveData,
     partitions: Option<Vec<Partition>>,
@@ -210,7 +232,7 @@ impl Resource for ResDrive {
 
     fn do_sensor(req: Self::Req) -> AResult<SensorResultType> {
         let data = DriveData::new(&req);
-        let partitions = Partition::fetch()?;
+        let partitions = Partition::fetch(&root())?;
         Ok(SensorResultType::SyncResult(SensorRsp::Drive(
             ResDriveRsp {
                 data: data,
@@ -226,6 +248,24 @@ impl Resource for ResDrive {
         }
     }
 
+    /// `used` is summed over the mounted partitions of the drive.
+    fn alert_readings(&self) -> Option<Readings> {
+        let (used, total) = match self.partitions.is_empty() {
+            true => (None, None),
+            false => (
+                Some(self.partitions.iter().map(|e| e.used_bytes() as f64).sum()),
+                Some(self.partitions.iter().map(|e| e.total_bytes as f64).sum()),
+            ),
+        };
+        Some(
+            Readings::new("drive", Some(self.info.block_device.clone()))
+                .share("used", used, total)
+                .value("read", self.read_speed_history.newest().copied())
+                .value("write", self.write_speed_history.newest().copied())
+                .percent("busy", self.activity_history.newest().map(|e| e * 100.)),
+        )
+    }
+
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
         let width = args.width;
         let block = GroupedLines::builder(width, &self.theme)
@@ -236,7 +276,16 @@ impl Resource for ResDrive {
                     .newest()
                     .or_nan(|e| format!("{:.1} %", e)),
             )
-            .lines(self.activity_graph(width).into())
+            .lines(
+                ls_sidebar_meter(
+                    width,
+                    &self.activity_history,
+                    100.,
+                    0.,
+                    self.theme.palette().drive,
+                )
+                .into(),
+            )
             .active(args.focused)
             .build(format!("Drive({})", self.supply_name))?;
 
@@ -277,7 +326,7 @@ impl Resource for ResDrive {
                     self.read_highest.get(),
                     0.,
                     3,
-                    ratatui::style::Color::Green,
+                    self.theme.palette().drive,
                 )
                 .into(),
             )
@@ -293,7 +342,7 @@ impl Resource for ResDrive {
                     self.write_highest.get(),
                     0.,
                     3,
-                    ratatui::style::Color::Green,
+                    self.theme.palette().drive,
                 )
                 .into(),
             )
@@ -3,14 +3,16 @@ use std::sync::Arc;
 use chin_tools::AResult;
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
-        ls_history_graph,
+        ls_sidebar_meter,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
     },
     ring::Ring,
     sensor::{
         gpu::{Gpu, GpuData},
+        root,
         units::convert_power,
     },
     tarits::{None2NaN, None2NaNDef, None2NanString},
@@ -37,7 +39,7 @@ pub struct ResGPU {
 
 impl ResGPU {
     pub fn new(theme: SharedTheme) -> AResult<Vec<Self>> {
-        let gpu_infos = Gpu::get_gpus()?;
+        let gpu_infos = Gpu::get_gpus(&root())?;
 
         Ok(gpu_infos
             .into_iter()
@@ -84,14 +86,35 @@ impl Resource for ResGPU {
         self.gpu_data.replace(data.clone());
     }
 
+    /// GPUs are named by their card number, e.g. `gpu.0.temp`.
+    fn alert_readings(&self) -> Option<Readings> {
+        let data = self.gpu_data.as_ref()?;
+        let card = self
+            .info
+            .sysfs_path()
+            .file_name()
+            .and_then(|e| e.to_str())
+            .map(|e| e.trim_start_matches("card").to_string());
+        Some(
+            Readings::new("gpu", card)
+                .percent("usage", Some(data.usage_fraction * 100.))
+                .percent("enc", Some(data.encode_fraction * 100.))
+                .percent("dec", Some(data.decode_fraction * 100.))
+                .share(
+                    "vram",
+                    Some(data.used_vram as f64),
+                    Some(data.total_vram as f64),
+                )
+                .value("temp", Some(data.temp))
+                .value("power", Some(data.power_usage)),
+        )
+    }
+
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
         let width = args.width;
         let block = GroupedLines::builder(width, &self.theme)
             .kv("UR", self.total_usage.or_nan(|e| format!("{:.1} %", e)))
-            .lines(
-                ls_history_graph(width, &self.history, 1., 0., 3, ratatui::style::Color::Red)
-                    .into(),
-            )
+            .lines(ls_sidebar_meter(width, &self.history, 1., 0., self.theme.palette().gpu).into())
             .active(args.focused)
             .build(format!(
                 "GPU({})",
@@ -2,14 +2,16 @@ use chin_tools::AResult;
 use itertools::Itertools;
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
-        ls_history_graph,
+        ls_history_graph, ls_sidebar_meter,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
     },
     ring::Ring,
     sensor::{
         memory::{self, MemoryData, MemoryDevice},
+        root,
         units::convert_storage,
     },
     tarits::{None2NaN, None2NanString},
@@ -28,6 +30,7 @@ pub struct ResMEM {
     pub mem_usage_percent: Option<f64>,
 
     pub usage_history: Ring<f64>,
+    data: Option<MemoryData>,
 
     // Show
     theme: SharedTheme,
@@ -37,9 +40,11 @@ pub struct ResMEM {
 
 impl ResMEM {
     pub fn new(theme: SharedTheme) -> AResult<Self> {
-        let meminfo = memory::get_memory_devices()?;
+        Ok(Self::from_info(theme, memory::get_memory_devices()?))
+    }
 
-        Ok(Self {
+    pub fn from_info(theme: SharedTheme, meminfo: Vec<MemoryDevice>) -> Self {
+        Self {
             info: meminfo,
 
             theme,
@@ -47,8 +52,13 @@ impl ResMEM {
             formatted_total_mem: Default::default(),
             mem_usage_percent: Default::default(),
             usage_history: Ring::new(1000),
+            data: None,
             viewer_state: Default::default(),
-        })
+        }
+    }
+
+    pub fn info(&self) -> &[MemoryDevice] {
+        &self.info
     }
 
     pub fn mem_usage(&self) -> String {
@@ -80,8 +90,8 @@ impl Resource for ResMEM {
         ()
     }
 
-    fn do_sensor(req: Self::Req) -> AResult<SensorResultType> {
-        let data = MemoryData::fetch(req)?;
+    fn do_sensor(_req: Self::Req) -> AResult<SensorResultType> {
+        let data = MemoryData::fetch(&root())?;
 
         Ok(SensorResultType::SyncResult(super::SensorRsp::Memory(data)))
     }
@@ -107,6 +117,20 @@ impl Resource for ResMEM {
         self.usage_history.insert_at_first(memory_fraction);
         self.formatted_used_mem.replace(formatted_used_mem);
         self.formatted_total_mem.replace(formatted_total_mem);
+        self.data.replace(*data);
+    }
+
+    fn alert_readings(&self) -> Option<Readings> {
+        let data = self.data?;
+        let total = Some(data.total_mem as f64);
+        let used = data.total_mem.saturating_sub(data.available_mem);
+        let used_swap = data.total_swap.saturating_sub(data.free_swap);
+        Some(
+            Readings::new("mem", None)
+                .share("used", Some(used as f64), total)
+                .share("available", Some(data.available_mem as f64), total)
+                .share("swap", Some(used_swap as f64), Some(data.total_swap as f64)),
+        )
     }
 
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
@@ -120,13 +144,12 @@ impl Resource for ResMEM {
                 )
             })
             .kv("Usage", self.mem_usage())
-            .lines(ls_history_graph(
+            .lines(ls_sidebar_meter(
                 width,
                 &self.usage_history,
                 1.,
                 0.,
-                3,
-                ratatui::style::Color::Magenta,
+                self.theme.palette().memory,
             ))
             .active(args.focused)
             .build("Memory")?;
@@ -146,7 +169,7 @@ impl Resource for ResMEM {
                 1.,
                 0.,
                 3,
-                ratatui::style::Color::Magenta,
+                self.theme.palette().memory,
             ))
             .active(args.active)
             .build("Usage")?;
@@ -10,6 +10,7 @@ use std::{
     path::PathBuf,
     sync::Arc,
     thread::{self},
+    time::{Duration, Instant},
 };
 
 use battery::ResBattery;
@@ -28,18 +29,23 @@ use ratatui::{
     text::{Line, Span},
     Frame,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    alert::{self, Readings},
     app::ResourceEvent,
     component::{grouped_lines::GroupedLines, stateful_lines::StatefulLinesType},
     sensor::{
         battery::BatteryData,
         cpu::CpuData,
+        deserialize_result,
         gpu::{Gpu, GpuData},
         memory::MemoryData,
         network::{NetworkData, NetworkInterface},
+        serialize_result,
+        settings::SETTINGS,
     },
-    view::{NavigatorEvent, OverviewArg, PageArg},
+    view::{keymap::KeyContext, NavigatorEvent, OverviewArg, PageArg},
 };
 
 pub trait Resource {
@@ -58,6 +64,16 @@ pub trait Resource {
 
     fn update_data(&mut self, data: &Self::Rsp);
 
+    /// The values the alert rules are checked against after an update.
+    fn alert_readings(&self) -> Option<Readings> {
+        None
+    }
+
+    /// The sampling interval of this resource, `None` follows the refresh speed.
+    fn refresh_interval(&self) -> Option<Duration> {
+        None
+    }
+
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>>;
 
     fn _build_page(&mut self, _args: &PageArg) -> AResult<String> {
@@ -70,6 +86,11 @@ pub trait Resource {
         false
     }
 
+    /// The bindings of the page listed in the help, if it has its own keys.
+    fn key_context(&self) -> Option<KeyContext> {
+        None
+    }
+
     fn render_page(&mut self, frame: &mut Frame, args: &PageArg, max_width: u16) {
         let rect = if max_width > 0 && args.rect.width > max_width {
             let side = (args.rect.width - max_width) / 2;
@@ -106,10 +127,16 @@ pub trait Resource {
     }
 }
 
-#[derive(Debug)]
+/// Serialized as `{"resource": <variant>, "data": ...}` by the headless output.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "resource", content = "data")]
 pub enum SensorRsp {
     CPU(CpuData),
     Memory(MemoryData),
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     GPU(AResult<GpuData>),
     Drive(ResDriveRsp),
     Network(NetworkData),
@@ -123,14 +150,37 @@ pub enum SensorResultType {
 }
 
 impl SensorRsp {
-    fn get_id(&self) -> &str {
+    /// Readings of the sensors, not answers to a user action. They are held
+    /// back while the histories are frozen.
+    pub fn is_sample(&self) -> bool {
+        match self {
+            SensorRsp::Process(rsp) => matches!(
+                rsp,
+                ProcessRsp::Processes(_) | ProcessRsp::LoadAvg(_) | ProcessRsp::Uptime(_)
+            ),
+            _ => true,
+        }
+    }
+
+    pub fn get_id(&self) -> &str {
         match self {
             SensorRsp::CPU(_) => "CPU",
             SensorRsp::Memory(_) => "MEM",
             SensorRsp::GPU(res) => res.as_ref().map_or("GPU", |e| &e.id),
-            SensorRsp::Drive(rsp) => rsp.data.inner.sysfs_path.as_path().to_str().unwrap_or("drive"),
+            SensorRsp::Drive(rsp) => rsp
+                .data
+                .inner
+                .sysfs_path
+                .as_path()
+                .to_str()
+                .unwrap_or("drive"),
             SensorRsp::Network(data) => data.sysfs_path.as_str(),
-            SensorRsp::Battery(data) => data.inner.sysfs_path.as_path().to_str().unwrap_or("battery"),
+            SensorRsp::Battery(data) => data
+                .inner
+                .sysfs_path
+                .as_path()
+                .to_str()
+                .unwrap_or("battery"),
             SensorRsp::Process(_) => "process",
         }
     }
@@ -184,6 +234,28 @@ impl ResourceType {
         }
     }
 
+    /// The `refresh` entry of the config for this type wins over the cadence
+    /// of the resource itself, which wins over the refresh speed.
+    pub fn refresh_interval(&self) -> Duration {
+        let own = match self {
+            ResourceType::CPU(rt) => rt.refresh_interval(),
+            ResourceType::Memory(rt) => rt.refresh_interval(),
+            ResourceType::GPU(rt) => rt.refresh_interval(),
+            ResourceType::Drive(rt) => rt.refresh_interval(),
+            ResourceType::Network(rt) => rt.refresh_interval(),
+            ResourceType::Battery(rt) => rt.refresh_interval(),
+            ResourceType::Process(rt) => rt.refresh_interval(),
+        };
+
+        SETTINGS
+            .refresh_interval(self.get_type_name())
+            .map(Duration::from_secs_f32)
+            .or(own)
+            .unwrap_or_else(|| {
+                Duration::from_secs_f32(SETTINGS.refresh_speed().ui_refresh_interval())
+            })
+    }
+
     pub fn get_type_name(&self) -> &'static str {
         match self {
             ResourceType::CPU(rt) => rt.get_type_name(),
@@ -220,8 +292,20 @@ impl ResourceType {
         }
     }
 
-    pub fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
+    pub fn key_context(&self) -> Option<KeyContext> {
         match self {
+            ResourceType::CPU(rt) => rt.key_context(),
+            ResourceType::Memory(rt) => rt.key_context(),
+            ResourceType::GPU(rt) => rt.key_context(),
+            ResourceType::Drive(rt) => rt.key_context(),
+            ResourceType::Network(rt) => rt.key_context(),
+            ResourceType::Battery(rt) => rt.key_context(),
+            ResourceType::Process(rt) => rt.key_context(),
+        }
+    }
+
+    pub fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
+        let block = match self {
             ResourceType::CPU(rt) => rt.overview_content(args),
             ResourceType::Memory(rt) => rt.overview_content(args),
             ResourceType::GPU(rt) => rt.overview_content(args),
@@ -229,6 +313,19 @@ impl ResourceType {
             ResourceType::Network(rt) => rt.overview_content(args),
             ResourceType::Battery(rt) => rt.overview_content(args),
             ResourceType::Process(rt) => rt.overview_content(args),
+        };
+        block.map(|block| block.alert(alert::is_firing(self.get_id())))
+    }
+
+    pub fn alert_readings(&self) -> Option<Readings> {
+        match self {
+            ResourceType::CPU(rt) => rt.alert_readings(),
+            ResourceType::Memory(rt) => rt.alert_readings(),
+            ResourceType::GPU(rt) => rt.alert_readings(),
+            ResourceType::Drive(rt) => rt.alert_readings(),
+            ResourceType::Network(rt) => rt.alert_readings(),
+            ResourceType::Battery(rt) => rt.alert_readings(),
+            ResourceType::Process(rt) => rt.alert_readings(),
         }
     }
 
@@ -286,7 +383,19 @@ impl ResourceType {
         }
     }
 
-    pub fn updata_data(&mut self, rsp: &SensorRsp) -> bool {
+    /// Returns true if `rsp` belongs to this resource, the alert rules are
+    /// checked right after.
+    pub fn updata_data(&mut self, rsp: &SensorRsp, sampled_at: Instant) -> bool {
+        let updated = self.update_matching(rsp);
+        if updated {
+            if let Some(readings) = self.alert_readings() {
+                alert::evaluate(self.get_id(), &readings, sampled_at);
+            }
+        }
+        updated
+    }
+
+    fn update_matching(&mut self, rsp: &SensorRsp) -> bool {
         let rsp_id = rsp.get_id();
         match rsp {
             SensorRsp::CPU(data) => {
@@ -324,6 +433,7 @@ impl ResourceType {
                 if let ResourceType::Drive(rt) = self {
                     if rsp_id == rt.get_id() {
                         rt.update_data(data);
+                        return true;
                     }
                 }
             }
@@ -1,17 +1,19 @@
-use std::{cell::Cell, sync::Arc, time::SystemTime};
+use std::{cell::Cell, sync::Arc, time::Instant};
 
 use chin_tools::AResult;
 use ratatui::layout::Rect;
 
 use crate::{
+    alert::Readings,
     component::{
         grouped_lines::GroupedLines,
-        ls_history_graph,
+        ls_history_graph, ls_sidebar_meter,
         stateful_lines::{StatefulGroupedLines, StatefulLinesType},
     },
     ring::Ring,
     sensor::{
         network::{NetworkData, NetworkInterface},
+        root,
         units::{convert_speed, convert_storage},
         Sensor,
     },
@@ -26,7 +28,7 @@ use super::{Resource, SensorResultType, SensorRsp};
 pub struct ResNetwork {
     info: Arc<NetworkInterface>,
 
-    last_timestamp: Option<SystemTime>,
+    last_timestamp: Option<Instant>,
 
     old_received_bytes: Option<usize>,
     old_sent_bytes: Option<usize>,
@@ -47,29 +49,37 @@ pub struct ResNetwork {
 
 impl ResNetwork {
     pub fn new(theme: SharedTheme) -> AResult<Vec<Self>> {
-        let network_paths = NetworkInterface::get_sysfs_paths().unwrap_or_default();
+        let network_paths = NetworkInterface::get_sysfs_paths(&root()).unwrap_or_default();
 
         let rns = network_paths
             .iter()
-            .map(|path| ResNetwork {
-                info: Arc::new(NetworkInterface::from_sysfs(&path)),
-                theme: theme.clone(),
-                old_received_bytes: None,
-                old_sent_bytes: None,
-                last_timestamp: None,
-                highest_received_speed: Default::default(),
-                highest_sent_speed: Default::default(),
-                received_speed: None,
-                sent_speed: None,
-                sendhistory: Ring::new(1000),
-                receive_history: Ring::new(1000),
-                viewer_state: Default::default(),
-            })
+            .map(|path| Self::from_info(theme.clone(), NetworkInterface::from_sysfs(&path)))
             .collect();
 
         Ok(rns)
     }
 
+    pub fn from_info(theme: SharedTheme, info: NetworkInterface) -> Self {
+        ResNetwork {
+            info: Arc::new(info),
+            theme,
+            old_received_bytes: None,
+            old_sent_bytes: None,
+            last_timestamp: None,
+            highest_received_speed: Default::default(),
+            highest_sent_speed: Default::default(),
+            received_speed: None,
+            sent_speed: None,
+            sendhistory: Ring::new(1000),
+            receive_history: Ring::new(1000),
+            viewer_state: Default::default(),
+        }
+    }
+
+    pub fn info(&self) -> &NetworkInterface {
+        &self.info
+    }
+
     fn interface(&self) -> String {
         self.info.interface_name.to_str().or_unk(|e| e.to_string())
     }
@@ -81,7 +91,7 @@ impl Resource for ResNetwork {
     type Rsp = NetworkData;
 
     fn get_id(&self) -> &str {
-        self.info.hw_address.as_ref().map_or("", |e| e)
+        self.info.sysfs_path.to_str().unwrap_or_default()
     }
 
     fn get_req(&self) -> Self::Req {
@@ -101,6 +111,7 @@ impl Resource for ResNetwork {
             display_name: _,
             hw_address: _,
             sysfs_path: _,
+            sampled_at,
         } = data;
 
         if let (Some(old_time), Some(old_received_bytes), Some(old_sent_bytes)) = (
@@ -108,9 +119,10 @@ impl Resource for ResNetwork {
             self.old_received_bytes,
             self.old_sent_bytes,
         ) {
-            let time_passed = SystemTime::now()
-                .duration_since(old_time)
-                .map_or(1.0f64, |timestamp| timestamp.as_secs_f64());
+            let time_passed = sampled_at.saturating_duration_since(old_time).as_secs_f64();
+            if time_passed <= 0. {
+                return;
+            }
 
             let received_delta = if let (Ok(received_bytes),) = (received_bytes,) {
                 Some(received_bytes.saturating_sub(old_received_bytes) as f64 / time_passed)
@@ -158,11 +170,19 @@ impl Resource for ResNetwork {
             }
         }
 
-        self.last_timestamp.replace(SystemTime::now());
+        self.last_timestamp.replace(*sampled_at);
         self.old_received_bytes = received_bytes.as_ref().map(|e| *e).ok();
         self.old_sent_bytes = sent_bytes.as_ref().map(|e| *e).ok();
     }
 
+    fn alert_readings(&self) -> Option<Readings> {
+        Some(
+            Readings::new("net", Some(self.interface()))
+                .value("rx", self.received_speed)
+                .value("tx", self.sent_speed),
+        )
+    }
+
     fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
         let width = args.width;
         let block = GroupedLines::builder(width, &self.theme)
@@ -171,34 +191,32 @@ impl Resource for ResNetwork {
                     "R",
                     self.received_speed
                         .as_ref()
-                        .or_nan(|e| convert_storage(**e, false)),
+                        .or_nan(|e| convert_speed(**e, true)),
                 ),
                 (
                     "S",
                     self.sent_speed
                         .as_ref()
-                        .or_nan(|e| convert_storage(**e, false)),
+                        .or_nan(|e| convert_speed(**e, true)),
                 ),
             ])
             .lines(
-                ls_history_graph(
+                ls_sidebar_meter(
                     width,
                     &self.sendhistory,
                     self.highest_sent_speed.get(),
                     0.,
-                    3,
-                    ratatui::style::Color::Yellow,
+                    self.theme.palette().send,
                 )
                 .into(),
             )
             .lines(
-                ls_history_graph(
+                ls_sidebar_meter(
                     width,
                     &self.receive_history,
                     self.highest_received_speed.get(),
                     0.,
-                    3,
-                    ratatui::style::Color::Blue,
+                    self.theme.palette().receive,
                 )
                 .into(),
             )
@@ -222,9 +240,9 @@ impl Resource for ResNetwork {
         } = args.rect;
 
         fn label(history: &Ring<f64>, highest: &f64) -> String {
-            let formatted_read_speed = history.newest().or_nan(|e| convert_speed(**e, false));
+            let formatted_read_speed = history.newest().or_nan(|e| convert_speed(**e, true));
 
-            let formatted_highest_read_speed = convert_speed(*highest, false);
+            let formatted_highest_read_speed = convert_speed(*highest, true);
             format!(
                 "{formatted_read_speed} · {} {formatted_highest_read_speed}",
                 "Highest:"
@@ -243,7 +261,7 @@ impl Resource for ResNetwork {
                     self.highest_received_speed.get(),
                     0.,
                     3,
-                    ratatui::style::Color::Blue,
+                    self.theme.palette().receive,
                 )
                 .into(),
             )
@@ -258,7 +276,7 @@ impl Resource for ResNetwork {
                     self.highest_sent_speed.get(),
                     0.,
                     3,
-                    ratatui::style::Color::Yellow,
+                    self.theme.palette().send,
                 )
                 .into(),
             )
@@ -0,0 +1,491 @@
+use std::{cmp::Ordering, str::FromStr};
+
+use crossterm::event::KeyEvent;
+use itertools::Itertools;
+use process_data::Containerization;
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::Clear,
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+use unicode_width::UnicodeWidthChar;
+
+use crate::{
+    component::{render_border, s_label},
+    sensor::{
+        process::ProcessItem,
+        settings::SETTINGS,
+        units::{conver_storage_width4, convert_seconds},
+    },
+    utils::is_only_char,
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme,
+    },
+};
+
+use super::{ProcessRow, PROCESS_SORT_TYPE};
+
+#[derive(
+    Clone, Copy, PartialEq, Eq, Hash, Debug, EnumIter, EnumString, Display, Serialize, Deserialize,
+)]
+pub enum ProcessCell {
+    PID,
+    PRG,
+    USER,
+    STATE,
+    NICE,
+    AFF,
+    CMD,
+    MEM,
+    CPU,
+    READ,
+    WRITE,
+    TIME,
+    CTIME,
+    GPU,
+    VRAM,
+    ENC,
+    DEC,
+    CGROUP,
+    CONT,
+}
+
+impl ProcessCell {
+    fn width(&self) -> u16 {
+        match self {
+            ProcessCell::PID => 9,
+            ProcessCell::PRG => 11,
+            ProcessCell::USER => 6,
+            ProcessCell::STATE => 4,
+            ProcessCell::NICE => 5,
+            ProcessCell::AFF => 10,
+            ProcessCell::CMD => 200,
+            ProcessCell::MEM => 7,
+            ProcessCell::CPU => 6,
+            ProcessCell::READ => 7,
+            ProcessCell::WRITE => 7,
+            ProcessCell::TIME => 10,
+            ProcessCell::CTIME => 10,
+            ProcessCell::GPU => 6,
+            ProcessCell::VRAM => 7,
+            ProcessCell::ENC => 6,
+            ProcessCell::DEC => 6,
+            ProcessCell::CGROUP => 16,
+            ProcessCell::CONT => 8,
+        }
+    }
+
+    pub fn keep_width<S>(&self, noodle: S) -> String
+    where
+        S: Into<String>,
+    {
+        let total_width = self.width();
+        let content_width = total_width.saturating_sub(2);
+        let mut noodle: String = noodle.into();
+
+        let mut width: usize = 0;
+        let mut size = None;
+
+        // `size` is the byte offset of the first char which does not fit
+        for (offset, c) in noodle.char_indices() {
+            if let Some(wid) = c.width() {
+                let nw = width.saturating_add(wid);
+                if nw > content_width.into() {
+                    size.replace(offset);
+                    break;
+                }
+                width = nw;
+            }
+        }
+
+        if let Some(size) = size {
+            noodle.truncate(size);
+        }
+
+        let padding = total_width.saturating_sub(width as u16);
+        if padding > 0 {
+            for _ in 0..padding {
+                noodle.push(' ');
+            }
+        }
+
+        noodle
+    }
+
+    pub fn to_value(&self, data: &ProcessItem) -> Span<'static> {
+        let s = match self {
+            ProcessCell::PID => self.keep_width(data.pid.to_string().as_str()),
+            ProcessCell::USER => self.keep_width(data.user.as_str()),
+            ProcessCell::STATE => self.keep_width(data.state.to_string()),
+            ProcessCell::NICE => self.keep_width(data.nice.to_string()),
+            ProcessCell::AFF => self.keep_width(data.cpus_allowed.as_deref().unwrap_or("-")),
+            ProcessCell::CMD => self.keep_width(data.commandline.as_str()),
+            ProcessCell::MEM => {
+                self.keep_width(conver_storage_width4(data.memory_usage as f64).as_str())
+            }
+            ProcessCell::CPU => {
+                self.keep_width(format!("{:.1}", data.cpu_time_ratio * 100.).as_str())
+            }
+            ProcessCell::READ => match data.read_speed.as_ref() {
+                Some(o) => self.keep_width(conver_storage_width4(*o).as_str()),
+                None => {
+                    return s_label(
+                        &self.keep_width(conver_storage_width4(0.).as_str()),
+                        Style::new().fg(theme::palette().unknown),
+                    )
+                }
+            },
+            ProcessCell::WRITE => match data.write_speed.as_ref() {
+                Some(o) => self.keep_width(conver_storage_width4(*o).as_str()),
+                None => {
+                    return s_label(
+                        &self.keep_width(conver_storage_width4(0.).as_str()),
+                        Style::new().fg(theme::palette().unknown),
+                    )
+                }
+            },
+            ProcessCell::TIME => self.keep_width(convert_seconds(data.starttime as u64)),
+            ProcessCell::CTIME => self.keep_width(convert_seconds(
+                (data.user_cpu_time + data.system_cpu_time) as u64,
+            )),
+            ProcessCell::PRG => self.keep_width(&data.display_name),
+            ProcessCell::GPU => self.keep_width(format!("{:.1}", data.gpu_usage * 100.)),
+            ProcessCell::VRAM => self.keep_width(conver_storage_width4(data.gpu_mem_usage as f64)),
+            ProcessCell::ENC => self.keep_width(format!("{:.1}", data.enc_usage * 100.)),
+            ProcessCell::DEC => self.keep_width(format!("{:.1}", data.dec_usage * 100.)),
+            ProcessCell::CGROUP => self.keep_width(data.cgroup.as_deref().unwrap_or("-")),
+            ProcessCell::CONT => self.keep_width(match data.containerization {
+                Containerization::None => "-",
+                Containerization::Flatpak => "Flatpak",
+                Containerization::Snap => "Snap",
+            }),
+        };
+
+        s.into()
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            ProcessCell::PID => "PID",
+            ProcessCell::PRG => "NAME",
+            ProcessCell::USER => "USER",
+            ProcessCell::STATE => "S",
+            ProcessCell::NICE => "NI",
+            ProcessCell::AFF => "AFF",
+            ProcessCell::CMD => "CMD",
+            ProcessCell::MEM => "MEM",
+            ProcessCell::CPU => "CPU",
+            ProcessCell::READ => "READ",
+            ProcessCell::WRITE => "WRIT",
+            ProcessCell::TIME => "START",
+            ProcessCell::CTIME => "CPUTIME",
+            ProcessCell::GPU => "GPU",
+            ProcessCell::VRAM => "VRAM",
+            ProcessCell::ENC => "ENC",
+            ProcessCell::DEC => "DEC",
+            ProcessCell::CGROUP => "CGROUP",
+            ProcessCell::CONT => "CONT",
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            ProcessCell::PID => "Process ID",
+            ProcessCell::PRG => "Program name",
+            ProcessCell::USER => "Owner",
+            ProcessCell::STATE => "State",
+            ProcessCell::NICE => "Nice value",
+            ProcessCell::AFF => "CPU affinity",
+            ProcessCell::CMD => "Command line",
+            ProcessCell::MEM => "Memory usage",
+            ProcessCell::CPU => "CPU usage",
+            ProcessCell::READ => "Disk read speed",
+            ProcessCell::WRITE => "Disk write speed",
+            ProcessCell::TIME => "Start time since boot",
+            ProcessCell::CTIME => "Total CPU time",
+            ProcessCell::GPU => "GPU usage",
+            ProcessCell::VRAM => "Video memory usage",
+            ProcessCell::ENC => "Video encoder usage",
+            ProcessCell::DEC => "Video decoder usage",
+            ProcessCell::CGROUP => "Cgroup",
+            ProcessCell::CONT => "Containerization",
+        }
+    }
+
+    fn to_label(&self, suffix: char) -> Span<'static> {
+        let mut s = String::new();
+        s.push_str(self.label());
+        s.push(suffix);
+
+        s = self.keep_width(s);
+        s.into()
+    }
+
+    pub fn cmp(&self, f: &ProcessItem, b: &ProcessItem, desc: bool) -> Ordering {
+        let r = match self {
+            ProcessCell::PID => f.pid.cmp(&b.pid),
+            ProcessCell::PRG => f.display_name.cmp(&b.display_name),
+            ProcessCell::USER => f.user.cmp(&b.user),
+            ProcessCell::STATE => f.state.cmp(&b.state),
+            ProcessCell::NICE => f.nice.cmp(&b.nice),
+            ProcessCell::AFF => f.cpus_allowed.cmp(&b.cpus_allowed),
+            ProcessCell::CMD => f.commandline.cmp(&b.commandline),
+            ProcessCell::MEM => f.memory_usage.cmp(&b.memory_usage),
+            ProcessCell::CPU => f.cpu_time_ratio.total_cmp(&b.cpu_time_ratio),
+            ProcessCell::READ => f
+                .read_speed
+                .partial_cmp(&b.read_speed)
+                .unwrap_or(Ordering::Equal),
+            ProcessCell::WRITE => f
+                .write_speed
+                .partial_cmp(&b.write_speed)
+                .unwrap_or(Ordering::Equal),
+            ProcessCell::TIME => f.starttime.total_cmp(&b.starttime),
+            ProcessCell::CTIME => (f.user_cpu_time + f.system_cpu_time)
+                .total_cmp(&(b.user_cpu_time + b.system_cpu_time)),
+            ProcessCell::GPU => f.gpu_usage.total_cmp(&b.gpu_usage),
+            ProcessCell::VRAM => f.gpu_mem_usage.cmp(&b.gpu_mem_usage),
+            ProcessCell::ENC => f.enc_usage.total_cmp(&b.enc_usage),
+            ProcessCell::DEC => f.dec_usage.total_cmp(&b.dec_usage),
+            ProcessCell::CGROUP => f.cgroup.cmp(&b.cgroup),
+            ProcessCell::CONT => (f.containerization as u8).cmp(&(b.containerization as u8)),
+        };
+
+        if desc {
+            r.reverse()
+        } else {
+            r
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct LineBuilder {
+    pub labels: Vec<ProcessCell>,
+}
+
+impl LineBuilder {
+    const DEFAULT: [ProcessCell; 7] = [
+        ProcessCell::PID,
+        ProcessCell::USER,
+        ProcessCell::CPU,
+        ProcessCell::MEM,
+        ProcessCell::READ,
+        ProcessCell::WRITE,
+        ProcessCell::CMD,
+    ];
+
+    pub fn new() -> Self {
+        let header = SETTINGS
+            .process_columns()
+            .map(|columns| {
+                columns
+                    .split(',')
+                    .filter_map(|e| ProcessCell::from_str(e.trim()).ok())
+                    .unique()
+                    .collect_vec()
+            })
+            .filter(|columns| !columns.is_empty())
+            .unwrap_or_else(|| Self::DEFAULT.to_vec());
+
+        Self { labels: header }
+    }
+
+    pub fn save(&self) {
+        let columns = self.labels.iter().map(ToString::to_string).join(",");
+        if let Err(err) = SETTINGS.set_process_columns(columns) {
+            tracing::error!("unable to save process columns: {}", err);
+        }
+    }
+
+    pub fn to_line(&self, row: &ProcessRow, active: bool) -> Line<'static> {
+        let spans: Vec<Span<'static>> = self
+            .labels
+            .iter()
+            .map(|pc| match pc {
+                ProcessCell::PID if row.app.is_some() => pc
+                    .keep_width(format!(
+                        "[{}]",
+                        row.app.as_ref().map_or(0, |app| app.processes_amount)
+                    ))
+                    .into(),
+                ProcessCell::CMD if row.fold.is_some() || !row.prefix.is_empty() => {
+                    let marker = match row.fold {
+                        Some(true) => "▸ ",
+                        Some(false) => "▾ ",
+                        None => "",
+                    };
+                    pc.keep_width(format!("{}{}{}", row.prefix, marker, row.item.commandline))
+                        .into()
+                }
+                _ => pc.to_value(&row.item),
+            })
+            .collect();
+        let palette = theme::palette();
+        let line = Line::from(spans).fg(palette.fg);
+        if active {
+            line.patch_style(palette.selection())
+        } else {
+            line
+        }
+    }
+
+    pub fn to_header(&self) -> Line<'static> {
+        let mut spans: Vec<Span<'static>> = vec![];
+        let cmp = { PROCESS_SORT_TYPE.read().unwrap().clone() };
+
+        for ele in self.labels.iter() {
+            let suffix = if let Some((cell, desc)) = cmp {
+                if cell == *ele {
+                    if desc {
+                        '↓'
+                    } else {
+                        '↑'
+                    }
+                } else {
+                    ' '
+                }
+            } else {
+                ' '
+            };
+
+            spans.push(ele.to_label(suffix))
+        }
+        Line::from(spans).style(theme::palette().header())
+    }
+}
+
+/// Overlay to add, remove and reorder the columns of the process table, the
+/// shown columns come first in their current order.
+#[derive(Debug, Default)]
+pub struct ColumnChooser {
+    cursor: usize,
+}
+
+impl ColumnChooser {
+    fn entries(builder: &LineBuilder) -> Vec<(ProcessCell, bool)> {
+        builder
+            .labels
+            .iter()
+            .map(|e| (*e, true))
+            .chain(
+                ProcessCell::iter()
+                    .filter(|e| !builder.labels.contains(e))
+                    .map(|e| (e, false)),
+            )
+            .collect()
+    }
+
+    /// Returns false when the chooser should be closed.
+    pub fn handle_key(&mut self, key: &KeyEvent, builder: &mut LineBuilder) -> bool {
+        let entries = Self::entries(builder);
+        let Some((cell, shown)) = entries.get(self.cursor).cloned() else {
+            return false;
+        };
+
+        let action = keymap::lookup(key, KeyContext::Process);
+        let global = keymap::lookup(key, KeyContext::Global);
+        if matches!(action, Some(Action::Back | Action::Open | Action::Columns)) {
+            return false;
+        } else if global == Some(Action::Up) {
+            self.cursor = self.cursor.saturating_sub(1);
+        } else if global == Some(Action::Down) {
+            self.cursor = (self.cursor + 1).min(entries.len().saturating_sub(1));
+        } else if action == Some(Action::Fold) {
+            if shown {
+                if builder.labels.len() > 1 {
+                    builder.labels.retain(|e| *e != cell);
+                    self.cursor = builder.labels.len();
+                }
+            } else {
+                builder.labels.push(cell);
+                self.cursor = builder.labels.len() - 1;
+            }
+            builder.save();
+        } else if is_only_char(key, '[') && shown && self.cursor > 0 {
+            builder.labels.swap(self.cursor, self.cursor - 1);
+            self.cursor -= 1;
+            builder.save();
+        } else if is_only_char(key, ']') && shown && self.cursor + 1 < builder.labels.len() {
+            builder.labels.swap(self.cursor, self.cursor + 1);
+            self.cursor += 1;
+            builder.save();
+        }
+
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, builder: &LineBuilder) {
+        let entries = Self::entries(builder);
+
+        let width = 40.min(area.width);
+        let height = (entries.len() as u16 + 3).min(area.height);
+        let rect = Rect {
+            x: area.right().saturating_sub(width),
+            y: area.y,
+            width,
+            height,
+        };
+        frame.render_widget(Clear, rect);
+
+        let palette = theme::palette();
+        let mut lines: Vec<Line> = entries
+            .iter()
+            .enumerate()
+            .map(|(idx, (cell, shown))| {
+                let line = Line::raw(format!(
+                    "[{}] {:<8} {}",
+                    if *shown { 'x' } else { ' ' },
+                    cell.label(),
+                    cell.description()
+                ));
+                if idx == self.cursor {
+                    line.patch_style(palette.selection())
+                } else {
+                    line
+                }
+            })
+            .collect();
+        lines.push(
+            Line::raw(format!(
+                "{} toggle  [ ] move  {} close",
+                keymap::keys(Action::Fold),
+                keymap::keys(Action::Back)
+            ))
+            .style(palette.hint()),
+        );
+
+        let inner = Rect {
+            x: rect.x.saturating_add(1),
+            y: rect.y.saturating_add(1),
+            width: rect.width.saturating_sub(2),
+            height: rect.height.saturating_sub(2),
+        };
+        frame.render_widget(Text::from(lines), inner);
+        render_border(true, rect, frame.buffer_mut());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use unicode_width::UnicodeWidthStr;
+
+    use super::ProcessCell;
+
+    #[test]
+    fn test_keep_width() {
+        let cell = ProcessCell::PRG;
+        assert_eq!(cell.keep_width("firefox"), "firefox    ");
+        // wider than the column, the 3 byte chars are cut between two chars
+        let name = cell.keep_width("系统监视器");
+        assert_eq!(name, "系统监视   ");
+        assert_eq!(name.width(), 11);
+        assert_eq!(cell.keep_width("monitor-系统"), "monitor-   ");
+    }
+}
@@ -0,0 +1,269 @@
+use chin_tools::AResult;
+use itertools::Itertools;
+use ratatui::{
+    style::{Modifier, Stylize},
+    text::Line,
+};
+
+use crate::{
+    component::{
+        grouped_lines::GroupedLines, ls_history_graph, stateful_lines::StatefulGroupedLines,
+    },
+    ring::Ring,
+    sensor::{
+        process::ProcessItem,
+        process_detail::ProcessDetail,
+        units::{convert_seconds, convert_storage},
+    },
+    tarits::{None2NaN, None2NanString},
+    view::theme::SharedTheme,
+};
+
+/// The detail page of a single process, opened with Enter on a process row.
+#[derive(Debug)]
+pub struct DetailView {
+    pub pid: i32,
+    item: Option<ProcessItem>,
+    detail: Option<Box<ProcessDetail>>,
+    exited: bool,
+
+    cpu_history: Ring<f64>,
+    mem_history: Ring<f64>,
+    highest_mem: f64,
+
+    pub viewer_state: StatefulGroupedLines<'static>,
+}
+
+impl DetailView {
+    pub fn new(pid: i32) -> Self {
+        Self {
+            pid,
+            item: None,
+            detail: None,
+            exited: false,
+            cpu_history: Ring::new(1000),
+            mem_history: Ring::new(1000),
+            highest_mem: 0.,
+            viewer_state: Default::default(),
+        }
+    }
+
+    pub fn update(&mut self, item: Option<ProcessItem>, detail: Option<Box<ProcessDetail>>) {
+        match (item, detail) {
+            (Some(item), Some(detail)) => {
+                self.cpu_history.insert_at_first(item.cpu_time_ratio as f64);
+                self.mem_history.insert_at_first(item.memory_usage as f64);
+                self.highest_mem = self.highest_mem.max(item.memory_usage as f64);
+                self.item.replace(item);
+                self.detail.replace(detail);
+            }
+            _ => {
+                self.exited = true;
+            }
+        }
+    }
+
+    pub fn build(&mut self, width: u16, theme: &SharedTheme, active: bool) -> AResult<()> {
+        let mut blocks = vec![];
+
+        let title = self.item.as_ref().map_or(self.pid.to_string(), |item| {
+            format!("{} {}", item.pid, item.display_name)
+        });
+
+        let mut overview = GroupedLines::builder(width, theme);
+        if self.exited {
+            overview = overview.kv("Status", "Exited");
+        }
+        if let Some(item) = self.item.as_ref() {
+            overview = overview
+                .multi_kv_single_line(vec![
+                    ("PID", item.pid.to_string()),
+                    ("PPID", item.ppid.to_string()),
+                    ("User", item.user.clone()),
+                    ("State", item.state.to_string()),
+                ])
+                .multi_kv_single_line(vec![
+                    (
+                        "Threads",
+                        self.detail
+                            .as_ref()
+                            .and_then(|e| e.threads)
+                            .map(|e| e.to_string())
+                            .or_nan_owned(),
+                    ),
+                    ("Started", convert_seconds(item.starttime as u64)),
+                    (
+                        "CPU Time",
+                        convert_seconds((item.user_cpu_time + item.system_cpu_time) as u64),
+                    ),
+                ])
+                .kv_sep("CPU", format!("{:.1} %", item.cpu_time_ratio * 100.))
+                .lines(ls_history_graph(
+                    width.saturating_sub(2),
+                    &self.cpu_history,
+                    1.,
+                    0.,
+                    3,
+                    theme.palette().cpu,
+                ))
+                .kv_sep("Memory", convert_storage(item.memory_usage as f64, false))
+                .lines(ls_history_graph(
+                    width.saturating_sub(2),
+                    &self.mem_history,
+                    self.highest_mem.max(1.),
+                    0.,
+                    3,
+                    theme.palette().memory,
+                ));
+        }
+        blocks.push(overview.active(active).build(title)?);
+
+        let Some(detail) = self.detail.as_ref() else {
+            self.viewer_state.update_blocks(blocks);
+            return Ok(());
+        };
+
+        if !detail.tasks.is_empty() {
+            let header = format!(
+                "{:<9}{:<17}{:<3}{:>6}{:>6}{:>12}{:>12}",
+                "TID", "NAME", "S", "CPU", "CORE", "VCSW", "NVCSW"
+            );
+            let mut lines = vec![Line::raw(header)
+                .add_modifier(Modifier::BOLD)
+                .patch_style(theme.palette().header())];
+            for task in detail.tasks.iter() {
+                lines.push(
+                    Line::raw(format!(
+                        "{:<9}{:<17}{:<3}{:>6.1}{:>6}{:>12}{:>12}",
+                        task.tid,
+                        task.comm.chars().take(16).collect::<String>(),
+                        task.state,
+                        task.cpu_time_ratio * 100.,
+                        task.processor,
+                        task.voluntary_ctxt_switches.or_nan(|e| e.to_string()),
+                        task.nonvoluntary_ctxt_switches.or_nan(|e| e.to_string()),
+                    ))
+                    .fg(theme.fg()),
+                );
+            }
+            blocks.push(
+                GroupedLines::builder(width, theme)
+                    .lines(lines)
+                    .active(active)
+                    .build(format!("Threads ({})", detail.tasks.len()))?,
+            );
+        }
+
+        blocks.push(
+            GroupedLines::builder(width, theme)
+                .kv("Command Line", detail.cmdline.join(" "))
+                .kv(
+                    "Cwd",
+                    detail
+                        .cwd
+                        .as_ref()
+                        .map(|e| e.to_string_lossy().to_string())
+                        .or_nan_owned(),
+                )
+                .kv(
+                    "Executable",
+                    detail
+                        .exe
+                        .as_ref()
+                        .map(|e| e.to_string_lossy().to_string())
+                        .or_nan_owned(),
+                )
+                .active(active)
+                .build("Command")?,
+        );
+
+        if let Some(smaps) = detail.smaps.as_ref() {
+            blocks.push(
+                GroupedLines::builder(width, theme)
+                    .multi_kv_single_line(vec![
+                        ("RSS", convert_storage(smaps.rss as f64, false)),
+                        ("PSS", convert_storage(smaps.pss as f64, false)),
+                        ("USS", convert_storage(smaps.uss as f64, false)),
+                        ("Swap", convert_storage(smaps.swap as f64, false)),
+                    ])
+                    .active(active)
+                    .build("Memory Maps")?,
+            );
+        }
+
+        if !detail.io.is_empty() {
+            let mut io = GroupedLines::builder(width, theme);
+            for (key, value) in detail.io.iter() {
+                let value = if key.ends_with("bytes") || key.ends_with("char") {
+                    convert_storage(*value as f64, false)
+                } else {
+                    value.to_string()
+                };
+                io = io.kv(key, value);
+            }
+            blocks.push(io.active(active).build("I/O")?);
+        }
+
+        if !detail.gpus.is_empty() {
+            let mut gpus = GroupedLines::builder(width, theme);
+            for (slot, usage) in detail.gpus.iter() {
+                gpus = gpus.kv(
+                    &slot.to_string(),
+                    format!(
+                        "GPU {:.1} % - ENC {:.1} % - DEC {:.1} % - VRAM {}",
+                        usage.gpu * 100.,
+                        usage.enc * 100.,
+                        usage.dec * 100.,
+                        convert_storage(usage.mem as f64, false)
+                    ),
+                );
+            }
+            blocks.push(gpus.active(active).build("GPU")?);
+        }
+
+        let mut cgroup = GroupedLines::builder(width, theme);
+        for line in detail.cgroup.iter() {
+            cgroup = cgroup.value(line);
+        }
+        blocks.push(cgroup.active(active).build("Cgroup")?);
+
+        let mut namespaces = GroupedLines::builder(width, theme);
+        for (name, link) in detail.namespaces.iter() {
+            namespaces = namespaces.kv(name, link);
+        }
+        blocks.push(namespaces.active(active).build("Namespaces")?);
+
+        let mut limits = GroupedLines::builder(width, theme);
+        for limit in detail.limits.iter() {
+            limits = limits.kv(
+                &limit.name,
+                format!("{} / {} {}", limit.soft, limit.hard, limit.unit)
+                    .trim()
+                    .to_string(),
+            );
+        }
+        blocks.push(limits.active(active).build("Limits (Soft / Hard)")?);
+
+        let mut fds = GroupedLines::builder(width, theme);
+        for (fd, target) in detail.fds.iter() {
+            fds = fds.kv(&fd.to_string(), target);
+        }
+        blocks.push(
+            fds.active(active)
+                .build(format!("File Descriptors ({})", detail.fds.len()))?,
+        );
+
+        let mut environ = GroupedLines::builder(width, theme);
+        for env in detail.environ.iter().sorted() {
+            environ = environ.value(env);
+        }
+        blocks.push(
+            environ
+                .active(active)
+                .build(format!("Environment ({})", detail.environ.len()))?,
+        );
+
+        self.viewer_state.update_blocks(blocks);
+        Ok(())
+    }
+}
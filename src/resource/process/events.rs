@@ -0,0 +1,98 @@
+use std::sync::Arc;
+
+use ratatui::{
+    style::{Modifier, Stylize},
+    text::{Line, Span},
+};
+
+use crate::{
+    component::stateful_lines::StatefulColumn,
+    sensor::{
+        process_events::{ProcessEvent, ProcessEventKind},
+        units::convert_seconds,
+    },
+    view::theme::{self, SharedTheme},
+};
+
+/// The "Process events" panel, a log of processes started and exited while
+/// restop is running, newest first.
+#[derive(Debug)]
+pub struct EventsView {
+    events: Option<Arc<Vec<ProcessEvent>>>,
+    pub view_state: StatefulColumn<'static>,
+}
+
+fn format_duration(seconds: f64) -> String {
+    if seconds < 60. {
+        format!("{:.2}s", seconds)
+    } else {
+        convert_seconds(seconds as u64)
+    }
+}
+
+impl EventsView {
+    pub fn new() -> Self {
+        Self {
+            events: None,
+            view_state: StatefulColumn::new(),
+        }
+    }
+
+    pub fn update(&mut self, events: Arc<Vec<ProcessEvent>>) {
+        self.events.replace(events);
+    }
+
+    fn to_header() -> Line<'static> {
+        Line::raw(format!(
+            "{:<14}{:<7}{:<9}{:<10}{:>10}{:>10}  {}",
+            "TIME", "EVENT", "PID", "USER", "LIFETIME", "CPUTIME", "CMD"
+        ))
+        .add_modifier(Modifier::BOLD)
+        .patch_style(theme::palette().header())
+    }
+
+    fn to_line(event: &ProcessEvent, active: bool, theme: &SharedTheme) -> Line<'static> {
+        let item = &event.item;
+        let palette = theme.palette();
+        let (kind, color) = match event.kind {
+            ProcessEventKind::Started => ("start", palette.low),
+            ProcessEventKind::Exited => ("exit", palette.high),
+        };
+        let (lifetime, cpu_time) = match event.kind {
+            ProcessEventKind::Started => ("-".to_string(), "-".to_string()),
+            ProcessEventKind::Exited => (
+                event.lifetime.map_or("-".to_string(), format_duration),
+                format_duration(event.cpu_time()),
+            ),
+        };
+
+        let line = Line::from(vec![
+            Span::raw(format!("{:<14}", event.time.format("%H:%M:%S%.3f"))),
+            Span::raw(format!("{:<7}", kind)).fg(color),
+            Span::raw(format!(
+                "{:<9}{:<10}{:>10}{:>10}  {}",
+                item.pid,
+                item.user.chars().take(9).collect::<String>(),
+                lifetime,
+                cpu_time,
+                item.commandline
+            )),
+        ])
+        .fg(theme.fg());
+
+        if active {
+            line.patch_style(palette.selection())
+        } else {
+            line
+        }
+    }
+
+    pub fn build(&mut self, height: u16, theme: &SharedTheme) {
+        self.view_state.set_header(Self::to_header());
+        self.view_state.update_view_height(height);
+        if let Some(events) = self.events.as_ref() {
+            self.view_state
+                .update_lines(events, |e, active| Self::to_line(e, active, theme));
+        }
+    }
+}
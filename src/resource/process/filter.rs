@@ -0,0 +1,454 @@
+//! Query language of the process filter, e.g.
+//! `user:postgres cpu>5 mem>1G name~^java`.
+//!
+//! Terms are `field` `op` `value`, a term without a known field, e.g.
+//! `localhost:8080`, searches the command line. Terms next to each other are
+//! ANDed, `OR`/`||`, `AND`/`&&`, `NOT`/`!` and parentheses work as usual.
+//!
+//! - `:` contains (case insensitive) for text, equals for numbers
+//! - `=`, `!=` equals, not equals
+//! - `~`, `!~` regex match, no match
+//! - `>`, `>=`, `<`, `<=` numeric comparison, sizes accept `K`, `M`, `G`, `T`
+
+use std::str::FromStr;
+
+use anyhow::{bail, Context, Result};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use strum_macros::{Display, EnumString};
+
+use crate::sensor::{process::ProcessItem, units::parse_size};
+
+static RE_TERM: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^([A-Za-z_]+)(>=|<=|!=|!~|=|:|~|>|<)(.*)$").unwrap());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+pub enum Field {
+    Pid,
+    Ppid,
+    User,
+    Name,
+    Cmd,
+    State,
+    Nice,
+    Cpu,
+    Mem,
+    Read,
+    Write,
+    Gpu,
+    Vram,
+    Enc,
+    Dec,
+    Cgroup,
+    Start,
+    Cputime,
+}
+
+impl Field {
+    fn number(&self, item: &ProcessItem) -> Option<f64> {
+        let n = match self {
+            Field::Pid => item.pid as f64,
+            Field::Ppid => item.ppid as f64,
+            Field::Nice => item.nice as f64,
+            Field::Cpu => item.cpu_time_ratio as f64 * 100.,
+            Field::Mem => item.memory_usage as f64,
+            Field::Read => item.read_speed?,
+            Field::Write => item.write_speed?,
+            Field::Gpu => item.gpu_usage as f64 * 100.,
+            Field::Vram => item.gpu_mem_usage as f64,
+            Field::Enc => item.enc_usage as f64 * 100.,
+            Field::Dec => item.dec_usage as f64 * 100.,
+            Field::Start => item.starttime,
+            Field::Cputime => item.user_cpu_time + item.system_cpu_time,
+            Field::User | Field::Name | Field::Cmd | Field::State | Field::Cgroup => return None,
+        };
+        Some(n)
+    }
+
+    fn is_numeric(&self) -> bool {
+        !matches!(
+            self,
+            Field::User | Field::Name | Field::Cmd | Field::State | Field::Cgroup
+        )
+    }
+
+    fn text(&self, item: &ProcessItem) -> Option<String> {
+        match self {
+            Field::User => Some(item.user.clone()),
+            Field::Name => Some(item.display_name.clone()),
+            Field::Cmd => Some(item.commandline.clone()),
+            Field::State => Some(item.state.to_string()),
+            Field::Cgroup => item.cgroup.clone(),
+            _ => self.number(item).map(|n| n.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Cmp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl Cmp {
+    fn test(&self, left: f64, right: f64) -> bool {
+        match self {
+            Cmp::Eq => left == right,
+            Cmp::Ne => left != right,
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ProcessFilter {
+    And(Box<ProcessFilter>, Box<ProcessFilter>),
+    Or(Box<ProcessFilter>, Box<ProcessFilter>),
+    Not(Box<ProcessFilter>),
+    /// Bare word, searched in the command line.
+    Search(String),
+    Contains(Field, String),
+    Equals(Field, String),
+    Regex(Field, #[serde(with = "regex_pattern")] Regex),
+    Number(Field, Cmp, f64),
+}
+
+impl ProcessFilter {
+    pub fn matches(&self, item: &ProcessItem) -> bool {
+        match self {
+            ProcessFilter::And(l, r) => l.matches(item) && r.matches(item),
+            ProcessFilter::Or(l, r) => l.matches(item) || r.matches(item),
+            ProcessFilter::Not(e) => !e.matches(item),
+            ProcessFilter::Search(s) => item.commandline.contains(s.as_str()),
+            ProcessFilter::Contains(field, s) => field
+                .text(item)
+                .is_some_and(|t| t.to_lowercase().contains(s.as_str())),
+            ProcessFilter::Equals(field, s) => field.text(item).is_some_and(|t| t == *s),
+            ProcessFilter::Regex(field, re) => field.text(item).is_some_and(|t| re.is_match(&t)),
+            ProcessFilter::Number(field, cmp, n) => {
+                field.number(item).is_some_and(|v| cmp.test(v, *n))
+            }
+        }
+    }
+}
+
+impl FromStr for ProcessFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let filter = parser.parse_or()?;
+        if let Some(token) = parser.tokens.get(parser.pos) {
+            bail!("unexpected {}", token)
+        }
+        Ok(filter)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    LParen,
+    RParen,
+    Word { text: String, quoted: bool },
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::LParen => write!(f, "`(`"),
+            Token::RParen => write!(f, "`)`"),
+            Token::Word { text, .. } => write!(f, "`{}`", text),
+        }
+    }
+}
+
+impl Token {
+    fn is_keyword(&self, keywords: &[&str]) -> bool {
+        match self {
+            Token::Word {
+                text,
+                quoted: false,
+            } => keywords.iter().any(|k| k.eq_ignore_ascii_case(text)),
+            _ => false,
+        }
+    }
+}
+
+/// Splits on whitespace, `(` only opens a group at the start of a word and
+/// `)` only closes one if it is unbalanced inside the word, so regexes like
+/// `name~^(foo|bar)` stay in one piece.
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let mut tokens = vec![];
+    let mut chars = s.chars().peekable();
+
+    while let Some(c) = chars.peek().cloned() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '(' {
+            chars.next();
+            tokens.push(Token::LParen);
+        } else if c == ')' {
+            chars.next();
+            tokens.push(Token::RParen);
+        } else {
+            let mut text = String::new();
+            let mut depth = 0;
+            let quoted = c == '"';
+
+            while let Some(c) = chars.peek().cloned() {
+                if c.is_whitespace() || (c == ')' && depth == 0) {
+                    break;
+                }
+                chars.next();
+                match c {
+                    '"' => loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => text.push(c),
+                            None => bail!("unclosed `\"`"),
+                        }
+                    },
+                    '(' => {
+                        depth += 1;
+                        text.push(c);
+                    }
+                    ')' => {
+                        depth -= 1;
+                        text.push(c);
+                    }
+                    _ => text.push(c),
+                }
+            }
+
+            tokens.push(Token::Word { text, quoted });
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn parse_or(&mut self) -> Result<ProcessFilter> {
+        let mut left = self.parse_and()?;
+        while self.peek().is_some_and(|t| t.is_keyword(&["OR", "||"])) {
+            self.pos += 1;
+            let right = self.parse_and()?;
+            left = ProcessFilter::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<ProcessFilter> {
+        let mut left = self.parse_not()?;
+        loop {
+            match self.peek() {
+                None | Some(Token::RParen) => break,
+                Some(t) if t.is_keyword(&["OR", "||"]) => break,
+                Some(t) if t.is_keyword(&["AND", "&&"]) => {
+                    self.pos += 1;
+                }
+                _ => {}
+            }
+            let right = self.parse_not()?;
+            left = ProcessFilter::And(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<ProcessFilter> {
+        match self.peek() {
+            Some(t) if t.is_keyword(&["NOT", "!"]) => {
+                self.pos += 1;
+                Ok(ProcessFilter::Not(Box::new(self.parse_not()?)))
+            }
+            Some(Token::Word {
+                text,
+                quoted: false,
+            }) if text.len() > 1 && text.starts_with('!') => {
+                let term = parse_term(&text[1..])?;
+                self.pos += 1;
+                Ok(ProcessFilter::Not(Box::new(term)))
+            }
+            _ => self.parse_primary(),
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<ProcessFilter> {
+        let token = self.peek().cloned().context("expected a term")?;
+        self.pos += 1;
+        match token {
+            Token::LParen => {
+                let filter = self.parse_or()?;
+                match self.peek() {
+                    Some(Token::RParen) => {
+                        self.pos += 1;
+                        Ok(filter)
+                    }
+                    _ => bail!("expected `)`"),
+                }
+            }
+            Token::RParen => bail!("unexpected `)`"),
+            Token::Word { text, quoted: true } => Ok(ProcessFilter::Search(text)),
+            Token::Word {
+                text,
+                quoted: false,
+            } => {
+                if ["AND", "OR", "&&", "||"]
+                    .iter()
+                    .any(|k| k.eq_ignore_ascii_case(&text))
+                {
+                    bail!("expected a term before `{}`", text)
+                }
+                parse_term(&text)
+            }
+        }
+    }
+}
+
+fn parse_term(text: &str) -> Result<ProcessFilter> {
+    let Some(captures) = RE_TERM.captures(text) else {
+        return Ok(ProcessFilter::Search(text.to_string()));
+    };
+    let (name, op, value) = (&captures[1], &captures[2], &captures[3]);
+
+    let Ok(field) = Field::from_str(&name.to_lowercase()) else {
+        return Ok(ProcessFilter::Search(text.to_string()));
+    };
+    if value.is_empty() {
+        bail!("missing value after `{}{}`", name, op)
+    }
+
+    let filter = match op {
+        "~" | "!~" => {
+            let re = Regex::new(value).with_context(|| format!("invalid regex `{}`", value))?;
+            let filter = ProcessFilter::Regex(field, re);
+            if op == "!~" {
+                ProcessFilter::Not(Box::new(filter))
+            } else {
+                filter
+            }
+        }
+        ":" | "=" | "!=" if !field.is_numeric() => {
+            let filter = if op == ":" {
+                ProcessFilter::Contains(field, value.to_lowercase())
+            } else {
+                ProcessFilter::Equals(field, value.to_string())
+            };
+            if op == "!=" {
+                ProcessFilter::Not(Box::new(filter))
+            } else {
+                filter
+            }
+        }
+        _ => {
+            if !field.is_numeric() {
+                bail!("`{}` can not be compared with `{}`", field, op)
+            }
+            let cmp = match op {
+                ":" | "=" => Cmp::Eq,
+                "!=" => Cmp::Ne,
+                ">" => Cmp::Gt,
+                ">=" => Cmp::Ge,
+                "<" => Cmp::Lt,
+                _ => Cmp::Le,
+            };
+            ProcessFilter::Number(field, cmp, parse_size(value)?)
+        }
+    };
+
+    Ok(filter)
+}
+
+/// Regexes are sent to a remote agent as their pattern.
+mod regex_pattern {
+    use regex::Regex;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(re: &Regex, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(re.as_str())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+        let pattern = String::deserialize(deserializer)?;
+        Regex::new(&pattern).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::str::FromStr;
+
+    use crate::sensor::process::ProcessItem;
+
+    use super::ProcessFilter;
+
+    fn item(pid: i32, user: &str, cmd: &str, cpu: f32, mem: usize) -> ProcessItem {
+        ProcessItem {
+            pid,
+            user: user.to_string(),
+            display_name: cmd.split(' ').next().unwrap_or_default().to_string(),
+            commandline: cmd.to_string(),
+            cpu_time_ratio: cpu,
+            memory_usage: mem,
+            ..Default::default()
+        }
+    }
+
+    fn matches(query: &str, item: &ProcessItem) -> bool {
+        ProcessFilter::from_str(query).unwrap().matches(item)
+    }
+
+    #[test]
+    fn test_filter() {
+        let postgres = item(10, "postgres", "postgres -D /var/lib/pg", 0.2, 2 << 30);
+        let java = item(11, "alice", "java -jar app.jar", 0.01, 512 << 20);
+
+        assert!(matches("postgres", &postgres));
+        assert!(matches("user:postgres cpu>5 mem>1G", &postgres));
+        assert!(!matches("user:postgres cpu>50", &postgres));
+        assert!(matches("name~^java", &java));
+        assert!(!matches("name~^java", &postgres));
+        assert!(matches("user:alice OR user:postgres", &postgres));
+        assert!(matches("NOT user:alice", &postgres));
+        assert!(matches("!user:alice", &postgres));
+        assert!(matches("(pid=11 || pid=10) && mem<=512M", &java));
+        assert!(matches("name~^(java|node)", &java));
+        assert!(matches("user!=root", &java));
+        assert!(matches("\"-jar app\"", &java));
+
+        let curl = item(12, "alice", "curl http://localhost:8080", 0., 0);
+        assert!(matches("localhost:8080", &curl));
+        assert!(matches("user:alice http://", &curl));
+        assert!(!matches("localhost:8080", &java));
+    }
+
+    #[test]
+    fn test_filter_error() {
+        assert!(ProcessFilter::from_str("cpu>").is_err());
+        assert!(ProcessFilter::from_str("cpu>abc").is_err());
+        assert!(ProcessFilter::from_str("user>5").is_err());
+        assert!(ProcessFilter::from_str("name~(").is_err());
+        assert!(ProcessFilter::from_str("(cpu>5").is_err());
+        assert!(ProcessFilter::from_str("cpu>5 OR").is_err());
+        assert!(ProcessFilter::from_str("\"unclosed").is_err());
+    }
+}
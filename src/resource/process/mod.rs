@@ -0,0 +1,1291 @@
+mod columns;
+mod detail;
+mod events;
+mod filter;
+mod tune;
+
+use std::{
+    cell::Cell,
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, RwLock},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Context;
+use chin_tools::AResult;
+use crossterm::event::KeyCode;
+use flume::{Receiver, RecvTimeoutError, Sender};
+
+use itertools::Itertools;
+use nix::errno::Errno;
+use once_cell::sync::Lazy;
+use process_data::ProcessData;
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app::ResourceEvent,
+    component::{
+        grouped_lines::GroupedLines,
+        input::Input,
+        render_border,
+        stateful_lines::{StatefulColumn, StatefulLinesType},
+    },
+    ring::Ring,
+    sensor::{
+        apps::{AppItem, AppsContext},
+        process::{
+            read_proc_loadavg, read_proc_uptime, LoadAvg, ProcessAction, ProcessItem, ThreadItem,
+        },
+        process_detail::ProcessDetail,
+        process_events::ProcessEvent,
+        root,
+        units::convert_seconds,
+    },
+    tarits::{None2NaN, None2NanString},
+    utils::{is_esc, is_only_char},
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme::{self, SharedTheme},
+        NavigatorEvent, OverviewArg, PageArg,
+    },
+};
+
+use columns::{ColumnChooser, LineBuilder, ProcessCell};
+use detail::DetailView;
+use events::EventsView;
+use filter::ProcessFilter;
+use tune::{ProcessTune, TuneDialog, TuneKind};
+
+use super::{Resource, SensorResultType};
+
+pub const PROCESS_ID: &'static str = "PROCESS";
+/// How often `/proc` is listed for new processes between two refreshes.
+const PROCESS_SCAN_INTERVAL: Duration = Duration::from_millis(100);
+/// How many process tables are kept to look back at while frozen, fewer than
+/// the other histories since every one holds the whole table.
+pub const PROCESS_SNAPSHOTS: usize = 120;
+
+static PROCESS_WORKER_CHANNEL: Lazy<(Sender<ProcessMsg>, Receiver<ProcessMsg>)> =
+    Lazy::new(|| flume::unbounded());
+static PROCESS_SORT_TYPE: Lazy<RwLock<Option<(ProcessCell, bool)>>> =
+    Lazy::new(|| RwLock::new(None));
+
+fn get_process_sort() -> Option<(ProcessCell, bool)> {
+    PROCESS_SORT_TYPE.read().unwrap().clone()
+}
+
+fn try_change_sort(c: ProcessCell) {
+    let sort = get_process_sort();
+
+    if let Ok(mut write) = PROCESS_SORT_TYPE.write() {
+        if let Some((cell, desc)) = sort {
+            write.replace((c, if cell == c { !desc } else { true }));
+        } else {
+            write.replace((c, true));
+        }
+        let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Sort(*write));
+    }
+}
+
+/// Hands a message of a remote client to the process worker.
+pub fn send_to_worker(msg: ProcessMsg) {
+    let _ = PROCESS_WORKER_CHANNEL.0.send(msg);
+}
+
+/// The messages for the process worker, taken by the connection to a remote
+/// agent when there is no local worker.
+pub fn worker_requests() -> Receiver<ProcessMsg> {
+    PROCESS_WORKER_CHANNEL.1.clone()
+}
+
+#[derive(Debug)]
+pub struct ResProcess {
+    /// The process table of the last samples, to show it while frozen.
+    data: Ring<Arc<Vec<ProcessRow>>>,
+    loadavg: Option<LoadAvg>,
+    uptime: Cell<u64>,
+    theme: SharedTheme,
+
+    view_state: StatefulColumn<'static>,
+    line_builder: LineBuilder,
+    filter: Option<Input>,
+    filter_error: Option<String>,
+    signal_dialog: Option<SignalDialog>,
+    tune_dialog: Option<TuneDialog>,
+    column_chooser: Option<ColumnChooser>,
+    detail: Option<DetailView>,
+    events: Option<EventsView>,
+}
+
+/// The first three are the periodic readings, the rest answers the actions
+/// of the user.
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ProcessRsp {
+    Processes(Arc<Vec<ProcessRow>>),
+    LoadAvg(LoadAvg),
+    Uptime(u64),
+    Action(SignalTarget, ProcessAction, Result<(), ActionFailure>),
+    Tune(i32, Result<(), ActionFailure>),
+    /// Both are `None` if the process has exited.
+    Detail(i32, Option<ProcessItem>, Option<Box<ProcessDetail>>),
+    Events(Arc<Vec<ProcessEvent>>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessView {
+    Flat,
+    Tree,
+    Apps,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignalTarget {
+    Process(i32),
+    /// Every process of the app with this id.
+    App(String),
+}
+
+/// One line of the process table. In tree mode `prefix` holds the indentation
+/// guides, and a folded row carries the summed usage of its whole subtree.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessRow {
+    pub item: ProcessItem,
+    pub prefix: String,
+    /// `Some(folded)` if this row has children in tree or apps mode.
+    pub fold: Option<bool>,
+    /// Set if this row stands for a whole app in apps mode.
+    pub app: Option<AppRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppRow {
+    /// `None` for the "System Processes" group.
+    pub id: Option<String>,
+    pub processes_amount: usize,
+}
+
+impl ProcessRow {
+    fn flat(item: ProcessItem) -> Self {
+        Self {
+            item,
+            prefix: String::new(),
+            fold: None,
+            app: None,
+        }
+    }
+
+    fn from_app(app: AppItem, expanded: bool) -> Self {
+        let item = ProcessItem {
+            display_name: app.display_name.clone(),
+            commandline: app.display_name,
+            memory_usage: app.memory_usage,
+            cpu_time_ratio: app.cpu_time_ratio,
+            containerization: app.containerization,
+            read_speed: Some(app.read_speed),
+            read_total: Some(app.read_total),
+            write_speed: Some(app.write_speed),
+            write_total: Some(app.write_total),
+            gpu_usage: app.gpu_usage,
+            enc_usage: app.enc_usage,
+            dec_usage: app.dec_usage,
+            gpu_mem_usage: app.gpu_mem_usage,
+            ..Default::default()
+        };
+
+        Self {
+            item,
+            prefix: String::new(),
+            fold: Some(!expanded),
+            app: Some(AppRow {
+                id: app.id,
+                processes_amount: app.processes_amount,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActionFailure {
+    pub message: String,
+    pub permission_denied: bool,
+}
+
+#[derive(Debug)]
+enum SignalDialogState {
+    Confirm,
+    Pending,
+    Failed(ActionFailure),
+}
+
+/// Confirmation line shown at the bottom of the process table before a
+/// signal is sent, it also carries the error if the signal failed.
+#[derive(Debug)]
+struct SignalDialog {
+    target: SignalTarget,
+    label: String,
+    action: ProcessAction,
+    state: SignalDialogState,
+}
+
+impl SignalDialog {
+    /// The "System Processes" group can not be signaled as a whole.
+    fn new(row: &ProcessRow) -> Option<Self> {
+        let (target, label) = match row.app.as_ref() {
+            Some(app) => (
+                SignalTarget::App(app.id.clone()?),
+                format!(
+                    "{} ({} processes)",
+                    row.item.display_name, app.processes_amount
+                ),
+            ),
+            None => (
+                SignalTarget::Process(row.item.pid),
+                format!(
+                    "{} ({}, {})",
+                    row.item.pid, row.item.display_name, row.item.user
+                ),
+            ),
+        };
+
+        Some(Self {
+            target,
+            label,
+            action: ProcessAction::TERM,
+            state: SignalDialogState::Confirm,
+        })
+    }
+
+    fn send(&mut self, elevated: bool) {
+        self.state = SignalDialogState::Pending;
+        let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Signal {
+            target: self.target.clone(),
+            action: self.action,
+            elevated,
+        });
+    }
+
+    /// Returns false when the dialog should be closed.
+    fn handle_key(&mut self, key: &crossterm::event::KeyEvent) -> bool {
+        match &self.state {
+            SignalDialogState::Confirm => {
+                if is_only_char(key, 'y') || key.code == KeyCode::Enter {
+                    self.send(false);
+                } else if is_only_char(key, 'n') || is_esc(key) {
+                    return false;
+                } else if is_only_char(key, 't') {
+                    self.action = ProcessAction::TERM;
+                } else if is_only_char(key, 'k') {
+                    self.action = ProcessAction::KILL;
+                } else if is_only_char(key, 's') {
+                    self.action = ProcessAction::STOP;
+                } else if is_only_char(key, 'c') {
+                    self.action = ProcessAction::CONT;
+                }
+            }
+            SignalDialogState::Pending => {
+                if is_esc(key) {
+                    return false;
+                }
+            }
+            SignalDialogState::Failed(failure) => {
+                if failure.permission_denied && is_only_char(key, 'p') {
+                    self.send(true);
+                } else if is_esc(key) || is_only_char(key, 'n') || key.code == KeyCode::Enter {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    fn to_line(&self) -> Line<'static> {
+        let target = &self.label;
+        let signal = self.action.signal();
+        match &self.state {
+            SignalDialogState::Confirm => Line::from(vec![
+                Span::raw(format!("Send {} to {}? ", signal, target)),
+                Span::raw("[y]es [n]o  [t]erm [k]ill [s]top [c]ont")
+                    .style(theme::palette().hint()),
+            ]),
+            SignalDialogState::Pending => Line::raw(format!("Sending {} to {}...", signal, target)),
+            SignalDialogState::Failed(failure) => {
+                let mut spans = vec![Span::raw(format!(
+                    "{} to {} failed: {}",
+                    signal, target, failure.message
+                ))
+                .style(theme::palette().alert())];
+                if failure.permission_denied {
+                    spans.push(
+                        Span::raw("  [p] retry with pkexec").style(theme::palette().hint()),
+                    );
+                }
+                Line::from(spans)
+            }
+        }
+    }
+}
+
+impl ResProcess {
+    pub fn spawn(theme: SharedTheme, result_tx: &Sender<ResourceEvent>) -> AResult<Self> {
+        ProcessWorker::spawn(result_tx)?;
+        Ok(Self::new(theme))
+    }
+
+    /// Without a worker, the rows only come from `update_data`. Actions are
+    /// never carried out, e.g. when replaying a recording.
+    pub fn new(theme: SharedTheme) -> Self {
+        Self {
+            data: Ring::new(PROCESS_SNAPSHOTS),
+            theme,
+            loadavg: Default::default(),
+            uptime: Default::default(),
+            view_state: StatefulColumn::new(),
+            line_builder: LineBuilder::new(),
+            filter: None,
+            filter_error: None,
+            signal_dialog: None,
+            tune_dialog: None,
+            column_chooser: None,
+            detail: None,
+            events: None,
+        }
+    }
+
+    fn selected_row(&self) -> Option<&ProcessRow> {
+        self.data
+            .newest()
+            .and_then(|data| data.get(self.view_state.cur_line()))
+    }
+}
+
+impl Resource for ResProcess {
+    type Req = ();
+
+    type Rsp = ProcessRsp;
+
+    fn do_sensor(_: Self::Req) -> AResult<SensorResultType> {
+        PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Detect)?;
+        Ok(SensorResultType::AsyncResult)
+    }
+
+    fn get_id(&self) -> &str {
+        PROCESS_ID
+    }
+
+    fn get_req(&self) -> Self::Req {
+        ()
+    }
+
+    fn overview_content(&self, args: &mut OverviewArg) -> AResult<GroupedLines<'static>> {
+        let width = args.width;
+        let block = GroupedLines::builder(width, &self.theme)
+            .kv("Uptime", convert_seconds(self.uptime.get()))
+            .kv("Load", self.loadavg.or_nan_owned())
+            .kv(
+                "Processes",
+                self.data.newest().or_nan(|e| {
+                    format!(
+                        "{} {}",
+                        e.len(),
+                        self.loadavg
+                            .as_ref()
+                            .map_or("".to_string(), |e| e.processes.to_string())
+                    )
+                }),
+            )
+            .active(args.focused)
+            .build("Process")?;
+
+        Ok(block)
+    }
+
+    fn _build_page(&mut self, args: &PageArg) -> AResult<String> {
+        self.view_state.set_header(self.line_builder.to_header());
+        self.view_state.update_view_height(args.rect.height);
+        if let Some(data) = self.data.newest() {
+            self.view_state
+                .update_lines(data, |e, s| self.line_builder.to_line(e, s));
+        }
+
+        Ok("Process".to_string())
+    }
+
+    fn update_data(&mut self, data: &Self::Rsp) {
+        match data {
+            ProcessRsp::Processes(process_data) => {
+                self.data.insert_at_first(process_data.clone());
+            }
+            ProcessRsp::LoadAvg(load) => {
+                self.loadavg.replace(load.clone());
+            }
+            ProcessRsp::Uptime(uptime) => {
+                self.uptime.set(*uptime);
+            }
+            ProcessRsp::Detail(pid, item, detail) => {
+                if let Some(view) = self.detail.as_mut().filter(|view| view.pid == *pid) {
+                    view.update(item.clone(), detail.clone());
+                }
+            }
+            ProcessRsp::Events(events) => {
+                if let Some(view) = self.events.as_mut() {
+                    view.update(events.clone());
+                }
+            }
+            ProcessRsp::Tune(pid, result) => {
+                if let Some(dialog) = self.tune_dialog.as_mut().filter(|e| e.pid == *pid) {
+                    match result {
+                        Ok(_) => {
+                            self.tune_dialog.take();
+                        }
+                        Err(failure) => dialog.failed(failure),
+                    }
+                }
+            }
+            ProcessRsp::Action(target, action, result) => {
+                if let Some(dialog) = self.signal_dialog.as_mut() {
+                    if dialog.target == *target && dialog.action == *action {
+                        match result {
+                            Ok(_) => {
+                                self.signal_dialog.take();
+                            }
+                            Err(failure) => {
+                                dialog.state = SignalDialogState::Failed(failure.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn cached_page_state<'b>(&'b mut self) -> StatefulLinesType<'static, 'b> {
+        match self.detail.as_mut() {
+            Some(detail) => StatefulLinesType::Groups(&mut detail.viewer_state),
+            None => match self.events.as_mut() {
+                Some(events) => StatefulLinesType::Lines(&mut events.view_state),
+                None => StatefulLinesType::Lines(&mut self.view_state),
+            },
+        }
+    }
+
+    fn get_type_name(&self) -> &'static str {
+        PROCESS_ID
+    }
+
+    fn handle_navi_event(&mut self, event: &NavigatorEvent) -> bool {
+        match event {
+            NavigatorEvent::KeyEvent(ke) => {
+                let action = keymap::lookup(ke, KeyContext::Process);
+                if self.detail.is_some() {
+                    if action == Some(Action::Back) {
+                        self.detail.take();
+                        let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Detail(None));
+                        return true;
+                    }
+                    return false;
+                }
+
+                if let Some(chooser) = self.column_chooser.as_mut() {
+                    if !chooser.handle_key(ke, &mut self.line_builder) {
+                        self.column_chooser.take();
+                    }
+                    return true;
+                }
+
+                if let Some(dialog) = self.signal_dialog.as_mut() {
+                    if !dialog.handle_key(ke) {
+                        self.signal_dialog.take();
+                    }
+                    return true;
+                }
+
+                if let Some(dialog) = self.tune_dialog.as_mut() {
+                    if !dialog.handle_key(ke) {
+                        self.tune_dialog.take();
+                    }
+                    return true;
+                }
+
+                if let Some(input) = self.filter.as_mut() {
+                    let handled = input.handle_event(ke);
+                    if handled {
+                        let query = input.get_input();
+                        if query.trim().is_empty() {
+                            self.filter_error.take();
+                            let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Filter(None));
+                        } else {
+                            // keep the last valid filter while the query is being typed
+                            match query.parse::<ProcessFilter>() {
+                                Ok(filter) => {
+                                    self.filter_error.take();
+                                    let _ = PROCESS_WORKER_CHANNEL
+                                        .0
+                                        .send(ProcessMsg::Filter(Some(filter)));
+                                }
+                                Err(err) => {
+                                    self.filter_error.replace(err.to_string());
+                                }
+                            }
+                        }
+                        return handled;
+                    }
+                };
+
+                if action == Some(Action::Back) {
+                    if self.filter.is_none() && self.events.take().is_some() {
+                        let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Events(false));
+                        return true;
+                    }
+                    self.filter.take();
+                    self.filter_error.take();
+                    let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Filter(None));
+                    return true;
+                }
+
+                if action == Some(Action::Filter) {
+                    self.filter.replace(Input::new());
+                    return true;
+                }
+
+                if action == Some(Action::Events) {
+                    let show = self.events.is_none();
+                    self.events = show.then(EventsView::new);
+                    let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Events(show));
+                    return true;
+                }
+
+                // the rest only applies to the process table
+                if self.events.is_some() {
+                    return false;
+                }
+
+                match action {
+                    Some(Action::SortPid) => try_change_sort(ProcessCell::PID),
+                    Some(Action::SortCpu) => try_change_sort(ProcessCell::CPU),
+                    Some(Action::SortMem) => try_change_sort(ProcessCell::MEM),
+                    Some(Action::SortCmd) => try_change_sort(ProcessCell::CMD),
+                    Some(Action::Columns) => {
+                        self.column_chooser.replace(ColumnChooser::default());
+                    }
+                    Some(Action::TreeView) => {
+                        let _ = PROCESS_WORKER_CHANNEL
+                            .0
+                            .send(ProcessMsg::ToggleView(ProcessView::Tree));
+                    }
+                    Some(Action::AppsView) => {
+                        let _ = PROCESS_WORKER_CHANNEL
+                            .0
+                            .send(ProcessMsg::ToggleView(ProcessView::Apps));
+                    }
+                    Some(Action::Fold) => {
+                        if let Some(row) = self.selected_row() {
+                            if let Some(app) = row.app.as_ref() {
+                                let _ = PROCESS_WORKER_CHANNEL
+                                    .0
+                                    .send(ProcessMsg::ToggleApp(app.id.clone()));
+                            } else if row.fold.is_some() {
+                                let _ = PROCESS_WORKER_CHANNEL
+                                    .0
+                                    .send(ProcessMsg::ToggleFold(row.item.pid));
+                            }
+                        }
+                    }
+                    Some(Action::Open) => {
+                        if let Some(row) = self.selected_row() {
+                            if let Some(app) = row.app.as_ref() {
+                                let _ = PROCESS_WORKER_CHANNEL
+                                    .0
+                                    .send(ProcessMsg::ToggleApp(app.id.clone()));
+                            } else {
+                                let pid = row.item.pid;
+                                self.detail.replace(DetailView::new(pid));
+                                let _ =
+                                    PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Detail(Some(pid)));
+                            }
+                        }
+                    }
+                    Some(Action::Signal) => {
+                        if let Some(dialog) = self.selected_row().and_then(SignalDialog::new) {
+                            self.signal_dialog.replace(dialog);
+                        }
+                    }
+                    Some(kind @ (Action::Renice | Action::Affinity)) => {
+                        let kind = if kind == Action::Renice {
+                            TuneKind::Nice
+                        } else {
+                            TuneKind::Affinity
+                        };
+                        if let Some(row) = self.selected_row().filter(|row| row.app.is_none()) {
+                            self.tune_dialog.replace(TuneDialog::new(&row.item, kind));
+                        }
+                    }
+                    _ => return false,
+                }
+                true
+            }
+        }
+    }
+
+    fn key_context(&self) -> Option<KeyContext> {
+        if self.filter.is_some() {
+            Some(KeyContext::Filter)
+        } else {
+            Some(KeyContext::Process)
+        }
+    }
+
+    fn get_name(&self) -> String {
+        "".to_string()
+    }
+
+    fn render_page(&mut self, frame: &mut ratatui::Frame, args: &PageArg, _max_width: u16) {
+        if let Some(detail) = self.detail.as_mut() {
+            if let Err(err) = detail.build(args.rect.width, &self.theme, args.active) {
+                tracing::error!("unable to build process detail: {}", err);
+                return;
+            }
+            detail.viewer_state.render(frame, args.rect, args.active);
+            return;
+        }
+
+        let inner = Rect {
+            x: args.rect.x.saturating_add(1),
+            y: args.rect.y.saturating_add(1),
+            width: args.rect.width.saturating_sub(2),
+            height: args.rect.height.saturating_sub(2),
+        };
+
+        if let Some(filter) = self.filter.as_mut() {
+            let rect = Rect {
+                y: inner.y,
+                height: 1,
+                ..inner
+            };
+            filter.draw(frame, &rect);
+        }
+
+        let content_rect = if self.filter.is_some() {
+            Rect {
+                y: inner.y.saturating_add(1),
+                height: inner.height.saturating_sub(1),
+                ..inner
+            }
+        } else {
+            inner
+        };
+
+        let content_rect = if let Some(err) = self.filter_error.as_ref() {
+            let rect = Rect {
+                height: 1,
+                ..content_rect
+            };
+            frame.render_widget(Line::raw(err.as_str()).style(self.theme.palette().alert()), rect);
+
+            Rect {
+                y: content_rect.y.saturating_add(1),
+                height: content_rect.height.saturating_sub(1),
+                ..content_rect
+            }
+        } else {
+            content_rect
+        };
+
+        let content_rect = if let Some(dialog) = self.signal_dialog.as_ref() {
+            let rect = Rect {
+                y: content_rect.bottom().saturating_sub(1),
+                height: 1,
+                ..content_rect
+            };
+            frame.render_widget(dialog.to_line(), rect);
+
+            Rect {
+                height: content_rect.height.saturating_sub(1),
+                ..content_rect
+            }
+        } else {
+            content_rect
+        };
+
+        let content_rect = if let Some(dialog) = self.tune_dialog.as_mut() {
+            let rect = Rect {
+                y: content_rect.bottom().saturating_sub(1),
+                height: 1,
+                ..content_rect
+            };
+            dialog.render(frame, rect);
+
+            Rect {
+                height: content_rect.height.saturating_sub(1),
+                ..content_rect
+            }
+        } else {
+            content_rect
+        };
+
+        if let Some(events) = self.events.as_mut() {
+            events.build(content_rect.height, &self.theme);
+            events.view_state.render(frame, content_rect);
+        } else if content_rect.height > 0 {
+            match self._build_page(args) {
+                Ok(_) => {}
+                Err(err) => {
+                    tracing::error!("unable to render_page: {}", err);
+                    return;
+                }
+            }
+
+            let lines = self.cached_page_state();
+
+            if let StatefulLinesType::Lines(lines) = lines {
+                lines.render(frame, content_rect)
+            }
+        }
+
+        if let Some(chooser) = self.column_chooser.as_ref() {
+            chooser.render(frame, inner, &self.line_builder);
+        }
+
+        let buffer = frame.buffer_mut();
+        render_border(args.active, args.rect, buffer);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub enum ProcessMsg {
+    Detect,
+    Filter(Option<ProcessFilter>),
+    /// Carries the sort of the table, the worker may run on another machine.
+    Sort(Option<(ProcessCell, bool)>),
+    /// Switches to the given view, or back to the flat list if it is active.
+    ToggleView(ProcessView),
+    ToggleFold(i32),
+    ToggleApp(Option<String>),
+    /// The process shown in the detail page, which is read on every refresh.
+    Detail(Option<i32>),
+    Signal {
+        target: SignalTarget,
+        action: ProcessAction,
+        elevated: bool,
+    },
+    Tune {
+        pid: i32,
+        tune: ProcessTune,
+    },
+    /// Whether the process events panel is shown.
+    Events(bool),
+}
+
+pub struct Process {}
+
+pub struct ProcessWorker {
+    app_context: AppsContext,
+    filter: Option<ProcessFilter>,
+    view: ProcessView,
+    folded: HashSet<i32>,
+    expanded_apps: HashSet<Option<String>>,
+    detail_pid: Option<i32>,
+    /// Threads of `detail_pid`, kept to compute the CPU usage of each thread.
+    threads: HashMap<i32, crate::sensor::process::Process>,
+    show_events: bool,
+}
+
+impl ProcessWorker {
+    pub fn spawn(result_tx: &Sender<ResourceEvent>) -> AResult<()> {
+        let mut worker = ProcessWorker {
+            app_context: AppsContext::new(),
+            filter: None,
+            view: ProcessView::Flat,
+            folded: HashSet::new(),
+            expanded_apps: HashSet::new(),
+            detail_pid: None,
+            threads: HashMap::new(),
+            show_events: false,
+        };
+
+        let req_rx = PROCESS_WORKER_CHANNEL.1.clone();
+        let result_tx = result_tx.clone();
+
+        thread::Builder::new()
+            .name("processworker".to_owned())
+            .spawn(move || loop {
+                match req_rx.recv_timeout(PROCESS_SCAN_INTERVAL) {
+                    Ok(msg) => match msg {
+                        ProcessMsg::Detect => {
+                            if let Ok(uptime) = read_proc_uptime(&root()) {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(ProcessRsp::Uptime(uptime)),
+                                ));
+                            }
+                            if let Ok(load) = read_proc_loadavg(&root()) {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(ProcessRsp::LoadAvg(load)),
+                                ));
+                            }
+                            worker.updata_data();
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                            if let Some(detail) = worker.get_detail() {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(detail),
+                                ));
+                            }
+                            if let Some(events) = worker.get_events() {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(events),
+                                ));
+                            }
+                        }
+                        ProcessMsg::Detail(pid) => {
+                            worker.detail_pid = pid;
+                            worker.threads.clear();
+                            if let Some(detail) = worker.get_detail() {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(detail),
+                                ));
+                            }
+                        }
+                        ProcessMsg::Filter(filter) => {
+                            worker.filter = filter;
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                            if let Some(events) = worker.get_events() {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(events),
+                                ));
+                            }
+                        }
+                        ProcessMsg::Sort(sort) => {
+                            *PROCESS_SORT_TYPE.write().unwrap() = sort;
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                        }
+                        ProcessMsg::ToggleView(view) => {
+                            worker.view = if worker.view == view {
+                                ProcessView::Flat
+                            } else {
+                                view
+                            };
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                        }
+                        ProcessMsg::ToggleFold(pid) => {
+                            if !worker.folded.remove(&pid) {
+                                worker.folded.insert(pid);
+                            }
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                        }
+                        ProcessMsg::ToggleApp(id) => {
+                            if !worker.expanded_apps.remove(&id) {
+                                worker.expanded_apps.insert(id);
+                            }
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Processes(
+                                    Arc::new(worker.get_process_items()),
+                                )),
+                            ));
+                        }
+                        ProcessMsg::Signal {
+                            target,
+                            action,
+                            elevated,
+                        } => {
+                            let result = worker.execute_process_action(&target, action, elevated);
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Action(
+                                    target, action, result,
+                                )),
+                            ));
+                        }
+                        ProcessMsg::Tune { pid, tune } => {
+                            let result = worker.tune_process(pid, &tune);
+                            let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                crate::resource::SensorRsp::Process(ProcessRsp::Tune(pid, result)),
+                            ));
+                        }
+                        ProcessMsg::Events(show) => {
+                            worker.show_events = show;
+                            if let Some(events) = worker.get_events() {
+                                let _ = result_tx.send(ResourceEvent::SensorRsp(
+                                    crate::resource::SensorRsp::Process(events),
+                                ));
+                            }
+                        }
+                    },
+                    Err(RecvTimeoutError::Timeout) => {
+                        worker.app_context.scan_new_processes();
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            })?;
+
+        Ok(())
+    }
+
+    pub fn updata_data(&mut self) {
+        match ProcessData::all_process_data(&root()) {
+            Ok(data) => {
+                self.app_context.refresh(data);
+            }
+            Err(err) => {
+                tracing::error!("unable to update process data: {}", err);
+            }
+        }
+    }
+
+    /// The processes below `--root` are not the ones nix and pkexec reach,
+    /// their pids belong to another system.
+    fn foreign_root() -> Result<(), ActionFailure> {
+        let root = root();
+        if root == Path::new("/") {
+            return Ok(());
+        }
+        Err(ActionFailure {
+            message: format!("the processes below {} can not be changed", root.display()),
+            permission_denied: false,
+        })
+    }
+
+    pub fn execute_process_action(
+        &self,
+        target: &SignalTarget,
+        action: ProcessAction,
+        elevated: bool,
+    ) -> Result<(), ActionFailure> {
+        Self::foreign_root()?;
+        let result = match target {
+            SignalTarget::Process(pid) => self
+                .app_context
+                .get_process(*pid)
+                .context(Errno::ESRCH)
+                .and_then(|process| {
+                    if elevated {
+                        process.execute_process_action_elevated(action)
+                    } else {
+                        process.execute_process_action(action)
+                    }
+                }),
+            SignalTarget::App(id) => {
+                self.app_context
+                    .get_app(id)
+                    .context(Errno::ESRCH)
+                    .and_then(|app| {
+                        if elevated {
+                            return app.execute_process_action_elevated(&self.app_context, action);
+                        }
+                        // report EPERM first so the pkexec retry is offered
+                        app.execute_process_action(&self.app_context, action)
+                            .into_iter()
+                            .filter_map(Result::err)
+                            .sorted_by_key(|err| err.downcast_ref::<Errno>() != Some(&Errno::EPERM))
+                            .next()
+                            .map_or(Ok(()), Err)
+                    })
+            }
+        };
+
+        result.map_err(|err| {
+            tracing::error!("unable to send {} to {:?}: {}", action, target, err);
+            ActionFailure {
+                message: err.to_string(),
+                permission_denied: err.downcast_ref::<Errno>() == Some(&Errno::EPERM),
+            }
+        })
+    }
+
+    pub fn tune_process(&self, pid: i32, tune: &ProcessTune) -> Result<(), ActionFailure> {
+        Self::foreign_root()?;
+        let result = self
+            .app_context
+            .get_process(pid)
+            .context(Errno::ESRCH)
+            .and_then(|process| match tune {
+                ProcessTune::Nice(nice) => process.set_nice(*nice),
+                ProcessTune::Affinity(cpus) => process.set_affinity(cpus),
+            });
+
+        result.map_err(|err| {
+            tracing::error!("unable to apply {:?} to {}: {}", tune, pid, err);
+            ActionFailure {
+                message: err.to_string(),
+                permission_denied: err.downcast_ref::<Errno>() == Some(&Errno::EPERM),
+            }
+        })
+    }
+
+    fn refresh_threads(&mut self, pid: i32) -> Vec<ThreadItem> {
+        let data = match ProcessData::all_thread_data(&root(), pid) {
+            Ok(data) => data,
+            Err(err) => {
+                tracing::error!("unable to read threads of {}: {}", pid, err);
+                return vec![];
+            }
+        };
+
+        let mut threads = HashMap::new();
+        for thread_data in data {
+            let tid = thread_data.pid;
+            let thread = match self.threads.remove(&tid) {
+                Some(mut thread) => {
+                    thread.update(thread_data);
+                    thread
+                }
+                None => crate::sensor::process::Process::from_process_data(thread_data),
+            };
+            threads.insert(tid, thread);
+        }
+        self.threads = threads;
+
+        self.threads
+            .values()
+            .map(ThreadItem::from_process)
+            .sorted_by(|a, b| {
+                b.cpu_time_ratio
+                    .total_cmp(&a.cpu_time_ratio)
+                    .then(a.tid.cmp(&b.tid))
+            })
+            .collect()
+    }
+
+    fn get_detail(&mut self) -> Option<ProcessRsp> {
+        let pid = self.detail_pid?;
+        let item = self.app_context.process_item(pid);
+        let detail = match ProcessDetail::read(pid) {
+            Ok(mut detail) => {
+                if let Some(process) = self.app_context.get_process(pid) {
+                    detail.gpus = process.gpu_usage_by_slot();
+                }
+                detail.tasks = self.refresh_threads(pid);
+                Some(Box::new(detail))
+            }
+            Err(err) => {
+                tracing::error!("unable to read detail of {}: {}", pid, err);
+                None
+            }
+        };
+
+        Some(ProcessRsp::Detail(pid, item, detail))
+    }
+
+    fn get_events(&self) -> Option<ProcessRsp> {
+        if !self.show_events {
+            return None;
+        }
+
+        let events = self
+            .app_context
+            .events()
+            .filter(|e| self.matches(&e.item))
+            .cloned()
+            .collect();
+        Some(ProcessRsp::Events(Arc::new(events)))
+    }
+
+    fn matches(&self, item: &ProcessItem) -> bool {
+        self.filter.as_ref().is_none_or(|f| f.matches(item))
+    }
+
+    pub fn get_process_items(&self) -> Vec<ProcessRow> {
+        if self.view == ProcessView::Apps {
+            return self.get_app_rows();
+        }
+
+        let items = self
+            .app_context
+            .process_items()
+            .into_values()
+            .filter(|e| self.matches(e));
+        let sorted = sort_items(items);
+
+        if self.view == ProcessView::Tree {
+            ProcessTree::new(sorted, &self.folded).rows()
+        } else {
+            sorted.into_iter().map(ProcessRow::flat).collect()
+        }
+    }
+
+    fn get_app_rows(&self) -> Vec<ProcessRow> {
+        let ctx = &self.app_context;
+        let items = ctx.process_items();
+
+        let apps = ctx.app_items().into_values().filter_map(|app| {
+            let members: Vec<ProcessItem> = match app.id.as_ref() {
+                Some(id) => ctx
+                    .get_app(id)
+                    .map(|app| {
+                        app.processes_iter(ctx)
+                            .filter_map(|process| items.get(&process.data.pid).cloned())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                None => ctx
+                    .system_processes_iter()
+                    .filter_map(|process| items.get(&process.data.pid).cloned())
+                    .collect(),
+            };
+
+            // the app item has no user or pid of its own, so the filter is
+            // matched by its processes
+            let members: Vec<ProcessItem> =
+                members.into_iter().filter(|e| self.matches(e)).collect();
+            if self.filter.is_some() && members.is_empty() {
+                return None;
+            }
+
+            let expanded = self.expanded_apps.contains(&app.id);
+            Some((ProcessRow::from_app(app, expanded), members))
+        });
+
+        let apps: Vec<(ProcessRow, Vec<ProcessItem>)> =
+            if let Some((cell, desc)) = get_process_sort() {
+                apps.sorted_by(|(e1, _), (e2, _)| cell.cmp(&e1.item, &e2.item, desc))
+                    .collect()
+            } else {
+                apps.sorted_by(|(e1, _), (e2, _)| e1.item.display_name.cmp(&e2.item.display_name))
+                    .collect()
+            };
+
+        let mut rows = vec![];
+        for (app_row, members) in apps {
+            let expanded = app_row.fold == Some(false);
+            rows.push(app_row);
+            if expanded {
+                let members = sort_items(members.into_iter());
+                let len = members.len();
+                for (idx, item) in members.into_iter().enumerate() {
+                    let mut row = ProcessRow::flat(item);
+                    row.prefix = if idx + 1 == len { "└─" } else { "├─" }.to_string();
+                    rows.push(row);
+                }
+            }
+        }
+        rows
+    }
+}
+
+fn sort_items(items: impl Iterator<Item = ProcessItem>) -> Vec<ProcessItem> {
+    if let Some((cell, desc)) = get_process_sort() {
+        items.sorted_by(|e1, e2| cell.cmp(e1, e2, desc)).collect()
+    } else {
+        items.sorted_by_key(|e| e.pid).collect()
+    }
+}
+
+/// Parent/child view of the (already filtered and sorted) processes, processes
+/// whose parent is not visible become roots.
+struct ProcessTree<'a> {
+    items: HashMap<i32, ProcessItem>,
+    children: HashMap<i32, Vec<i32>>,
+    roots: Vec<i32>,
+    folded: &'a HashSet<i32>,
+}
+
+impl<'a> ProcessTree<'a> {
+    fn new(sorted: Vec<ProcessItem>, folded: &'a HashSet<i32>) -> Self {
+        let pids: HashSet<i32> = sorted.iter().map(|e| e.pid).collect();
+        let mut children: HashMap<i32, Vec<i32>> = HashMap::new();
+        let mut roots = vec![];
+
+        for item in sorted.iter() {
+            if item.ppid != item.pid && pids.contains(&item.ppid) {
+                children.entry(item.ppid).or_default().push(item.pid);
+            } else {
+                roots.push(item.pid);
+            }
+        }
+
+        Self {
+            items: sorted.into_iter().map(|e| (e.pid, e)).collect(),
+            children,
+            roots,
+            folded,
+        }
+    }
+
+    fn rows(&self) -> Vec<ProcessRow> {
+        let mut rows = Vec::with_capacity(self.items.len());
+        for pid in self.roots.iter() {
+            self.walk(*pid, "", None, &mut rows);
+        }
+        rows
+    }
+
+    /// `last` is `None` for roots, otherwise tells if this is the last child.
+    fn walk(&self, pid: i32, guides: &str, last: Option<bool>, rows: &mut Vec<ProcessRow>) {
+        let Some(item) = self.items.get(&pid) else {
+            return;
+        };
+        let (prefix, child_guides) = match last {
+            None => (String::new(), String::new()),
+            Some(true) => (format!("{guides}└─"), format!("{guides}  ")),
+            Some(false) => (format!("{guides}├─"), format!("{guides}│ ")),
+        };
+
+        let children = self.children.get(&pid);
+        let folded = children.is_some() && self.folded.contains(&pid);
+
+        let item = if folded {
+            self.subtree_sum(pid, item.clone())
+        } else {
+            item.clone()
+        };
+
+        rows.push(ProcessRow {
+            item,
+            prefix,
+            fold: children.map(|_| folded),
+            app: None,
+        });
+
+        if let (Some(children), false) = (children, folded) {
+            for (idx, child) in children.iter().enumerate() {
+                self.walk(*child, &child_guides, Some(idx + 1 == children.len()), rows);
+            }
+        }
+    }
+
+    fn subtree_sum(&self, pid: i32, mut sum: ProcessItem) -> ProcessItem {
+        let mut stack: Vec<i32> = self.children.get(&pid).cloned().unwrap_or_default();
+        while let Some(child) = stack.pop() {
+            if let Some(item) = self.items.get(&child) {
+                sum.cpu_time_ratio += item.cpu_time_ratio;
+                sum.memory_usage += item.memory_usage;
+                sum.read_speed = add_option(sum.read_speed, item.read_speed);
+                sum.write_speed = add_option(sum.write_speed, item.write_speed);
+            }
+            if let Some(children) = self.children.get(&child) {
+                stack.extend(children);
+            }
+        }
+        sum
+    }
+}
+
+fn add_option(a: Option<f64>, b: Option<f64>) -> Option<f64> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a + b),
+        (a, None) => a,
+        (None, b) => b,
+    }
+}
@@ -0,0 +1,166 @@
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component::input::Input,
+    sensor::{cpu::topology::parse_cpu_list, process::ProcessItem},
+    utils::is_esc,
+    view::theme,
+};
+
+use super::{ActionFailure, ProcessMsg, PROCESS_WORKER_CHANNEL};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessTune {
+    Nice(i32),
+    Affinity(Vec<usize>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TuneKind {
+    Nice,
+    Affinity,
+}
+
+#[derive(Debug)]
+enum TuneDialogState {
+    Editing,
+    Pending,
+    Failed(String),
+}
+
+/// Input line shown at the bottom of the process table to change the nice
+/// value or the cpu affinity of the selected process.
+#[derive(Debug)]
+pub struct TuneDialog {
+    pub pid: i32,
+    label: String,
+    kind: TuneKind,
+    input: Input,
+    state: TuneDialogState,
+}
+
+impl TuneDialog {
+    pub fn new(item: &ProcessItem, kind: TuneKind) -> Self {
+        let current = match kind {
+            TuneKind::Nice => item.nice.to_string(),
+            TuneKind::Affinity => item.cpus_allowed.clone().unwrap_or_default(),
+        };
+
+        Self {
+            pid: item.pid,
+            label: format!("{} ({})", item.pid, item.display_name),
+            kind,
+            input: Input::with_input(&current),
+            state: TuneDialogState::Editing,
+        }
+    }
+
+    fn parse(&self) -> anyhow::Result<ProcessTune> {
+        let input = self.input.get_input();
+        match self.kind {
+            TuneKind::Nice => {
+                let nice: i32 = input.trim().parse()?;
+                if !(-20..=19).contains(&nice) {
+                    anyhow::bail!("nice value must be between -20 and 19")
+                }
+                Ok(ProcessTune::Nice(nice))
+            }
+            TuneKind::Affinity => {
+                // whether they exist is up to the machine the affinity is set on
+                let cpus = parse_cpu_list(&input)?;
+                if cpus.is_empty() {
+                    anyhow::bail!("no cpu given")
+                }
+                Ok(ProcessTune::Affinity(cpus))
+            }
+        }
+    }
+
+    pub fn failed(&mut self, failure: &ActionFailure) {
+        let message = if failure.permission_denied && self.kind == TuneKind::Nice {
+            format!("{} (lowering the nice value needs root)", failure.message)
+        } else {
+            failure.message.clone()
+        };
+        self.state = TuneDialogState::Failed(message);
+    }
+
+    /// Returns false when the dialog should be closed.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        if is_esc(key) {
+            return false;
+        }
+
+        match &self.state {
+            TuneDialogState::Editing => {
+                if key.code == KeyCode::Enter {
+                    match self.parse() {
+                        Ok(tune) => {
+                            self.state = TuneDialogState::Pending;
+                            let _ = PROCESS_WORKER_CHANNEL.0.send(ProcessMsg::Tune {
+                                pid: self.pid,
+                                tune,
+                            });
+                        }
+                        Err(err) => {
+                            self.state = TuneDialogState::Failed(err.to_string());
+                        }
+                    }
+                } else {
+                    self.input.handle_event(key);
+                }
+            }
+            TuneDialogState::Pending => {}
+            TuneDialogState::Failed(_) => {
+                // back to the input to correct the value
+                self.state = TuneDialogState::Editing;
+            }
+        }
+        true
+    }
+
+    pub fn render(&mut self, frame: &mut ratatui::Frame, rect: Rect) {
+        let what = match self.kind {
+            TuneKind::Nice => "Nice",
+            TuneKind::Affinity => "CPU affinity",
+        };
+
+        match &self.state {
+            TuneDialogState::Editing => {
+                let prompt = format!("{} of {}: ", what, self.label);
+                let prompt_width = (prompt.chars().count() as u16).min(rect.width);
+                frame.render_widget(Line::raw(prompt), rect);
+
+                let input_rect = Rect {
+                    x: rect.x + prompt_width,
+                    width: rect.width - prompt_width,
+                    ..rect
+                };
+                if input_rect.width > 0 {
+                    self.input.draw(frame, &input_rect);
+                }
+            }
+            TuneDialogState::Pending => {
+                frame.render_widget(
+                    Line::raw(format!("Setting {} of {}...", what, self.label)),
+                    rect,
+                );
+            }
+            TuneDialogState::Failed(message) => {
+                frame.render_widget(
+                    Line::from(vec![
+                        Span::raw(format!("{} of {} failed: {}", what, self.label, message))
+                            .style(theme::palette().alert()),
+                        Span::raw("  [esc] close").style(theme::palette().hint()),
+                    ]),
+                    rect,
+                );
+            }
+        }
+    }
+}
@@ -1,9 +1,91 @@
-use std::{iter::Chain, ops::Range};
+use std::{
+    cell::Cell,
+    iter::{Chain, Skip},
+    ops::Range,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        RwLock,
+    },
+    time::{Duration, Instant},
+};
+
+/// While frozen, every ring is read as it was `VIEW_OFFSET` before the
+/// freeze, so the whole UI shows the same past moment even though the
+/// resources are sampled at different intervals.
+static FROZEN_AT: RwLock<Option<Instant>> = RwLock::new(None);
+static VIEW_OFFSET: RwLock<Duration> = RwLock::new(Duration::ZERO);
+/// The most samples any ring holds, the limit of the view offset.
+static LONGEST: AtomicUsize = AtomicUsize::new(0);
+
+thread_local! {
+    /// When the samples being inserted were taken, now if unset.
+    static SAMPLED_AT: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+pub fn is_frozen() -> bool {
+    FROZEN_AT.read().is_ok_and(|e| e.is_some())
+}
+
+/// Freezes at the newest sample or goes back to live.
+pub fn toggle_freeze() -> bool {
+    if let Ok(mut offset) = VIEW_OFFSET.write() {
+        *offset = Duration::ZERO;
+    }
+    let Ok(mut frozen_at) = FROZEN_AT.write() else {
+        return false;
+    };
+    *frozen_at = match *frozen_at {
+        Some(_) => None,
+        None => Some(Instant::now()),
+    };
+    frozen_at.is_some()
+}
+
+pub fn view_offset() -> Duration {
+    if is_frozen() {
+        VIEW_OFFSET.read().map_or(Duration::ZERO, |e| *e)
+    } else {
+        Duration::ZERO
+    }
+}
+
+/// The moment shown while frozen.
+fn view_time() -> Option<Instant> {
+    let frozen_at = (*FROZEN_AT.read().ok()?)?;
+    frozen_at.checked_sub(view_offset())
+}
+
+/// Moves the view `delta` steps back in time, only while frozen.
+pub fn scrub(delta: isize, step: Duration) {
+    if !is_frozen() {
+        return;
+    }
+    let max = step * LONGEST.load(Ordering::Relaxed).saturating_sub(1) as u32;
+    if let Ok(mut offset) = VIEW_OFFSET.write() {
+        let moved = step * delta.unsigned_abs() as u32;
+        *offset = if delta < 0 {
+            offset.saturating_sub(moved)
+        } else {
+            offset.saturating_add(moved).min(max)
+        };
+    }
+}
+
+/// Inserts the samples of `f` as taken at `sampled_at`, e.g. the ones which
+/// arrived while frozen.
+pub fn sampled_at<R>(sampled_at: Instant, f: impl FnOnce() -> R) -> R {
+    SAMPLED_AT.set(Some(sampled_at));
+    let result = f();
+    SAMPLED_AT.set(None);
+    result
+}
 
 #[derive(Debug)]
 pub struct Ring<T> {
     len: usize,
     vec: Vec<T>,
+    /// When every sample of `vec` was taken.
+    times: Vec<Instant>,
     cursor: usize,
     pub name: String,
 }
@@ -13,6 +95,7 @@ impl<T> Ring<T> {
         Self {
             len: length,
             vec: Vec::with_capacity(length),
+            times: Vec::with_capacity(length),
             cursor: 0,
             name: "".to_owned(),
         }
@@ -26,35 +109,46 @@ impl<T> Ring<T> {
     }
 
     pub fn insert_at_first(&mut self, v: T) {
+        let time = SAMPLED_AT.get().unwrap_or_else(Instant::now);
         if self.vec.len() < self.len {
             self.vec.insert(0, v);
+            self.times.insert(0, time);
+            LONGEST.fetch_max(self.vec.len(), Ordering::Relaxed);
         } else {
             self.cursor = (self.len.saturating_add(self.cursor).saturating_sub(1)) % self.len;
 
             self.vec[self.cursor] = v;
+            self.times[self.cursor] = time;
         }
     }
 
+    /// The sample at the view offset, the newest one unless frozen.
     pub fn newest(&self) -> Option<&T> {
-        if self.vec.is_empty() {
-            None
-        } else {
-            Some(&self.vec[self.cursor])
-        }
+        self.new_to_old_iter().next()
     }
 
+    /// Starts at the newest sample taken before the moment shown while
+    /// frozen.
     pub fn new_to_old_iter(&self) -> IterRing<'_, T> {
+        let order = (self.cursor..self.vec.len()).chain(0..self.cursor);
+        let offset = match view_time() {
+            Some(view_time) => order
+                .clone()
+                .take_while(|id| self.times[*id] > view_time)
+                .count(),
+            None => 0,
+        };
         IterRing {
             vec: &self.vec,
-            chain: (self.cursor..self.vec.len()).chain(0..self.cursor),
-            len: self.vec.len(),
+            chain: order.skip(offset),
+            len: self.vec.len() - offset,
         }
     }
 }
 
 pub struct IterRing<'r, T> {
     vec: &'r Vec<T>,
-    chain: Chain<Range<usize>, Range<usize>>,
+    chain: Skip<Chain<Range<usize>, Range<usize>>>,
     len: usize,
 }
 
@@ -14,7 +14,9 @@ use tracing::debug;
 use crate::tarits::{NaNDefault, None2NanString};
 
 use super::{
-    process::{Process, ProcessAction, ProcessItem},
+    process::{pkexec_kill, read_proc_uptime_secs, Process, ProcessAction, ProcessItem},
+    process_events::{ProcessEvent, ProcessEventLog},
+    root, rooted,
     time::boot_time,
     TICK_RATE,
 };
@@ -108,6 +110,9 @@ pub struct AppsContext {
     processes_assigned_to_apps: HashSet<i32>,
     read_bytes_from_dead_system_processes: u64,
     write_bytes_from_dead_system_processes: u64,
+    /// Processes found by `scan_new_processes` since the last refresh.
+    transient: HashMap<i32, Process>,
+    events: ProcessEventLog,
 }
 
 /// Convenience struct for displaying running applications and
@@ -381,6 +386,18 @@ impl App {
             .map(|process| process.execute_process_action(action))
             .collect()
     }
+
+    pub fn execute_process_action_elevated(
+        &self,
+        apps: &AppsContext,
+        action: ProcessAction,
+    ) -> Result<()> {
+        let pids: Vec<i32> = self.processes_iter(apps).map(|e| e.data.pid).collect();
+        if pids.is_empty() {
+            return Ok(());
+        }
+        pkexec_kill(&pids, action)
+    }
 }
 
 impl AppsContext {
@@ -399,6 +416,8 @@ impl AppsContext {
             processes_assigned_to_apps: HashSet::new(),
             read_bytes_from_dead_system_processes: 0,
             write_bytes_from_dead_system_processes: 0,
+            transient: HashMap::new(),
+            events: ProcessEventLog::new(),
         }
     }
 
@@ -589,35 +608,41 @@ impl AppsContext {
     }
 
     pub fn process_item(&self, pid: i32) -> Option<ProcessItem> {
-        self.get_process(pid).map(|process| {
-            let full_comm = if process.executable_name.starts_with(&process.data.comm) {
-                process.executable_name.clone()
-            } else {
-                process.data.comm.clone()
-            };
-            ProcessItem {
-                pid: process.data.pid,
-                user: process.data.user.clone(),
-                display_name: full_comm.clone(),
-                memory_usage: process.data.memory_usage,
-                cpu_time_ratio: process.cpu_time_ratio(),
-                user_cpu_time: ((process.data.user_cpu_time) as f64 / (*TICK_RATE) as f64),
-                system_cpu_time: ((process.data.system_cpu_time) as f64 / (*TICK_RATE) as f64),
-                commandline: Process::sanitize_cmdline(process.data.commandline.clone())
-                    .unwrap_or(full_comm),
-                containerization: process.data.containerization,
-                starttime: process.starttime(),
-                cgroup: process.data.cgroup.clone(),
-                read_speed: process.read_speed(),
-                read_total: process.data.read_bytes,
-                write_speed: process.write_speed(),
-                write_total: process.data.write_bytes,
-                gpu_usage: process.gpu_usage(),
-                enc_usage: process.enc_usage(),
-                dec_usage: process.dec_usage(),
-                gpu_mem_usage: process.gpu_mem_usage(),
-            }
-        })
+        self.get_process(pid).map(Self::to_process_item)
+    }
+
+    fn to_process_item(process: &Process) -> ProcessItem {
+        let full_comm = if process.executable_name.starts_with(&process.data.comm) {
+            process.executable_name.clone()
+        } else {
+            process.data.comm.clone()
+        };
+        ProcessItem {
+            pid: process.data.pid,
+            ppid: process.data.ppid,
+            state: process.data.state,
+            nice: process.data.nice,
+            cpus_allowed: process.data.cpus_allowed.clone(),
+            user: process.data.user.clone(),
+            display_name: full_comm.clone(),
+            memory_usage: process.data.memory_usage,
+            cpu_time_ratio: process.cpu_time_ratio(),
+            user_cpu_time: ((process.data.user_cpu_time) as f64 / (*TICK_RATE) as f64),
+            system_cpu_time: ((process.data.system_cpu_time) as f64 / (*TICK_RATE) as f64),
+            commandline: Process::sanitize_cmdline(process.data.commandline.clone())
+                .unwrap_or(full_comm),
+            containerization: process.data.containerization,
+            starttime: process.starttime(),
+            cgroup: process.data.cgroup.clone(),
+            read_speed: process.read_speed(),
+            read_total: process.data.read_bytes,
+            write_speed: process.write_speed(),
+            write_total: process.data.write_bytes,
+            gpu_usage: process.gpu_usage(),
+            enc_usage: process.enc_usage(),
+            dec_usage: process.dec_usage(),
+            gpu_mem_usage: process.gpu_mem_usage(),
+        }
     }
 
     /// Returns a `HashMap` of running graphical applications. For more info,
@@ -777,25 +802,31 @@ impl AppsContext {
     /// Refreshes the statistics about the running applications and processes.
     pub fn refresh(&mut self, new_process_data: Vec<ProcessData>) {
         let mut updated_processes = HashSet::new();
+        // the processes found at startup are not new
+        let first_refresh = self.processes.is_empty();
+        let uptime = read_proc_uptime_secs(&root()).ok();
 
         for process_data in new_process_data {
             updated_processes.insert(process_data.pid);
             // refresh our old processes
             if let Some(old_process) = self.processes.get_mut(&process_data.pid) {
-                old_process.cpu_time_last = old_process
-                    .data
-                    .user_cpu_time
-                    .saturating_add(old_process.data.system_cpu_time);
-                old_process.timestamp_last = old_process.data.timestamp;
-                old_process.read_bytes_last = old_process.data.read_bytes;
-                old_process.write_bytes_last = old_process.data.write_bytes;
-                old_process.gpu_usage_stats_last = old_process.data.gpu_usage_stats.clone();
-
-                old_process.data = process_data.clone();
+                old_process.update(process_data);
             } else {
                 // this is a new process, see if it belongs to a graphical app
 
-                let mut new_process = Process::from_process_data(process_data);
+                let mut new_process = match self.transient.remove(&process_data.pid) {
+                    Some(mut process) => {
+                        process.update(process_data);
+                        process
+                    }
+                    None => {
+                        let process = Process::from_process_data(process_data);
+                        if !first_refresh {
+                            self.events.started(Self::to_process_item(&process));
+                        }
+                        process
+                    }
+                };
 
                 if let Some(app_id) = self.app_associated_with_process(&new_process) {
                     self.processes_assigned_to_apps.insert(new_process.data.pid);
@@ -810,6 +841,15 @@ impl AppsContext {
         }
 
         // all the not-updated processes have unfortunately died, probably
+        for process in self
+            .processes
+            .values()
+            .filter(|process| !updated_processes.contains(&process.data.pid))
+            .chain(self.transient.values())
+        {
+            self.events.exited(Self::to_process_item(process), uptime);
+        }
+        self.transient.clear();
 
         // collect the I/O stats for died app processes so an app doesn't suddenly have less total disk I/O
         self.apps.values_mut().for_each(|app| {
@@ -868,6 +908,62 @@ impl AppsContext {
             .retain(|pid| updated_processes.contains(pid));
     }
 
+    /// Only lists `/proc` and reads the processes which are not known yet, so
+    /// it can be called far more often than `refresh` to catch processes
+    /// living shorter than a refresh interval.
+    pub fn scan_new_processes(&mut self) {
+        // nothing to compare against before the first refresh
+        if self.processes.is_empty() {
+            return;
+        }
+
+        let proc = rooted(&root(), "/proc");
+        let Ok(dir) = std::fs::read_dir(&proc) else {
+            return;
+        };
+        let pids: HashSet<i32> = dir
+            .flatten()
+            .filter_map(|entry| entry.file_name().to_str()?.parse().ok())
+            .collect();
+
+        let exited: Vec<i32> = self
+            .transient
+            .keys()
+            .filter(|pid| !pids.contains(*pid))
+            .copied()
+            .collect();
+        if !exited.is_empty() {
+            let uptime = read_proc_uptime_secs(&root()).ok();
+            for pid in exited {
+                if let Some(process) = self.transient.remove(&pid) {
+                    self.events.exited(Self::to_process_item(&process), uptime);
+                }
+            }
+        }
+
+        for pid in pids {
+            if self.processes.contains_key(&pid) {
+                continue;
+            }
+            let Ok(data) = ProcessData::try_from_path(proc.join(pid.to_string())) else {
+                continue;
+            };
+            match self.transient.get_mut(&pid) {
+                Some(process) => process.update(data),
+                None => {
+                    let process = Process::from_process_data(data);
+                    self.events.started(Self::to_process_item(&process));
+                    self.transient.insert(pid, process);
+                }
+            }
+        }
+    }
+
+    /// Newest first.
+    pub fn events(&self) -> impl Iterator<Item = &ProcessEvent> {
+        self.events.iter()
+    }
+
     pub fn system_processes_iter(&self) -> impl Iterator<Item = &Process> {
         self.all_processes()
             .filter(|process| !self.processes_assigned_to_apps.contains(&process.data.pid))
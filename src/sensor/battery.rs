@@ -5,18 +5,39 @@ use std::{
 };
 
 use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::tarits::{None2NaN, None2NanString};
 
-use super::{units::convert_energy, Sensor};
+use super::{deserialize_result, rooted, serialize_result, units::convert_energy, Sensor};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct BatteryData {
     pub inner: Battery,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub charge: Result<f64>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub power_usage: Result<f64>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub health: Result<f64>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub state: Result<State>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub charge_cycles: Result<usize>,
 }
 
@@ -40,7 +61,7 @@ impl BatteryData {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
 pub enum State {
     Charging,
     Discharging,
@@ -82,7 +103,7 @@ impl Display for State {
     }
 }
 
-#[derive(Debug, Eq, PartialEq, Copy, Clone, Default)]
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Default, Serialize, Deserialize)]
 pub enum Technology {
     NickelMetalHydride,
     NickelCadmium,
@@ -141,7 +162,7 @@ impl Display for Technology {
     }
 }
 
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Battery {
     pub supply_name: String,
     pub sysfs_path: PathBuf,
@@ -152,9 +173,9 @@ pub struct Battery {
 }
 
 impl Battery {
-    pub fn get_sysfs_paths() -> Result<Vec<PathBuf>> {
+    pub fn get_sysfs_paths(root: &Path) -> Result<Vec<PathBuf>> {
         let mut list = Vec::new();
-        let mut entries = std::fs::read_dir("/sys/class/power_supply")?;
+        let mut entries = std::fs::read_dir(rooted(root, "/sys/class/power_supply"))?;
         while let Some(entry) = entries.next() {
             let entry = entry?;
             if std::fs::read_to_string(entry.path().join("type"))
@@ -315,3 +336,27 @@ impl Sensor for Battery {
             .or_unk(|e| e.to_str().or_nan_owned())
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_battery() {
+        let paths = Battery::get_sysfs_paths(&fixture("laptop")).unwrap();
+        assert_eq!(paths.len(), 1, "AC is not a battery");
+
+        let data = BatteryData::new(&paths[0]);
+        assert_eq!(data.inner.supply_name, "BAT0");
+        assert_eq!(data.inner.manufacturer.as_deref(), Some("SMP"));
+        assert_eq!(data.inner.technology, Technology::LithiumPolymer);
+        assert_eq!(data.inner.design_capacity, Some(57.02));
+        assert_eq!(data.charge.unwrap(), 0.87);
+        assert_eq!(data.power_usage.unwrap(), 8.512);
+        assert_eq!(data.health.unwrap(), 50320000. / 57020000.);
+        assert_eq!(data.state.unwrap(), State::Discharging);
+        assert_eq!(data.charge_cycles.unwrap(), 152);
+    }
+}
@@ -0,0 +1,182 @@
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::sensor::rooted;
+
+/// What the cpufreq driver offers, read once.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FreqInfo {
+    /// The `scaling_driver`, e.g. `intel_pstate` or `acpi-cpufreq`.
+    pub driver: Option<String>,
+    /// `active`, `passive` or `guided` of intel_pstate or amd_pstate.
+    pub pstate: Option<String>,
+    /// The hardware limits in Hz.
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub frequencies: Vec<u64>,
+    pub governors: Vec<String>,
+    /// The energy performance preferences.
+    pub epps: Vec<String>,
+}
+
+impl FreqInfo {
+    /// The files of the first CPU, every policy offers the same in practice.
+    pub fn read(root: &Path) -> Self {
+        let read = |file: &str| read_cpufreq(root, 0, file);
+        let list = |file: &str| -> Vec<String> {
+            read(file)
+                .map(|e| e.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+        let pstate = ["intel_pstate", "amd_pstate"].iter().find_map(|driver| {
+            std::fs::read_to_string(rooted(
+                root,
+                &format!("/sys/devices/system/cpu/{driver}/status"),
+            ))
+            .ok()
+            .map(|status| format!("{} {}", driver, status.trim()))
+        });
+
+        Self {
+            driver: read("scaling_driver"),
+            pstate,
+            min: read("cpuinfo_min_freq").and_then(|e| khz(&e)),
+            max: read("cpuinfo_max_freq").and_then(|e| khz(&e)),
+            frequencies: list("scaling_available_frequencies")
+                .iter()
+                .filter_map(|e| khz(e))
+                .collect(),
+            governors: list("scaling_available_governors"),
+            epps: list("energy_performance_available_preferences"),
+        }
+    }
+}
+
+/// The cpufreq policy and the throttle counters of a logical CPU.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuPolicy {
+    pub governor: Option<String>,
+    /// The scaling limits in Hz.
+    pub min: Option<u64>,
+    pub max: Option<u64>,
+    pub epp: Option<String>,
+    /// How often the core or its package got too hot since boot.
+    pub core_throttles: Option<u64>,
+    pub package_throttles: Option<u64>,
+}
+
+impl CpuPolicy {
+    pub fn read(root: &Path, cpu: usize) -> Self {
+        let read = |file: &str| read_cpufreq(root, cpu, file);
+        let throttles = |file: &str| {
+            std::fs::read_to_string(rooted(
+                root,
+                &format!("/sys/devices/system/cpu/cpu{cpu}/thermal_throttle/{file}"),
+            ))
+            .ok()?
+            .trim()
+            .parse()
+            .ok()
+        };
+        Self {
+            governor: read("scaling_governor"),
+            min: read("scaling_min_freq").and_then(|e| khz(&e)),
+            max: read("scaling_max_freq").and_then(|e| khz(&e)),
+            epp: read("energy_performance_preference"),
+            core_throttles: throttles("core_throttle_count"),
+            package_throttles: throttles("package_throttle_count"),
+        }
+    }
+}
+
+/// Whether the CPU may clock above its base frequency, from the cpufreq
+/// `boost` switch or the inverted `no_turbo` of intel_pstate.
+pub fn get_boost(root: &Path) -> Option<bool> {
+    let read = |path: &str| {
+        std::fs::read_to_string(rooted(root, path))
+            .ok()?
+            .trim()
+            .parse::<u8>()
+            .ok()
+    };
+    read("/sys/devices/system/cpu/cpufreq/boost")
+        .map(|e| e == 1)
+        .or_else(|| read("/sys/devices/system/cpu/intel_pstate/no_turbo").map(|e| e == 0))
+}
+
+/// A setting of the cpufreq policy which can be switched at runtime.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyKnob {
+    Governor,
+    Epp,
+}
+
+impl PolicyKnob {
+    fn file(&self) -> &'static str {
+        match self {
+            PolicyKnob::Governor => "scaling_governor",
+            PolicyKnob::Epp => "energy_performance_preference",
+        }
+    }
+}
+
+/// Writes `value` to the policy of every CPU in `cpus`, which needs root.
+/// Offline CPUs have no policy and are left out.
+pub fn set_policy(root: &Path, cpus: &[usize], knob: PolicyKnob, value: &str) -> Result<()> {
+    let mut changed = 0;
+    for cpu in cpus {
+        let path = rooted(
+            root,
+            &format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/{}", knob.file()),
+        );
+        if !path.exists() {
+            continue;
+        }
+        std::fs::write(&path, value)
+            .with_context(|| format!("unable to write {} of cpu{}", knob.file(), cpu))?;
+        changed += 1;
+    }
+    if changed == 0 {
+        bail!("no CPU has a {}", knob.file());
+    }
+    Ok(())
+}
+
+fn read_cpufreq(root: &Path, cpu: usize, file: &str) -> Option<String> {
+    std::fs::read_to_string(rooted(
+        root,
+        &format!("/sys/devices/system/cpu/cpu{cpu}/cpufreq/{file}"),
+    ))
+    .ok()
+    .map(|e| e.trim().to_string())
+    .filter(|e| !e.is_empty())
+}
+
+/// cpufreq counts in kHz.
+fn khz(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok().map(|e| e * 1000)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_policy() {
+        let root = fixture("laptop");
+        let info = FreqInfo::read(&root);
+        assert_eq!(info.pstate.as_deref(), Some("intel_pstate active"));
+        assert_eq!(info.governors, vec!["performance", "powersave"]);
+        assert_eq!(info.max, Some(2_800_000_000));
+
+        let policy = CpuPolicy::read(&root, 0);
+        assert_eq!(policy.governor.as_deref(), Some("powersave"));
+        assert_eq!(policy.epp.as_deref(), Some("balance_performance"));
+        assert_eq!(policy.package_throttles, Some(3));
+        assert_eq!(get_boost(&root), Some(true));
+    }
+}
@@ -0,0 +1,504 @@
+use anyhow::{anyhow, bail, Context, Result};
+use chin_tools::AResult;
+use glob::{glob, Pattern};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use super::{root, rooted};
+
+pub mod freq;
+pub mod power;
+pub mod thermal;
+pub mod topology;
+
+pub use freq::{CpuPolicy, FreqInfo};
+pub use power::EnergyCounter;
+pub use thermal::CpuTemperature;
+pub use topology::Topology;
+
+const KNOWN_HWMONS: &[&str] = &["zenpower", "coretemp", "k10temp"];
+
+const KNOWN_THERMAL_ZONES: &[&str] = &["x86_pkg_temp", "acpitz"];
+
+static RE_PROC_STAT: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"cpu[0-9]* *(?P<user>[0-9]*) *(?P<nice>[0-9]*) *(?P<system>[0-9]*) *(?P<idle>[0-9]*) *(?P<iowait>[0-9]*) *(?P<irq>[0-9]*) *(?P<softirq>[0-9]*) *(?P<steal>[0-9]*) *(?P<guest>[0-9]*) *(?P<guest_nice>[0-9]*)").unwrap()
+});
+
+static CPU_TEMPERATURE_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    let root = root();
+    let cpu_temperature_path = search_for_hwmons(&root, KNOWN_HWMONS)
+        .or_else(|| search_for_thermal_zones(&root, KNOWN_THERMAL_ZONES));
+
+    if let Some((sensor, path)) = &cpu_temperature_path {
+        tracing::debug!(
+            "CPU temperature sensor located at {} ({sensor})",
+            path.display()
+        );
+    } else {
+        tracing::warn!("No sensor for CPU temperature found!");
+    }
+
+    cpu_temperature_path.map(|(_, path)| path)
+});
+
+/// Looks for hwmons with the given names.
+/// This function is a bit inefficient since the `names` array is considered to be ordered by priority.
+fn search_for_hwmons(root: &Path, names: &[&'static str]) -> Option<(&'static str, PathBuf)> {
+    let pattern = rooted_pattern(root, "/sys/class/hwmon/hwmon*")?;
+    for temp_name in names {
+        for path in (glob(&pattern).unwrap()).flatten() {
+            if let Ok(read_name) = std::fs::read_to_string(path.join("name")) {
+                if &read_name.trim_end() == temp_name {
+                    return Some((temp_name, path.join("temp1_input")));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Looks for thermal zones with the given types.
+/// This function is a bit inefficient since the `types` array is considered to be ordered by priority.
+fn search_for_thermal_zones(
+    root: &Path,
+    types: &[&'static str],
+) -> Option<(&'static str, PathBuf)> {
+    let pattern = rooted_pattern(root, "/sys/class/thermal/thermal_zone*")?;
+    for temp_type in types {
+        for path in (glob(&pattern).unwrap()).flatten() {
+            if let Ok(read_type) = std::fs::read_to_string(path.join("type")) {
+                if &read_type.trim_end() == temp_type {
+                    return Some((temp_type, path.join("temp")));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// A glob `pattern` below `root`, with the root itself escaped.
+fn rooted_pattern(root: &Path, pattern: &str) -> Option<String> {
+    Some(format!(
+        "{}/{}",
+        Pattern::escape(root.to_str()?.trim_end_matches('/')),
+        pattern.trim_start_matches('/')
+    ))
+}
+
+/// The categories of [`CpuTimes::modes`].
+pub const CPU_MODES: [&str; 8] = [
+    "user", "nice", "system", "iowait", "irq", "softirq", "steal", "guest",
+];
+
+/// The columns of a `cpu` line of /proc/stat, in ticks since boot.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuTimes {
+    pub user: u64,
+    pub nice: u64,
+    pub system: u64,
+    pub idle: u64,
+    pub iowait: u64,
+    pub irq: u64,
+    pub softirq: u64,
+    pub steal: u64,
+    /// Already part of `user` and `nice`.
+    pub guest: u64,
+    pub guest_nice: u64,
+}
+
+impl CpuTimes {
+    /// Every tick once, guest time is counted in `user` and `nice`.
+    pub fn total(&self) -> u64 {
+        self.user
+            + self.nice
+            + self.system
+            + self.idle
+            + self.iowait
+            + self.irq
+            + self.softirq
+            + self.steal
+    }
+
+    /// The ticks neither idle nor waiting for I/O.
+    pub fn busy(&self) -> u64 {
+        self.total()
+            .saturating_sub(self.idle)
+            .saturating_sub(self.iowait)
+    }
+
+    /// The ticks of every category of [`CPU_MODES`], guest time taken out
+    /// of `user` and `nice`.
+    pub fn modes(&self) -> [u64; 8] {
+        [
+            self.user.saturating_sub(self.guest),
+            self.nice.saturating_sub(self.guest_nice),
+            self.system,
+            self.iowait,
+            self.irq,
+            self.softirq,
+            self.steal,
+            self.guest + self.guest_nice,
+        ]
+    }
+
+    /// The ticks elapsed since `old`.
+    pub fn since(&self, old: &CpuTimes) -> CpuTimes {
+        CpuTimes {
+            user: self.user.saturating_sub(old.user),
+            nice: self.nice.saturating_sub(old.nice),
+            system: self.system.saturating_sub(old.system),
+            idle: self.idle.saturating_sub(old.idle),
+            iowait: self.iowait.saturating_sub(old.iowait),
+            irq: self.irq.saturating_sub(old.irq),
+            softirq: self.softirq.saturating_sub(old.softirq),
+            steal: self.steal.saturating_sub(old.steal),
+            guest: self.guest.saturating_sub(old.guest),
+            guest_nice: self.guest_nice.saturating_sub(old.guest_nice),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CpuData {
+    pub new_total_usage: CpuTimes,
+    pub new_thread_usages: Vec<CpuTimes>,
+    pub temperature: Option<f32>,
+    /// Every package, core and CCD input, missing in older recordings.
+    #[serde(default)]
+    pub temperatures: Vec<CpuTemperature>,
+    pub frequencies: Vec<Option<u64>>,
+    /// The governor, limits and throttle counters of every logical CPU.
+    #[serde(default)]
+    pub policies: Vec<CpuPolicy>,
+    #[serde(default)]
+    pub boost: Option<bool>,
+    /// The RAPL energy counters, watts are computed between two samples.
+    #[serde(default)]
+    pub energies: Vec<EnergyCounter>,
+    #[serde(skip, default = "Instant::now")]
+    pub sampled_at: Instant,
+}
+
+impl CpuData {
+    pub fn fetch(root: &Path, logical_cpus: usize) -> AResult<Self> {
+        let new_total_usage = get_cpu_usage(root, None)?;
+
+        let temperature = get_temperature().ok();
+        let temperatures = thermal::get_temperatures();
+
+        let mut frequencies = Vec::with_capacity(logical_cpus);
+        let mut policies = Vec::with_capacity(logical_cpus);
+        let mut new_thread_usages = Vec::with_capacity(logical_cpus);
+
+        for i in 0..logical_cpus {
+            let smth = get_cpu_usage(root, Some(i))?;
+            new_thread_usages.push(smth);
+
+            let freq = get_cpu_freq(root, i).ok();
+            frequencies.push(freq);
+            policies.push(CpuPolicy::read(root, i));
+        }
+
+        Ok(Self {
+            new_total_usage,
+            new_thread_usages,
+            temperature,
+            temperatures,
+            frequencies,
+            policies,
+            boost: freq::get_boost(root),
+            energies: power::get_energies(),
+            sampled_at: Instant::now(),
+        })
+    }
+}
+
+/// What `lscpu` would show, older recordings lack the topology.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CpuInfo {
+    pub model_name: Option<String>,
+    pub vendor: Option<String>,
+    pub architecture: Option<String>,
+    pub logical_cpus: Option<usize>,
+    pub physical_cpus: Option<usize>,
+    pub sockets: Option<usize>,
+    pub dies: Option<usize>,
+    pub virtualization: Option<String>,
+    pub max_speed: Option<f64>,
+    pub microcode: Option<String>,
+    pub flags: Vec<String>,
+    /// The files of `/sys/devices/system/cpu/vulnerabilities` and their
+    /// state.
+    pub vulnerabilities: Vec<(String, String)>,
+    pub topology: Topology,
+    pub freq: FreqInfo,
+}
+
+fn trade_mark_symbols<S: AsRef<str>>(s: S) -> String {
+    s.as_ref()
+        .replace("(R)", "®")
+        .replace("(tm)", "™")
+        .replace("(TM)", "™")
+}
+
+/// The blocks of /proc/cpuinfo, one per logical CPU.
+fn parse_cpuinfo(cpuinfo: &str) -> Vec<BTreeMap<String, String>> {
+    cpuinfo
+        .split("\n\n")
+        .map(|block| {
+            block
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+                .collect::<BTreeMap<_, _>>()
+        })
+        .filter(|block| !block.is_empty())
+        .collect()
+}
+
+/// Returns a `CpuInfo` struct populated from sysfs and /proc/cpuinfo.
+///
+/// # Errors
+///
+/// Will return `Err` if not even the number of logical CPUs is known.
+pub fn cpu_info(root: &Path) -> Result<CpuInfo> {
+    let cpuinfo = std::fs::read_to_string(rooted(root, "/proc/cpuinfo"))
+        .map(|e| parse_cpuinfo(&e))
+        .unwrap_or_default();
+    // the first block with the key, some architectures have a trailing
+    // block for the whole machine
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|key| cpuinfo.iter().find_map(|block| block.get(*key)))
+            .cloned()
+    };
+
+    let topology = Topology::read(root, &cpuinfo);
+    if topology.threads.is_empty() {
+        bail!("unable to find any logical CPU");
+    }
+
+    let flags: Vec<String> = field(&["flags", "Features"])
+        .map(|e| e.split_whitespace().map(str::to_string).collect())
+        .unwrap_or_default();
+    let virtualization = if flags.iter().any(|e| e == "vmx") {
+        Some("VT-x".to_string())
+    } else if flags.iter().any(|e| e == "svm") {
+        Some("AMD-V".to_string())
+    } else {
+        None
+    };
+
+    let max_speed = topology
+        .threads
+        .iter()
+        .filter_map(|e| {
+            std::fs::read_to_string(rooted(
+                root,
+                &format!(
+                    "/sys/devices/system/cpu/cpu{}/cpufreq/cpuinfo_max_freq",
+                    e.id
+                ),
+            ))
+            .ok()?
+            .trim()
+            .parse::<f64>()
+            .ok()
+        })
+        .reduce(f64::max)
+        .map(|khz| khz * 1000.0);
+
+    let mut vulnerabilities: Vec<(String, String)> =
+        std::fs::read_dir(rooted(root, "/sys/devices/system/cpu/vulnerabilities"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let state = std::fs::read_to_string(e.path()).ok()?;
+                Some((
+                    e.file_name().to_str()?.to_string(),
+                    state.trim().to_string(),
+                ))
+            })
+            .collect();
+    vulnerabilities.sort();
+
+    let architecture = std::fs::read_to_string(rooted(root, "/proc/sys/kernel/arch"))
+        .map(|e| e.trim().to_string())
+        .unwrap_or_else(|_| std::env::consts::ARCH.to_string());
+
+    Ok(CpuInfo {
+        model_name: field(&["model name", "Model", "cpu model", "cpu"]).map(trade_mark_symbols),
+        vendor: field(&["vendor_id", "vendor", "CPU implementer"]),
+        architecture: Some(architecture),
+        logical_cpus: Some(topology.threads.len()),
+        physical_cpus: Some(topology.cores()).filter(|e| *e > 0),
+        sockets: Some(topology.packages()).filter(|e| *e > 0),
+        dies: Some(topology.dies()).filter(|e| *e > 0),
+        virtualization,
+        max_speed,
+        microcode: field(&["microcode"]),
+        flags,
+        vulnerabilities,
+        topology,
+        freq: FreqInfo::read(root),
+    })
+}
+
+/// Returns the frequency of the given CPU `core`
+///
+/// # Errors
+///
+/// Will return `Err` if the are problems during reading or parsing
+/// of the corresponding file in sysfs
+pub fn get_cpu_freq(root: &Path, core: usize) -> Result<u64> {
+    std::fs::read_to_string(rooted(
+        root,
+        &format!("/sys/devices/system/cpu/cpu{core}/cpufreq/scaling_cur_freq"),
+    ))
+    .with_context(|| format!("unable to read scaling_cur_freq for core {core}"))?
+    .replace('\n', "")
+    .parse::<u64>()
+    .context("can't parse scaling_cur_freq to usize")
+    .map(|x| x * 1000)
+}
+
+fn parse_proc_stat_line<S: AsRef<str>>(line: S) -> Result<CpuTimes> {
+    let captures = RE_PROC_STAT
+        .captures(line.as_ref())
+        .ok_or_else(|| anyhow!("using regex to parse /proc/stat failed"))?;
+    // older kernels lack the columns from iowait on
+    let column = |name: &str| {
+        captures
+            .name(name)
+            .and_then(|x| x.as_str().parse::<u64>().ok())
+    };
+    let required = |name: &str| column(name).ok_or_else(|| anyhow!("unable to get {name} time"));
+    Ok(CpuTimes {
+        user: required("user")?,
+        nice: required("nice")?,
+        system: required("system")?,
+        idle: required("idle")?,
+        iowait: column("iowait").unwrap_or(0),
+        irq: column("irq").unwrap_or(0),
+        softirq: column("softirq").unwrap_or(0),
+        steal: column("steal").unwrap_or(0),
+        guest: column("guest").unwrap_or(0),
+        guest_nice: column("guest_nice").unwrap_or(0),
+    })
+}
+
+fn get_proc_stat(root: &Path, core: Option<usize>) -> Result<String> {
+    // the combined stats are in line 0, the other cores are in the following lines,
+    // since our `core` argument starts with 0, we must add 1 to it if it's not `None`.
+    let selected_line_number = core.map_or(0, |x| x + 1);
+    let proc_stat_raw =
+        std::fs::read_to_string(rooted(root, "/proc/stat")).context("unable to read /proc/stat")?;
+    let mut proc_stat = proc_stat_raw.split('\n').collect::<Vec<&str>>();
+    proc_stat.retain(|x| x.starts_with("cpu"));
+    // return an `Error` if `core` is greater than the number of cores
+    if selected_line_number >= proc_stat.len() {
+        bail!("`core` argument greater than amount of cores")
+    }
+    Ok(proc_stat[selected_line_number].to_string())
+}
+
+/// Returns the CPU usage of either all cores combined (if supplied argument is `None`),
+/// or of a specific thread (taken from the supplied argument starting at 0)
+/// Please keep in mind that this is the total CPU time since boot, you have to do delta
+/// calculations yourself.
+///
+/// # Errors
+///
+/// Will return `Err` if the are problems during reading or parsing
+/// of /proc/stat
+pub fn get_cpu_usage(root: &Path, core: Option<usize>) -> Result<CpuTimes> {
+    parse_proc_stat_line(get_proc_stat(root, core)?)
+}
+
+/// Returns the CPU temperature.
+///
+/// # Errors
+///
+/// Will return `Err` if there was no way to read the CPU temperature.
+pub fn get_temperature() -> Result<f32> {
+    if let Some(path) = CPU_TEMPERATURE_PATH.as_ref() {
+        read_sysfs_thermal(path)
+    } else {
+        bail!("no CPU temperature sensor found")
+    }
+}
+
+fn read_sysfs_thermal<P: AsRef<Path>>(path: P) -> Result<f32> {
+    let path = path.as_ref();
+    let temp_string = std::fs::read_to_string(path)
+        .with_context(|| format!("unable to read {}", path.display()))?;
+    temp_string
+        .replace('\n', "")
+        .parse::<f32>()
+        .with_context(|| format!("unable to parse {}", path.display()))
+        .map(|t| t / 1000f32)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_cpu_usage() {
+        let root = fixture("laptop");
+        let total = get_cpu_usage(&root, None).unwrap();
+        assert_eq!(total.iowait, 16683);
+        assert_eq!(total.guest, 175628);
+        assert_eq!(total.total(), 60377929);
+        assert_eq!(total.busy(), 60377929 - 46828483 - 16683);
+        assert_eq!(total.modes()[0], 10132153 - 175628);
+        assert!(get_cpu_usage(&root, Some(1)).is_ok());
+        assert!(get_cpu_usage(&root, Some(2)).is_err());
+    }
+
+    #[test]
+    fn test_cpu_data() {
+        let data = CpuData::fetch(&fixture("laptop"), 2).unwrap();
+        assert_eq!(data.new_thread_usages.len(), 2);
+        assert_eq!(data.frequencies, vec![Some(2_800_000_000), None]);
+    }
+
+    #[test]
+    fn test_cpu_info() {
+        let info = cpu_info(&fixture("laptop")).unwrap();
+        assert_eq!(
+            info.model_name.as_deref(),
+            Some("Intel® Core™ M-5Y10c CPU @ 0.80GHz")
+        );
+        assert_eq!(info.microcode.as_deref(), Some("0x2f"));
+        assert_eq!(info.logical_cpus, Some(2));
+        assert_eq!(info.physical_cpus, Some(1));
+        assert_eq!(info.virtualization.as_deref(), Some("VT-x"));
+        assert_eq!(info.max_speed, Some(2_800_000_000.));
+        assert_eq!(info.vulnerabilities[0].0, "meltdown");
+    }
+
+    #[test]
+    fn test_temperature_sensor() {
+        let root = fixture("laptop");
+        let (name, path) = search_for_hwmons(&root, KNOWN_HWMONS).unwrap();
+        assert_eq!(name, "coretemp");
+        assert_eq!(read_sysfs_thermal(path).unwrap(), 47.);
+
+        let (name, path) = search_for_thermal_zones(&root, KNOWN_THERMAL_ZONES).unwrap();
+        assert_eq!(name, "x86_pkg_temp");
+        assert_eq!(read_sysfs_thermal(path).unwrap(), 48.);
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::rooted_pattern;
+use crate::sensor::root;
+
+static ENERGY_SOURCES: Lazy<Vec<EnergySource>> = Lazy::new(|| {
+    let sources = discover(&root());
+    tracing::debug!("{} CPU energy counters found", sources.len());
+    sources
+});
+
+/// A reading of the energy counter of a RAPL domain, in µJ since an
+/// arbitrary point. Watts are the difference between two readings.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnergyCounter {
+    /// `package`, `core`, `uncore`, `dram` or `psys`.
+    pub domain: String,
+    /// `None` for the domains outside of a package, e.g. `psys`.
+    pub package: Option<usize>,
+    pub energy: u64,
+    /// The counter starts from 0 again after this.
+    pub range: Option<u64>,
+}
+
+impl EnergyCounter {
+    /// The µJ used since `old`, taking a wrap around into account.
+    pub fn since(&self, old: &EnergyCounter) -> Option<u64> {
+        if self.energy >= old.energy {
+            Some(self.energy - old.energy)
+        } else {
+            self.range.map(|range| range - old.energy + self.energy)
+        }
+    }
+
+    /// E.g. `Package 0` or `DRAM`, the domains of a package get its id if
+    /// there are `packages` of them, e.g. `Cores 1`.
+    pub fn label(&self, packages: usize) -> String {
+        let name = match self.domain.as_str() {
+            "package" => "Package",
+            "core" => "Cores",
+            "uncore" => "Uncore",
+            "dram" => "DRAM",
+            "psys" => "Platform",
+            domain => domain,
+        };
+        match self.package {
+            Some(package) if self.domain == "package" || packages > 1 => {
+                format!("{} {}", name, package)
+            }
+            _ => name.to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EnergySource {
+    domain: String,
+    package: Option<usize>,
+    input: PathBuf,
+    range: Option<u64>,
+}
+
+/// Reads every counter found at the start. `energy_uj` is only readable by
+/// root on most kernels, the unreadable ones are left out.
+pub fn get_energies() -> Vec<EnergyCounter> {
+    ENERGY_SOURCES
+        .iter()
+        .filter_map(EnergySource::read)
+        .collect()
+}
+
+/// Whether the CPU has energy counters, readable or not.
+pub fn has_energy_counters() -> bool {
+    !ENERGY_SOURCES.is_empty()
+}
+
+impl EnergySource {
+    fn read(&self) -> Option<EnergyCounter> {
+        Some(EnergyCounter {
+            domain: self.domain.clone(),
+            package: self.package,
+            energy: read_u64(&self.input)?,
+            range: self.range,
+        })
+    }
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+/// The zones of the powercap `intel-rapl` control type, which AMD Zen uses
+/// as well since Linux 5.8. Without them the sockets of the `amd_energy`
+/// hwmon are used.
+fn discover(root: &Path) -> Vec<EnergySource> {
+    let mut sources = vec![];
+    if let Some(pattern) = rooted_pattern(root, "/sys/class/powercap/intel-rapl:*") {
+        let mut zones: Vec<PathBuf> = glob(&pattern).unwrap().flatten().collect();
+        zones.sort();
+        for zone in zones {
+            let Some(name) = std::fs::read_to_string(zone.join("name")).ok() else {
+                continue;
+            };
+            let name = name.trim();
+            // intel-rapl:0 is the first package, intel-rapl:0:1 a subzone of it
+            let ids: Vec<usize> = zone
+                .file_name()
+                .and_then(|e| e.to_str())
+                .and_then(|e| e.strip_prefix("intel-rapl:"))
+                .map(|e| e.split(':').filter_map(|e| e.parse().ok()).collect())
+                .unwrap_or_default();
+            let (domain, package) = match name.strip_prefix("package-") {
+                Some(package) => ("package", package.parse().ok()),
+                None if ids.len() > 1 => (name, ids.first().copied()),
+                None => (name, None),
+            };
+            sources.push(EnergySource {
+                domain: domain.to_string(),
+                package,
+                input: zone.join("energy_uj"),
+                range: read_u64(&zone.join("max_energy_range_uj")),
+            });
+        }
+    }
+
+    if sources.is_empty() {
+        sources = discover_amd_energy(root);
+    }
+    sources
+}
+
+fn discover_amd_energy(root: &Path) -> Vec<EnergySource> {
+    let Some(pattern) = rooted_pattern(root, "/sys/class/hwmon/hwmon*") else {
+        return vec![];
+    };
+    glob(&pattern)
+        .unwrap()
+        .flatten()
+        .filter(|hwmon| {
+            std::fs::read_to_string(hwmon.join("name")).is_ok_and(|e| e.trim() == "amd_energy")
+        })
+        .flat_map(|hwmon| {
+            glob(&format!(
+                "{}/energy*_label",
+                glob::Pattern::escape(&hwmon.to_string_lossy())
+            ))
+            .unwrap()
+            .flatten()
+            .filter_map(|label| {
+                // Esocket0, the Ecore000 of every core are left out
+                let package = std::fs::read_to_string(&label)
+                    .ok()?
+                    .trim()
+                    .strip_prefix("Esocket")?
+                    .parse()
+                    .ok()?;
+                let file = label.file_name()?.to_str()?.replace("_label", "_input");
+                Some(EnergySource {
+                    domain: "package".to_string(),
+                    package: Some(package),
+                    input: label.with_file_name(file),
+                    range: None,
+                })
+            })
+            .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_energy() {
+        let sources = discover(&fixture("laptop"));
+        let energies: Vec<_> = sources.iter().filter_map(EnergySource::read).collect();
+        let domains: Vec<_> = energies.iter().map(|e| e.label(1)).collect();
+        assert_eq!(domains, vec!["Package 0", "Cores", "Uncore", "DRAM"]);
+        assert_eq!(energies[1].label(2), "Cores 0");
+
+        let old = EnergyCounter {
+            energy: energies[0].range.unwrap() - 1_000_000,
+            ..energies[0].clone()
+        };
+        assert_eq!(
+            energies[0].since(&old),
+            Some(energies[0].energy + 1_000_000)
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::path::{Path, PathBuf};
+
+use glob::glob;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+
+use super::{read_sysfs_thermal, rooted_pattern, KNOWN_HWMONS, KNOWN_THERMAL_ZONES};
+use crate::sensor::root;
+
+static CPU_SENSORS: Lazy<Vec<SensorInput>> = Lazy::new(|| {
+    let sensors = discover(&root());
+    tracing::debug!("{} CPU temperature inputs found", sensors.len());
+    sensors
+});
+
+/// A reading of one temperature input of the CPU, in °C.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CpuTemperature {
+    /// The label of the driver, e.g. `Package id 0`, `Core 3`, `Tctl` or
+    /// `Tccd1`.
+    pub label: String,
+    pub package: Option<usize>,
+    /// The core id of the topology, only for per core inputs.
+    pub core: Option<usize>,
+    pub value: f32,
+    pub max: Option<f32>,
+    pub crit: Option<f32>,
+}
+
+#[derive(Debug, Clone)]
+struct SensorInput {
+    label: String,
+    package: Option<usize>,
+    core: Option<usize>,
+    input: PathBuf,
+    max: Option<f32>,
+    crit: Option<f32>,
+}
+
+/// Reads every input found at the start, the unreadable ones are left out.
+pub fn get_temperatures() -> Vec<CpuTemperature> {
+    CPU_SENSORS.iter().filter_map(SensorInput::read).collect()
+}
+
+impl SensorInput {
+    fn read(&self) -> Option<CpuTemperature> {
+        Some(CpuTemperature {
+            label: self.label.clone(),
+            package: self.package,
+            core: self.core,
+            value: read_sysfs_thermal(&self.input).ok()?,
+            max: self.max,
+            crit: self.crit,
+        })
+    }
+}
+
+/// Every input of the known hwmons, one hwmon per package. Without them
+/// the known thermal zones and their trip points are used.
+fn discover(root: &Path) -> Vec<SensorInput> {
+    let mut sensors = vec![];
+    let Some(pattern) = rooted_pattern(root, "/sys/class/hwmon/hwmon*") else {
+        return sensors;
+    };
+    let mut hwmons: Vec<PathBuf> = glob(&pattern).unwrap().flatten().collect();
+    // hwmon10 after hwmon9
+    hwmons.sort_by_key(|e| {
+        e.file_name().and_then(|e| {
+            e.to_str()?
+                .trim_start_matches("hwmon")
+                .parse::<usize>()
+                .ok()
+        })
+    });
+
+    let mut packages = 0;
+    for hwmon in hwmons {
+        let Ok(name) = std::fs::read_to_string(hwmon.join("name")) else {
+            continue;
+        };
+        if !KNOWN_HWMONS.contains(&name.trim()) {
+            continue;
+        }
+
+        let mut inputs: Vec<(usize, String, PathBuf)> = glob(&format!(
+            "{}/temp*_input",
+            glob::Pattern::escape(&hwmon.to_string_lossy())
+        ))
+        .unwrap()
+        .flatten()
+        .filter_map(|input| {
+            let file = input.file_name()?.to_str()?;
+            let idx = file
+                .strip_prefix("temp")?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()?;
+            let label = std::fs::read_to_string(hwmon.join(format!("temp{idx}_label")))
+                .map(|e| e.trim().to_string())
+                .unwrap_or_else(|_| format!("temp{idx}"));
+            Some((idx, label, input))
+        })
+        .collect();
+        inputs.sort_by_key(|e| e.0);
+
+        // coretemp names its package, k10temp and zenpower are counted
+        let package = inputs
+            .iter()
+            .find_map(|(_, label, _)| label.strip_prefix("Package id ")?.parse().ok())
+            .unwrap_or(packages);
+        packages = package + 1;
+
+        for (idx, label, input) in inputs {
+            let threshold = |kind: &str| {
+                read_sysfs_thermal(hwmon.join(format!("temp{idx}_{kind}")))
+                    .ok()
+                    .filter(|e| *e > 0.)
+            };
+            sensors.push(SensorInput {
+                core: label.strip_prefix("Core ").and_then(|e| e.parse().ok()),
+                package: Some(package),
+                max: threshold("max"),
+                crit: threshold("crit"),
+                label,
+                input,
+            });
+        }
+    }
+
+    if sensors.is_empty() {
+        sensors = discover_thermal_zones(root);
+    }
+    sensors
+}
+
+fn discover_thermal_zones(root: &Path) -> Vec<SensorInput> {
+    let Some(pattern) = rooted_pattern(root, "/sys/class/thermal/thermal_zone*") else {
+        return vec![];
+    };
+    glob(&pattern)
+        .unwrap()
+        .flatten()
+        .filter_map(|zone| {
+            let kind = std::fs::read_to_string(zone.join("type")).ok()?;
+            let kind = kind.trim();
+            if !KNOWN_THERMAL_ZONES.contains(&kind) {
+                return None;
+            }
+            // the trip points are `critical`, `hot`, `passive` or `active`
+            let trip = |wanted: &[&str]| {
+                (0..16).find_map(|idx| {
+                    let kind = std::fs::read_to_string(zone.join(format!("trip_point_{idx}_type")))
+                        .ok()?;
+                    wanted
+                        .contains(&kind.trim())
+                        .then(|| {
+                            read_sysfs_thermal(zone.join(format!("trip_point_{idx}_temp"))).ok()
+                        })
+                        .flatten()
+                })
+            };
+            Some(SensorInput {
+                label: kind.to_string(),
+                package: None,
+                core: None,
+                input: zone.join("temp"),
+                max: trip(&["hot", "passive"]),
+                crit: trip(&["critical"]),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_discover() {
+        let sensors = discover(&fixture("laptop"));
+        let temperatures: Vec<_> = sensors.iter().filter_map(SensorInput::read).collect();
+        assert_eq!(temperatures.len(), 2);
+        assert_eq!(temperatures[0].label, "Package id 0");
+        assert_eq!(temperatures[0].crit, Some(100.));
+        assert_eq!(temperatures[1].core, Some(0));
+        assert_eq!(temperatures[1].value, 45.);
+
+        let zones = discover_thermal_zones(&fixture("laptop"));
+        assert_eq!(zones[0].label, "x86_pkg_temp");
+    }
+}
@@ -0,0 +1,300 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::sensor::rooted;
+
+/// Where a logical CPU sits, `None` if sysfs and /proc/cpuinfo don't say.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuThread {
+    pub id: usize,
+    pub package: Option<usize>,
+    pub die: Option<usize>,
+    pub core: Option<usize>,
+    pub node: Option<usize>,
+}
+
+/// One instance of a cache, e.g. the L2 of a core.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuCache {
+    pub level: u8,
+    /// `Data`, `Instruction` or `Unified`.
+    pub kind: String,
+    pub size: u64,
+    /// The logical CPUs sharing it.
+    pub cpus: Vec<usize>,
+}
+
+impl CpuCache {
+    /// `L1d`, `L1i`, `L2` ...
+    pub fn name(&self) -> String {
+        match self.kind.as_str() {
+            "Data" => format!("L{}d", self.level),
+            "Instruction" => format!("L{}i", self.level),
+            _ => format!("L{}", self.level),
+        }
+    }
+}
+
+/// Threads of a core, cores of a package and the caches between them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Topology {
+    pub threads: Vec<CpuThread>,
+    pub caches: Vec<CpuCache>,
+    /// The NUMA nodes and their logical CPUs.
+    pub nodes: Vec<(usize, Vec<usize>)>,
+}
+
+impl Topology {
+    /// Reads `/sys/devices/system/cpu/cpuN`, the ids missing there are
+    /// taken from the `physical id` and `core id` of /proc/cpuinfo.
+    pub fn read(root: &Path, cpuinfo: &[BTreeMap<String, String>]) -> Self {
+        let cpu_dir = rooted(root, "/sys/devices/system/cpu");
+        let mut ids: Vec<usize> = std::fs::read_dir(&cpu_dir)
+            .map(|dir| {
+                dir.flatten()
+                    .filter_map(|e| e.file_name().to_str()?.strip_prefix("cpu")?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        if ids.is_empty() {
+            ids = cpuinfo
+                .iter()
+                .filter_map(|e| e.get("processor")?.parse().ok())
+                .collect();
+        }
+        ids.sort_unstable();
+
+        let nodes = read_nodes(root);
+
+        let mut caches = vec![];
+        let threads = ids
+            .into_iter()
+            .map(|id| {
+                let dir = cpu_dir.join(format!("cpu{id}"));
+                let topology = |file: &str| read_trimmed(&dir.join("topology").join(file));
+                let block = cpuinfo
+                    .iter()
+                    .find(|e| e.get("processor").and_then(|e| e.parse().ok()) == Some(id));
+                let from_cpuinfo = |key: &str| block.and_then(|e| e.get(key)?.parse().ok());
+
+                for index in std::fs::read_dir(dir.join("cache"))
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                {
+                    if let Some(cache) = read_cache(&index.path(), id) {
+                        if !caches.contains(&cache) {
+                            caches.push(cache);
+                        }
+                    }
+                }
+
+                CpuThread {
+                    id,
+                    package: topology("physical_package_id")
+                        .and_then(|e| e.parse().ok())
+                        .or_else(|| from_cpuinfo("physical id")),
+                    // -1 on architectures without dies
+                    die: topology("die_id").and_then(|e| e.parse().ok()),
+                    core: topology("core_id")
+                        .and_then(|e| e.parse().ok())
+                        .or_else(|| from_cpuinfo("core id")),
+                    node: nodes
+                        .iter()
+                        .find(|(_, cpus)| cpus.contains(&id))
+                        .map(|(node, _)| *node),
+                }
+            })
+            .collect();
+        caches.sort_by(|a, b| (a.level, &a.kind, &a.cpus).cmp(&(b.level, &b.kind, &b.cpus)));
+
+        Self {
+            threads,
+            caches,
+            nodes,
+        }
+    }
+
+    pub fn packages(&self) -> usize {
+        self.unique(|e| e.package.map(|p| (p, 0, 0)))
+    }
+
+    pub fn dies(&self) -> usize {
+        self.unique(|e| e.package.map(|p| (p, e.die.unwrap_or(0), 0)))
+    }
+
+    pub fn cores(&self) -> usize {
+        self.unique(|e| {
+            e.core
+                .map(|c| (e.package.unwrap_or(0), e.die.unwrap_or(0), c))
+        })
+    }
+
+    fn unique(&self, key: impl Fn(&CpuThread) -> Option<(usize, usize, usize)>) -> usize {
+        self.threads
+            .iter()
+            .filter_map(key)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+
+    /// The logical CPUs by package and die, then by core.
+    pub fn groups(&self) -> BTreeMap<(usize, usize), BTreeMap<usize, Vec<usize>>> {
+        let mut groups: BTreeMap<_, BTreeMap<_, Vec<_>>> = BTreeMap::new();
+        for thread in self.threads.iter() {
+            groups
+                .entry((thread.package.unwrap_or(0), thread.die.unwrap_or(0)))
+                .or_default()
+                .entry(thread.core.unwrap_or(thread.id))
+                .or_default()
+                .push(thread.id);
+        }
+        groups
+    }
+
+    /// Every cache level with the size of one instance, the number of
+    /// instances and the threads sharing one, e.g. `("L2", 256K, 4, 2)`.
+    pub fn cache_summary(&self) -> Vec<(String, u64, usize, usize)> {
+        let mut summary: Vec<(String, u64, usize, usize)> = vec![];
+        for cache in self.caches.iter() {
+            let name = cache.name();
+            match summary.iter_mut().find(|e| e.0 == name) {
+                Some(entry) => entry.2 += 1,
+                None => summary.push((name, cache.size, 1, cache.cpus.len())),
+            }
+        }
+        summary
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path)
+        .ok()
+        .map(|e| e.trim().to_string())
+}
+
+fn read_cache(dir: &Path, cpu: usize) -> Option<CpuCache> {
+    let level = read_trimmed(&dir.join("level"))?.parse().ok()?;
+    let kind = read_trimmed(&dir.join("type"))?;
+    let size = parse_cache_size(&read_trimmed(&dir.join("size"))?)?;
+    let cpus = read_trimmed(&dir.join("shared_cpu_list"))
+        .and_then(|e| parse_cpu_list(&e).ok())
+        .unwrap_or_else(|| vec![cpu]);
+    Some(CpuCache {
+        level,
+        kind,
+        size,
+        cpus,
+    })
+}
+
+fn read_nodes(root: &Path) -> Vec<(usize, Vec<usize>)> {
+    let mut nodes: Vec<(usize, Vec<usize>)> =
+        std::fs::read_dir(rooted(root, "/sys/devices/system/node"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|e| {
+                let id = e.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+                let cpus = read_trimmed(&e.path().join("cpulist"))?;
+                Some((id, parse_cpu_list(&cpus).ok()?))
+            })
+            .collect();
+    nodes.sort_unstable();
+    nodes
+}
+
+/// `32K`, `8M` or plain bytes.
+fn parse_cache_size(size: &str) -> Option<u64> {
+    let (number, unit) = match size.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((idx, _)) => size.split_at(idx),
+        None => (size, ""),
+    };
+    let factor = match unit.trim() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => return None,
+    };
+    number.parse::<u64>().ok().map(|e| e * factor)
+}
+
+/// The list format of sysfs and taskset(1), e.g. `0-3,8-11`. An empty list
+/// is no cpu, e.g. the one of a node with only memory.
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>> {
+    let mut cpus = vec![];
+    for part in list.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+        match part.split_once('-') {
+            Some((start, end)) => {
+                let start: usize = start.trim().parse().context("invalid cpu")?;
+                let end: usize = end.trim().parse().context("invalid cpu")?;
+                if start > end {
+                    bail!("invalid cpu range {}", part)
+                }
+                cpus.extend(start..=end);
+            }
+            None => cpus.push(part.parse().context("invalid cpu")?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// The reverse of `parse_cpu_list`.
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = vec![];
+    for cpu in cpus.iter().copied() {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == cpu => *end = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .into_iter()
+        .map(|(start, end)| {
+            if start == end {
+                start.to_string()
+            } else {
+                format!("{start}-{end}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_topology() {
+        assert_eq!(parse_cpu_list("0-2,8").unwrap(), vec![0, 1, 2, 8]);
+        assert_eq!(parse_cpu_list(" 0 - 1, 3 ").unwrap(), vec![0, 1, 3]);
+        assert!(parse_cpu_list("").unwrap().is_empty());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("0-x").is_err());
+        assert_eq!(format_cpu_list(&[0, 1, 2, 8]), "0-2,8");
+        assert_eq!(parse_cache_size("3072K"), Some(3 << 20));
+
+        let topology = Topology::read(&fixture("laptop"), &[]);
+        assert_eq!(topology.threads.len(), 2);
+        assert_eq!(topology.threads[1].node, Some(0));
+        assert_eq!(
+            (topology.packages(), topology.dies(), topology.cores()),
+            (1, 1, 1)
+        );
+        assert_eq!(topology.groups()[&(0, 0)][&0], vec![0, 1]);
+        assert_eq!(
+            topology.cache_summary()[0],
+            ("L1d".to_string(), 32 << 10, 1, 2)
+        );
+        assert_eq!(topology.caches.len(), 4);
+    }
+}
@@ -3,28 +3,45 @@ use chin_tools::AResult;
 use nix::sys::statvfs::statvfs;
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::{
     collections::HashMap,
     fmt::Display,
     path::{Path, PathBuf},
+    time::Instant,
 };
 
 use crate::tarits::PathString;
 
-use super::{units::convert_storage, Sensor};
+use super::{deserialize_result, rooted, serialize_result, units::convert_storage, Sensor};
 
 const SYS_STATS: &str = r" *(?P<read_ios>[0-9]*) *(?P<read_merges>[0-9]*) *(?P<read_sectors>[0-9]*) *(?P<read_ticks>[0-9]*) *(?P<write_ios>[0-9]*) *(?P<write_merges>[0-9]*) *(?P<write_sectors>[0-9]*) *(?P<write_ticks>[0-9]*) *(?P<in_flight>[0-9]*) *(?P<io_ticks>[0-9]*) *(?P<time_in_queue>[0-9]*) *(?P<discard_ios>[0-9]*) *(?P<discard_merges>[0-9]*) *(?P<discard_sectors>[0-9]*) *(?P<discard_ticks>[0-9]*) *(?P<flush_ios>[0-9]*) *(?P<flush_ticks>[0-9]*)";
 
 static RE_DRIVE: Lazy<Regex> = Lazy::new(|| Regex::new(SYS_STATS).unwrap());
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct DriveData {
     pub inner: Drive,
     pub is_virtual: bool,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub writable: Result<bool>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub removable: Result<bool>,
     pub disk_stats: HashMap<String, usize>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub capacity: Result<u64>,
+    /// When `disk_stats` was read, rates are computed between two samples.
+    #[serde(skip, default = "Instant::now")]
+    pub sampled_at: Instant,
 }
 
 impl DriveData {
@@ -34,6 +51,7 @@ impl DriveData {
         let writable = inner.writable();
         let removable = inner.removable();
         let disk_stats = inner.sys_stats().unwrap_or_default();
+        let sampled_at = Instant::now();
         let capacity = inner.capacity();
 
         Self {
@@ -43,11 +61,12 @@ impl DriveData {
             removable,
             disk_stats,
             capacity,
+            sampled_at,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DriveType {
     CdDvdBluray,
     Emmc,
@@ -66,7 +85,7 @@ pub enum DriveType {
     Unknown,
 }
 
-#[derive(Debug, Clone, Default, Eq)]
+#[derive(Debug, Clone, Default, Eq, Serialize, Deserialize)]
 pub struct Drive {
     pub model: Option<String>,
     pub drive_type: DriveType,
@@ -134,9 +153,9 @@ impl Drive {
     ///
     /// Will return `Err` if the are errors during
     /// reading or parsing
-    pub fn get_sysfs_paths() -> Result<Vec<PathBuf>> {
+    pub fn get_sysfs_paths(root: &Path) -> Result<Vec<PathBuf>> {
         let mut list = Vec::new();
-        let mut entries = std::fs::read_dir("/sys/block")?;
+        let mut entries = std::fs::read_dir(rooted(root, "/sys/block"))?;
         while let Some(entry) = entries.next() {
             let entry = entry?;
             let block_device = entry.file_name().to_string_lossy().to_string();
@@ -331,7 +350,7 @@ impl Drive {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Partition {
     pub total_bytes: u64,
     pub free_bytes: u64,
@@ -341,8 +360,10 @@ pub struct Partition {
 }
 
 impl Partition {
-    pub fn fetch() -> AResult<Vec<Partition>> {
-        let lines = std::fs::read_to_string("/proc/mounts")?;
+    /// The mount points are looked up below `root` too, but reported as they
+    /// are listed in `/proc/mounts`.
+    pub fn fetch(root: &Path) -> AResult<Vec<Partition>> {
+        let lines = std::fs::read_to_string(rooted(root, "/proc/mounts"))?;
 
         let mut result = vec![];
 
@@ -353,7 +374,7 @@ impl Partition {
                 let mount_point = fields[1];
                 let point = fields[2];
 
-                if let Ok(stats) = statvfs(mount_point) {
+                if let Ok(stats) = statvfs(&rooted(root, mount_point)) {
                     let total_space_bytes = stats.blocks() * stats.fragment_size();
                     let available_space_bytes = stats.blocks_available() * stats.block_size();
 
@@ -392,3 +413,41 @@ impl Sensor for Drive {
         self.sysfs_path.to_filename()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_drives() {
+        let mut paths = Drive::get_sysfs_paths(&fixture("laptop")).unwrap();
+        paths.sort();
+        let drives: Vec<Drive> = paths.iter().map(Drive::from_sysfs).collect();
+
+        assert_eq!(drives.len(), 2);
+        assert_eq!(drives[0].block_device, "nvme0n1");
+        assert_eq!(drives[0].drive_type, DriveType::Nvme);
+        assert_eq!(drives[0].model.as_deref(), Some("Samsung SSD 980 PRO 1TB"));
+        assert_eq!(drives[0].capacity().unwrap(), 1000215216 * 512);
+        assert!(drives[0].writable().unwrap());
+        assert!(!drives[0].removable().unwrap());
+        assert_eq!(drives[1].drive_type, DriveType::Hdd);
+
+        let stats = drives[0].sys_stats().unwrap();
+        assert_eq!(stats["read_sectors"], 12488070);
+        assert_eq!(stats["write_sectors"], 22102282);
+        assert_eq!(stats["flush_ticks"], 19362);
+    }
+
+    #[test]
+    fn test_partitions() {
+        // `/boot/efi` does not exist in the fixture
+        let partitions = Partition::fetch(&fixture("laptop")).unwrap();
+        let mount_points: Vec<&str> = partitions.iter().map(|e| e.mount_point.as_str()).collect();
+        assert_eq!(mount_points, vec!["/", "/proc"]);
+        assert_eq!(partitions[0].device, "/dev/nvme0n1p2");
+        assert_eq!(partitions[0].fs_type, "ext4");
+    }
+}
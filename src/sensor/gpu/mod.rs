@@ -6,6 +6,7 @@ mod other;
 use anyhow::{bail, Context, Result};
 use chin_tools::AResult;
 use process_data::pci_slot::PciSlot;
+use serde::{Deserialize, Serialize};
 
 use std::{
     collections::HashMap,
@@ -13,10 +14,13 @@ use std::{
     str::FromStr,
 };
 
-use glob::glob;
+use glob::{glob, Pattern};
 
 use crate::{
-    sensor::pci::{get_device, Device},
+    sensor::{
+        pci::{get_device, Device},
+        rooted,
+    },
     tarits::None2NanString,
 };
 
@@ -26,7 +30,7 @@ pub const VID_AMD: u16 = 4098;
 pub const VID_INTEL: u16 = 32902;
 pub const VID_NVIDIA: u16 = 4318;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuData {
     pub id: String,
     pub pci_slot: PciSlot,
@@ -206,9 +210,12 @@ impl Gpu {
     ///
     /// Will return `Err` if there are problems detecting
     /// the GPUs in the system
-    pub fn get_gpus() -> Result<Vec<Gpu>> {
+    pub fn get_gpus(root: &Path) -> Result<Vec<Gpu>> {
+        let drm = rooted(root, "/sys/class/drm");
+        let drm = drm.to_str().context("error transforming PathBuf to str")?;
+
         let mut gpu_vec: Vec<Gpu> = Vec::new();
-        for entry in glob("/sys/class/drm/card?")?.flatten() {
+        for entry in glob(&format!("{}/card?", Pattern::escape(drm)))?.flatten() {
             if let Ok(gpu) = Self::from_sysfs_path(entry) {
                 gpu_vec.push(gpu);
             }
@@ -465,3 +472,19 @@ impl Gpu {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_gpus() {
+        // card0-eDP-1 is a connector, not a card
+        let gpus = Gpu::get_gpus(&fixture("laptop")).unwrap();
+        assert_eq!(gpus.len(), 1);
+        assert!(matches!(gpus[0], Gpu::Intel(_)));
+        assert_eq!(gpus[0].pci_slot().to_string(), "0000:00:02.0");
+    }
+}
@@ -1,8 +1,11 @@
-use std::process::Command;
+use std::{path::Path, process::Command};
 
 use anyhow::{bail, Context, Result};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use super::rooted;
 
 const TEMPLATE_RE_PRESENT: &str = r"MEMORY_DEVICE_%_PRESENT=(\d)";
 
@@ -45,7 +48,7 @@ static RE_SWAP_FREE: Lazy<Regex> = Lazy::new(|| Regex::new(r"SwapFree:\s*(\d*) k
 static RE_NUM_MEMORY_DEVICES: Lazy<Regex> =
     Lazy::new(|| Regex::new(r"MEMORY_ARRAY_NUM_DEVICES=(\d*)").unwrap());
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub struct MemoryData {
     pub total_mem: usize,
     pub available_mem: usize,
@@ -54,9 +57,9 @@ pub struct MemoryData {
 }
 
 impl MemoryData {
-    pub fn fetch(_req: ()) -> Result<Self> {
-        let proc_mem =
-            std::fs::read_to_string("/proc/meminfo").context("unable to read /proc/meminfo")?;
+    pub fn fetch(root: &Path) -> Result<Self> {
+        let proc_mem = std::fs::read_to_string(rooted(root, "/proc/meminfo"))
+            .context("unable to read /proc/meminfo")?;
 
         let total_mem = RE_MEM_TOTAL
             .captures(&proc_mem)
@@ -131,7 +134,7 @@ impl MemoryData {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryDevice {
     pub speed_mts: Option<u32>,
     pub form_factor: Option<String>,
@@ -290,3 +293,19 @@ pub fn pkexec_dmidecode() -> Result<Vec<MemoryDevice>> {
     tracing::debug!("Memory information obtained using dmidecode (privileged)");
     Ok(parse_dmidecode(String::from_utf8(output.stdout)?.as_str()))
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::MemoryData;
+
+    #[test]
+    fn test_meminfo() {
+        let data = MemoryData::fetch(&fixture("laptop")).unwrap();
+        assert_eq!(data.total_mem, 16303428 * 1024);
+        assert_eq!(data.available_mem, 9876544 * 1024);
+        assert_eq!(data.total_swap, 8388604 * 1024);
+        assert_eq!(data.free_swap, 8123260 * 1024);
+    }
+}
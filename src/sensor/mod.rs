@@ -1,4 +1,10 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::RwLock,
+};
+
 use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
 
 pub mod apps;
 pub mod battery;
@@ -9,7 +15,8 @@ pub mod memory;
 pub mod network;
 pub mod pci;
 pub mod process;
-#[allow(unused_variables)]
+pub mod process_detail;
+pub mod process_events;
 pub mod settings;
 pub mod time;
 pub mod units;
@@ -32,8 +39,54 @@ pub static IS_FLATPAK: Lazy<bool> = Lazy::new(|| {
     is_flatpak
 });
 
+/// Where `/proc` and `/sys` are read from, changed by `--root`.
+static ROOT: Lazy<RwLock<PathBuf>> = Lazy::new(|| RwLock::new(PathBuf::from("/")));
+
+/// Reads every sensor below `root` from now on, e.g. a mounted container.
+pub fn set_root<P: Into<PathBuf>>(root: P) {
+    *ROOT.write().unwrap() = root.into();
+}
+
+pub fn root() -> PathBuf {
+    ROOT.read().unwrap().clone()
+}
+
+/// `path` below `root`, e.g. `rooted(root, "/proc/stat")`.
+pub fn rooted(root: &Path, path: &str) -> PathBuf {
+    root.join(path.trim_start_matches('/'))
+}
+
+/// A captured filesystem tree under `fixtures/`.
+#[cfg(test)]
+pub(crate) fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("fixtures")
+        .join(name)
+}
+
 pub trait Sensor {
     fn get_type_name(&self) -> &'static str;
     fn get_id(&self) -> String;
     fn get_name(&self) -> String;
 }
+
+/// Serializes a failed reading as `null`, used by the headless output.
+pub(crate) fn serialize_result<T, S>(
+    result: &anyhow::Result<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    T: serde::Serialize,
+    S: serde::Serializer,
+{
+    result.as_ref().ok().serialize(serializer)
+}
+
+/// The counterpart of `serialize_result` for recordings, `null` becomes an error.
+pub(crate) fn deserialize_result<'de, T, D>(deserializer: D) -> Result<anyhow::Result<T>, D::Error>
+where
+    T: serde::Deserialize<'de>,
+    D: serde::Deserializer<'de>,
+{
+    Ok(Option::<T>::deserialize(deserializer)?.ok_or_else(|| anyhow::anyhow!("not recorded")))
+}
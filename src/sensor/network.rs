@@ -1,21 +1,37 @@
 use std::{
-    collections::HashMap, ffi::OsString, fmt::Display, path::{Path, PathBuf}
+    collections::HashMap,
+    ffi::OsString,
+    fmt::Display,
+    path::{Path, PathBuf},
+    time::Instant,
 };
 
 use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 
 use crate::tarits::PathString;
 
-use super::{pci::get_device, Sensor};
+use super::{deserialize_result, pci::get_device, rooted, serialize_result, Sensor};
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct NetworkData {
     pub sysfs_path: String,
     pub hw_address: Option<String>,
     pub is_virtual: bool,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub received_bytes: Result<usize>,
+    #[serde(
+        serialize_with = "serialize_result",
+        deserialize_with = "deserialize_result"
+    )]
     pub sent_bytes: Result<usize>,
     pub display_name: String,
+    /// When the counters were read, rates are computed between two samples.
+    #[serde(skip, default = "Instant::now")]
+    pub sampled_at: Instant,
 }
 
 impl NetworkData {
@@ -23,6 +39,7 @@ impl NetworkData {
         let is_virtual = inner.is_virtual();
         let received_bytes = inner.received_bytes();
         let sent_bytes = inner.sent_bytes();
+        let sampled_at = Instant::now();
         let display_name = inner.display_name();
 
         Self {
@@ -32,11 +49,12 @@ impl NetworkData {
             received_bytes,
             sent_bytes,
             display_name,
+            sampled_at,
         }
     }
 }
 
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub enum InterfaceType {
     Bluetooth,
     Bridge,
@@ -110,7 +128,7 @@ impl InterfaceType {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 /// Represents a network interface found in /sys/class/net
 pub struct NetworkInterface {
     pub interface_name: OsString,
@@ -161,9 +179,9 @@ impl PartialEq for NetworkInterface {
 }
 
 impl NetworkInterface {
-    pub fn get_sysfs_paths() -> Result<Vec<PathBuf>> {
+    pub fn get_sysfs_paths(root: &Path) -> Result<Vec<PathBuf>> {
         let mut list = Vec::new();
-        let mut entries = std::fs::read_dir("/sys/class/net")?;
+        let mut entries = std::fs::read_dir(rooted(root, "/sys/class/net"))?;
         while let Some(entry) = entries.next() {
             let entry = entry?;
             let block_device = entry.file_name().to_string_lossy().to_string();
@@ -344,3 +362,24 @@ impl Sensor for NetworkInterface {
         self.sysfs_path.to_filename()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_interfaces() {
+        let paths = NetworkInterface::get_sysfs_paths(&fixture("laptop")).unwrap();
+        assert_eq!(paths.len(), 1, "lo is skipped");
+
+        let interface = NetworkInterface::from_sysfs(&paths[0]);
+        assert_eq!(interface.interface_name, "wlp3s0");
+        assert!(matches!(interface.interface_type, InterfaceType::Wlan));
+        assert_eq!(interface.driver_name.as_deref(), Some("iwlwifi"));
+        assert_eq!(interface.hw_address.as_deref(), Some("a0:b1:c2:d3:e4:f5"));
+        assert_eq!(interface.received_bytes().unwrap(), 123456789);
+        assert_eq!(interface.sent_bytes().unwrap(), 9876543);
+    }
+}
@@ -1,13 +1,19 @@
 use anyhow::{bail, Context, Result};
 use chin_tools::AResult;
+use nix::{
+    errno::Errno,
+    sys::signal::{kill, Signal},
+    unistd::Pid,
+};
 use process_data::{pci_slot::PciSlot, Containerization, GpuUsageStats, ProcessData};
-use std::{collections::BTreeMap, fmt::Display, process::Command};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt::Display, path::Path, process::Command};
 use strum_macros::Display;
 use tracing::debug;
 
 use crate::tarits::NaNDefault;
 
-use super::{NUM_CPUS, TICK_RATE};
+use super::{cpu::topology::parse_cpu_list, root, rooted, NUM_CPUS, TICK_RATE};
 
 /// Represents a process that can be found within procfs.
 #[derive(Debug, Clone, PartialEq)]
@@ -23,17 +29,107 @@ pub struct Process {
 }
 
 // TODO: Better name?
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display, Serialize, Deserialize)]
 pub enum ProcessAction {
     TERM,
     STOP,
     KILL,
     CONT,
 }
+
+impl ProcessAction {
+    pub fn signal(&self) -> Signal {
+        match self {
+            ProcessAction::TERM => Signal::SIGTERM,
+            ProcessAction::STOP => Signal::SIGSTOP,
+            ProcessAction::KILL => Signal::SIGKILL,
+            ProcessAction::CONT => Signal::SIGCONT,
+        }
+    }
+}
+
+/// `kill(1)` candidates used for the `pkexec` fallback, `pkexec` needs an absolute path.
+const KILL_PATHS: &[&str] = &["/usr/bin/kill", "/bin/kill"];
+
+/// Signals all of `pids` with a single `pkexec`, so the password is asked
+/// once for every process of an app.
+pub fn pkexec_kill(pids: &[i32], action: ProcessAction) -> Result<()> {
+    let kill_path = KILL_PATHS
+        .iter()
+        .find(|path| Path::new(path).exists())
+        .context("unable to find kill executable")?;
+
+    let status_code = Command::new("pkexec")
+        .arg("--disable-internal-agent")
+        .arg(kill_path)
+        .arg(format!("-{action}"))
+        .args(pids.iter().map(|pid| pid.to_string()))
+        .output()?
+        .status
+        .code()
+        .context("no status code?")?;
+
+    if [0, 3, 64].contains(&status_code) {
+        // 0 := successful; 3 := process not found; 64 := some of the pids
+        // not found, which we don't care about because that might happen
+        // because we killed the process' parent first, killing the child
+        // before we explicitly do
+        debug!(
+            "Successfully {action}ed {:?} with elevated privileges",
+            pids
+        );
+        Ok(())
+    } else {
+        bail!(
+            "couldn't kill {:?} with elevated privileges due to unknown reasons, status code: {}",
+            pids,
+            status_code
+        )
+    }
+}
+
+/// A row of the thread listing in the process detail page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ThreadItem {
+    pub tid: i32,
+    pub comm: String,
+    pub state: char,
+    pub processor: i32,
+    pub cpu_time_ratio: f32,
+    pub voluntary_ctxt_switches: Option<u64>,
+    pub nonvoluntary_ctxt_switches: Option<u64>,
+}
+
+impl ThreadItem {
+    pub fn from_process(thread: &Process) -> Self {
+        Self {
+            tid: thread.data.pid,
+            comm: thread.data.comm.clone(),
+            state: thread.data.state,
+            processor: thread.data.processor,
+            cpu_time_ratio: thread.cpu_time_ratio(),
+            voluntary_ctxt_switches: thread.data.voluntary_ctxt_switches,
+            nonvoluntary_ctxt_switches: thread.data.nonvoluntary_ctxt_switches,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessGpuUsage {
+    pub gpu: f32,
+    pub enc: f32,
+    pub dec: f32,
+    pub mem: u64,
+}
+
 /// Convenience struct for displaying running processes
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProcessItem {
     pub pid: i32,
+    pub ppid: i32,
+    pub state: char,
+    pub nice: i32,
+    pub cpus_allowed: Option<String>,
     pub user: String,
     pub display_name: String,
     pub memory_usage: usize,
@@ -94,40 +190,104 @@ impl Process {
         }
     }
 
-    pub fn execute_process_action(&self, _action: ProcessAction) -> Result<()> {
+    /// Sets the nice value of every thread, since Linux keeps one per thread.
+    /// The returned error can be downcasted to `nix::errno::Errno`.
+    pub fn set_nice(&self, nice: i32) -> Result<()> {
+        for tid in self.task_ids() {
+            // SAFETY: setpriority only reads its plain integer arguments.
+            let ret = unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice) };
+            if ret != 0 {
+                return Err(Errno::last().into());
+            }
+        }
+        debug!("Successfully set nice of {} to {}", self.data.pid, nice);
         Ok(())
     }
 
-    #[allow(dead_code)]
-    fn pkexec_execute_process_action(&self, action: &str, kill_path: &str) -> Result<()> {
-        let status_code = Command::new("pkexec")
-            .args([
-                "--disable-internal-agent",
-                kill_path,
-                action,
-                self.data.pid.to_string().as_str(),
-            ])
-            .output()?
-            .status
-            .code()
-            .context("no status code?")?;
-
-        if status_code == 0 || status_code == 3 {
-            // 0 := successful; 3 := process not found which we don't care
-            // about because that might happen because we killed the
-            // process' parent first, killing the child before we explicitly do
-            debug!(
-                "Successfully {action}ed {} with elevated privileges",
-                self.data.pid
-            );
-            Ok(())
-        } else {
-            bail!(
-                "couldn't kill {} with elevated privileges due to unknown reasons, status code: {}",
-                self.data.pid,
-                status_code
-            )
+    /// Pins every thread to `cpus`, which have to be online.
+    pub fn set_affinity(&self, cpus: &[usize]) -> Result<()> {
+        let online = std::fs::read_to_string(rooted(&root(), "/sys/devices/system/cpu/online"))
+            .ok()
+            .and_then(|e| parse_cpu_list(&e).ok());
+        for cpu in cpus {
+            if *cpu >= libc::CPU_SETSIZE as usize {
+                bail!(
+                    "cpu {} is beyond the {} cpus of an affinity",
+                    cpu,
+                    libc::CPU_SETSIZE
+                );
+            }
+            if online.as_ref().is_some_and(|online| !online.contains(cpu)) {
+                bail!("cpu {} is not online", cpu);
+            }
+        }
+
+        // SAFETY: cpu_set_t is a plain bit mask, all zeros is the empty set.
+        let mut set = unsafe { std::mem::zeroed::<libc::cpu_set_t>() };
+        // SAFETY: `set` is a valid cpu_set_t.
+        unsafe { libc::CPU_ZERO(&mut set) };
+        for cpu in cpus {
+            // SAFETY: every cpu was checked to be below CPU_SETSIZE, so the
+            // bit is inside of `set`.
+            unsafe { libc::CPU_SET(*cpu, &mut set) };
         }
+
+        for tid in self.task_ids() {
+            // SAFETY: `set` outlives the call and the size is the one of its
+            // type, the kernel only reads it.
+            let ret = unsafe {
+                libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &set)
+            };
+            if ret != 0 {
+                return Err(Errno::last().into());
+            }
+        }
+        debug!(
+            "Successfully set affinity of {} to {:?}",
+            self.data.pid, cpus
+        );
+        Ok(())
+    }
+
+    /// Sends the signal of `action` to this process, the returned error can be
+    /// downcasted to `nix::errno::Errno` to tell EPERM and ESRCH apart.
+    pub fn execute_process_action(&self, action: ProcessAction) -> Result<()> {
+        kill(Pid::from_raw(self.data.pid), action.signal())?;
+        debug!("Successfully sent {} to {}", action.signal(), self.data.pid);
+        Ok(())
+    }
+
+    /// The threads of this process, only the process itself if they can not
+    /// be listed.
+    fn task_ids(&self) -> Vec<i32> {
+        std::fs::read_dir(rooted(&root(), &format!("/proc/{}/task", self.data.pid)))
+            .map(|dir| {
+                dir.flatten()
+                    .filter_map(|e| e.file_name().to_str()?.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_else(|_| vec![self.data.pid])
+    }
+
+    /// Same as `execute_process_action`, but goes through `pkexec` so processes
+    /// owned by other users can be signaled as well.
+    pub fn execute_process_action_elevated(&self, action: ProcessAction) -> Result<()> {
+        pkexec_kill(&[self.data.pid], action)
+    }
+
+    /// Replaces the data with a newer reading, keeping the old one to compute
+    /// the rates.
+    pub fn update(&mut self, process_data: ProcessData) {
+        self.cpu_time_last = self
+            .data
+            .user_cpu_time
+            .saturating_add(self.data.system_cpu_time);
+        self.timestamp_last = self.data.timestamp;
+        self.read_bytes_last = self.data.read_bytes;
+        self.write_bytes_last = self.data.write_bytes;
+        self.gpu_usage_stats_last = self.data.gpu_usage_stats.clone();
+
+        self.data = process_data;
     }
 
     #[must_use]
@@ -256,6 +416,43 @@ impl Process {
         returned_gpu_usage
     }
 
+    fn engine_usage(&self, now: u64, last: u64, nvidia: bool) -> f32 {
+        if nvidia {
+            now as f32 / 100.0
+        } else if last == 0 {
+            0.0
+        } else {
+            ((now.saturating_sub(last) as f32)
+                / (self.data.timestamp.saturating_sub(self.timestamp_last) as f32).nan_default(0.0))
+                / 1_000_000.0
+        }
+    }
+
+    /// Same as `gpu_usage`, `enc_usage`, `dec_usage` and `gpu_mem_usage`, but
+    /// for every GPU on its own instead of the busiest one.
+    #[must_use]
+    pub fn gpu_usage_by_slot(&self) -> Vec<(PciSlot, ProcessGpuUsage)> {
+        self.data
+            .gpu_usage_stats
+            .iter()
+            .map(|(gpu, usage)| {
+                let old = self.gpu_usage_stats_last.get(gpu);
+                let engine = |now: u64, last: Option<u64>| {
+                    last.map_or(0.0, |last| self.engine_usage(now, last, usage.nvidia))
+                };
+                (
+                    *gpu,
+                    ProcessGpuUsage {
+                        gpu: engine(usage.gfx, old.map(|e| e.gfx)),
+                        enc: engine(usage.enc, old.map(|e| e.enc)),
+                        dec: engine(usage.dec, old.map(|e| e.dec)),
+                        mem: usage.mem,
+                    },
+                )
+            })
+            .collect()
+    }
+
     #[must_use]
     pub fn gpu_mem_usage(&self) -> u64 {
         self.data
@@ -280,7 +477,7 @@ impl Process {
     }
 }
 
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LoadAvg {
     pub last1: f32,
     pub last5: f32,
@@ -300,8 +497,8 @@ impl Display for LoadAvg {
 
 /// `proc/loadavg`  
 /// The first three fields in this file are load average figures giving the number of jobs in the run queue (state R) or waiting for disk I/O (state D) averaged over 1, 5, and 15 minutes. They are the same as the load average numbers given by uptime(1) and other programs. The fourth field consists of two numbers separated by a slash (/). The first of these is the number of currently runnable kernel scheduling entities (processes, threads). The value after the slash is the number of kernel scheduling entities that currently exist on the system. The fifth field is the PID of the process that was most recently created on the system.
-pub fn read_proc_loadavg() -> AResult<LoadAvg> {
-    let s = std::fs::read_to_string("/proc/loadavg")?;
+pub fn read_proc_loadavg(root: &Path) -> AResult<LoadAvg> {
+    let s = std::fs::read_to_string(rooted(root, "/proc/loadavg"))?;
     let mut iter = s.split(" ");
     Ok(LoadAvg {
         last1: iter.next().context("first")?.parse::<f32>()?,
@@ -311,8 +508,12 @@ pub fn read_proc_loadavg() -> AResult<LoadAvg> {
     })
 }
 
-pub fn read_proc_uptime() -> AResult<u64> {
-    std::fs::read_to_string("/proc/uptime")
+pub fn read_proc_uptime(root: &Path) -> AResult<u64> {
+    read_proc_uptime_secs(root).map(|uptime_secs: f64| uptime_secs as u64)
+}
+
+pub fn read_proc_uptime_secs(root: &Path) -> AResult<f64> {
+    std::fs::read_to_string(rooted(root, "/proc/uptime"))
         .context("unable to read /proc/uptime")
         .and_then(|procfs| {
             procfs
@@ -326,5 +527,51 @@ pub fn read_proc_uptime() -> AResult<u64> {
                 .parse::<f64>()
                 .context("unable to parse /proc/uptime")
         })
-        .map(|uptime_secs: f64| uptime_secs as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use process_data::ProcessData;
+
+    use crate::sensor::fixture;
+
+    use super::*;
+
+    #[test]
+    fn test_loadavg_and_uptime() {
+        let root = fixture("laptop");
+        let load = read_proc_loadavg(&root).unwrap();
+        assert_eq!(load.last1, 0.52);
+        assert_eq!(load.last15, 0.59);
+        assert_eq!(load.processes, "2/1024");
+        assert_eq!(read_proc_uptime(&root).unwrap(), 35040);
+    }
+
+    #[test]
+    fn test_process_data() {
+        let root = fixture("laptop");
+        let mut processes = ProcessData::all_process_data(&root).unwrap();
+        processes.sort_by_key(|e| e.pid);
+        assert_eq!(processes.len(), 2);
+
+        let firefox = &processes[1];
+        assert_eq!(firefox.pid, 4242);
+        assert_eq!(firefox.ppid, 1);
+        assert_eq!(firefox.comm, "firefox");
+        assert_eq!(firefox.state, 'S');
+        assert_eq!(firefox.user_cpu_time, 52310);
+        assert_eq!(firefox.system_cpu_time, 8812);
+        assert_eq!(firefox.starttime, 201337);
+        assert_eq!(firefox.cpus_allowed.as_deref(), Some("0-1"));
+        assert_eq!(firefox.voluntary_ctxt_switches, Some(1520));
+        assert_eq!(firefox.cgroup.as_deref(), Some("firefox"));
+        assert_eq!(firefox.read_bytes, Some(1228800));
+        assert_eq!(firefox.write_bytes, Some(40960));
+
+        let mut threads = ProcessData::all_thread_data(&root, 4242).unwrap();
+        threads.sort_by_key(|e| e.pid);
+        let tids: Vec<i32> = threads.iter().map(|e| e.pid).collect();
+        assert_eq!(tids, vec![4242, 4250]);
+        assert_eq!(threads[1].comm, "Renderer");
+    }
 }
@@ -0,0 +1,205 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use process_data::pci_slot::PciSlot;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    process::{ProcessGpuUsage, ThreadItem},
+    root, rooted,
+};
+
+/// Everything under `/proc/<pid>` which is too expensive to read for every
+/// process on each refresh, only read for the process opened in the detail page.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessDetail {
+    pub pid: i32,
+    pub cmdline: Vec<String>,
+    pub cwd: Option<PathBuf>,
+    pub exe: Option<PathBuf>,
+    pub environ: Vec<String>,
+    /// fd number and what it points to, empty if not permitted.
+    pub fds: Vec<(u32, String)>,
+    pub threads: Option<usize>,
+    /// Filled by the process worker, which keeps the previous readings.
+    pub tasks: Vec<ThreadItem>,
+    pub smaps: Option<SmapsRollup>,
+    /// Raw content of `/proc/<pid>/cgroup`.
+    pub cgroup: Vec<String>,
+    /// Namespace name and its `type:[inode]` link.
+    pub namespaces: Vec<(String, String)>,
+    pub limits: Vec<ProcessLimit>,
+    /// Key and value of every line in `/proc/<pid>/io`.
+    pub io: Vec<(String, u64)>,
+    pub gpus: Vec<(PciSlot, ProcessGpuUsage)>,
+}
+
+/// `/proc/<pid>/smaps_rollup`, in bytes.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct SmapsRollup {
+    pub rss: u64,
+    pub pss: u64,
+    /// Private clean + private dirty.
+    pub uss: u64,
+    pub swap: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProcessLimit {
+    pub name: String,
+    pub soft: String,
+    pub hard: String,
+    pub unit: String,
+}
+
+impl ProcessDetail {
+    pub fn read(pid: i32) -> Result<Self> {
+        let proc_path = rooted(&root(), &format!("/proc/{pid}"));
+
+        let cmdline = std::fs::read_to_string(proc_path.join("cmdline"))
+            .with_context(|| format!("unable to read cmdline of {pid}"))?
+            .split('\0')
+            .filter(|e| !e.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        let environ = std::fs::read(proc_path.join("environ"))
+            .map(|environ| {
+                environ
+                    .split(|c| *c == 0)
+                    .filter(|e| !e.is_empty())
+                    .map(|e| String::from_utf8_lossy(e).to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let threads = std::fs::read_to_string(proc_path.join("status"))
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("Threads:"))
+                    .and_then(|e| e.trim().parse().ok())
+            });
+
+        let cgroup = std::fs::read_to_string(proc_path.join("cgroup"))
+            .map(|e| e.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+
+        Ok(Self {
+            pid,
+            cmdline,
+            cwd: std::fs::read_link(proc_path.join("cwd")).ok(),
+            exe: std::fs::read_link(proc_path.join("exe")).ok(),
+            environ,
+            fds: Self::read_fds(&proc_path),
+            threads,
+            tasks: vec![],
+            smaps: Self::read_smaps_rollup(&proc_path).ok(),
+            cgroup,
+            namespaces: Self::read_namespaces(&proc_path),
+            limits: Self::read_limits(&proc_path).unwrap_or_default(),
+            io: Self::read_io(&proc_path).unwrap_or_default(),
+            gpus: vec![],
+        })
+    }
+
+    fn read_fds(proc_path: &Path) -> Vec<(u32, String)> {
+        let Ok(dir) = std::fs::read_dir(proc_path.join("fd")) else {
+            return vec![];
+        };
+
+        let mut fds: Vec<(u32, String)> = dir
+            .flatten()
+            .filter_map(|entry| {
+                let fd = entry.file_name().to_str()?.parse().ok()?;
+                let target = std::fs::read_link(entry.path()).ok()?;
+                Some((fd, target.to_string_lossy().to_string()))
+            })
+            .collect();
+        fds.sort_by_key(|(fd, _)| *fd);
+        fds
+    }
+
+    fn read_smaps_rollup(proc_path: &Path) -> Result<SmapsRollup> {
+        let smaps = std::fs::read_to_string(proc_path.join("smaps_rollup"))?;
+        let mut rollup = SmapsRollup::default();
+
+        for line in smaps.lines() {
+            let mut iter = line.split_whitespace();
+            let (Some(key), Some(value)) = (iter.next(), iter.next()) else {
+                continue;
+            };
+            // all values are in kB
+            let Ok(value) = value.parse::<u64>().map(|e| e * 1024) else {
+                continue;
+            };
+            match key {
+                "Rss:" => rollup.rss = value,
+                "Pss:" => rollup.pss = value,
+                "Private_Clean:" | "Private_Dirty:" => rollup.uss += value,
+                "Swap:" => rollup.swap = value,
+                _ => {}
+            }
+        }
+
+        Ok(rollup)
+    }
+
+    fn read_namespaces(proc_path: &Path) -> Vec<(String, String)> {
+        let Ok(dir) = std::fs::read_dir(proc_path.join("ns")) else {
+            return vec![];
+        };
+
+        let mut namespaces: Vec<(String, String)> = dir
+            .flatten()
+            .filter_map(|entry| {
+                let target = std::fs::read_link(entry.path()).ok()?;
+                Some((
+                    entry.file_name().to_string_lossy().to_string(),
+                    target.to_string_lossy().to_string(),
+                ))
+            })
+            .collect();
+        namespaces.sort();
+        namespaces
+    }
+
+    /// The columns of `/proc/<pid>/limits` are aligned with spaces and the
+    /// names contain spaces too, so split by the offsets of the header.
+    fn read_limits(proc_path: &Path) -> Result<Vec<ProcessLimit>> {
+        let limits = std::fs::read_to_string(proc_path.join("limits"))?;
+        let mut lines = limits.lines();
+        let header = lines.next().context("empty limits")?;
+        let soft = header.find("Soft Limit").context("no soft limit")?;
+        let hard = header.find("Hard Limit").context("no hard limit")?;
+        let units = header.find("Units").context("no units")?;
+
+        let column = |line: &str, start: usize, end: usize| -> String {
+            line.get(start..end.min(line.len()))
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+
+        Ok(lines
+            .map(|line| ProcessLimit {
+                name: column(line, 0, soft),
+                soft: column(line, soft, hard),
+                hard: column(line, hard, units),
+                unit: column(line, units, line.len()),
+            })
+            .collect())
+    }
+
+    fn read_io(proc_path: &Path) -> Result<Vec<(String, u64)>> {
+        let io = std::fs::read_to_string(proc_path.join("io"))?;
+        Ok(io
+            .lines()
+            .filter_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                Some((key.trim().to_string(), value.trim().parse().ok()?))
+            })
+            .collect())
+    }
+}
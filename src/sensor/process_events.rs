@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::process::ProcessItem;
+
+/// How many events are kept, older ones are dropped.
+const EVENT_LOG_CAPACITY: usize = 2000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProcessEventKind {
+    Started,
+    Exited,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessEvent {
+    pub kind: ProcessEventKind,
+    pub time: DateTime<Local>,
+    /// The process as it was last seen.
+    pub item: ProcessItem,
+    /// In seconds, only known when the process exited. At most one scan
+    /// interval longer than the real lifetime.
+    pub lifetime: Option<f64>,
+}
+
+impl ProcessEvent {
+    /// The final CPU time for exited processes, in seconds.
+    pub fn cpu_time(&self) -> f64 {
+        self.item.user_cpu_time + self.item.system_cpu_time
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProcessEventLog {
+    events: VecDeque<ProcessEvent>,
+}
+
+impl ProcessEventLog {
+    pub fn new() -> Self {
+        Self {
+            events: VecDeque::with_capacity(EVENT_LOG_CAPACITY),
+        }
+    }
+
+    pub fn started(&mut self, item: ProcessItem) {
+        self.push(ProcessEvent {
+            kind: ProcessEventKind::Started,
+            time: Local::now(),
+            item,
+            lifetime: None,
+        });
+    }
+
+    /// `uptime` is the current `/proc/uptime`, to compute the lifetime of the process.
+    pub fn exited(&mut self, item: ProcessItem, uptime: Option<f64>) {
+        let lifetime = uptime.map(|uptime| (uptime - item.starttime).max(0.));
+        self.push(ProcessEvent {
+            kind: ProcessEventKind::Exited,
+            time: Local::now(),
+            item,
+            lifetime,
+        });
+    }
+
+    fn push(&mut self, event: ProcessEvent) {
+        if self.events.len() >= EVENT_LOG_CAPACITY {
+            self.events.pop_back();
+        }
+        self.events.push_front(event);
+    }
+
+    /// Newest first.
+    pub fn iter(&self) -> impl Iterator<Item = &ProcessEvent> {
+        self.events.iter()
+    }
+}
@@ -1,8 +1,48 @@
-use anyhow::Result;
-use strum_macros::{Display, EnumString, FromRepr};
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::RwLock,
+};
+
+use anyhow::{Context, Result};
+use ini::Ini;
+use once_cell::sync::Lazy;
+use strum_macros::{Display, EnumIter, EnumString, FromRepr};
+
+/// `$XDG_CONFIG_HOME/restop/restop.ini`, falls back to `~/.config`.
+static CONFIG_PATH: Lazy<Option<PathBuf>> = Lazy::new(|| {
+    std::env::var("XDG_CONFIG_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            std::env::var("HOME")
+                .ok()
+                .map(|home| PathBuf::from(home).join(".config"))
+        })
+        .map(|dir| dir.join("restop").join("restop.ini"))
+});
+
+static CONFIG: Lazy<RwLock<Ini>> = Lazy::new(|| {
+    let ini = CONFIG_PATH
+        .as_ref()
+        .filter(|path| path.exists())
+        .and_then(|path| match Ini::load_from_file(path) {
+            Ok(ini) => Some(ini),
+            Err(err) => {
+                tracing::error!("unable to load config {:?}: {}", path, err);
+                None
+            }
+        });
+    RwLock::new(ini.unwrap_or_default())
+});
+
+/// Set by `--refresh-speed` and the +/- keys, lasts until the refresh speed
+/// is changed on the settings page.
+static REFRESH_SPEED_OVERRIDE: RwLock<Option<RefreshSpeed>> = RwLock::new(None);
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, EnumString, Display, Hash, FromRepr)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr, EnumIter)]
 pub enum Base {
     #[default]
     Decimal,
@@ -10,7 +50,7 @@ pub enum Base {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, EnumString, Display, Hash, FromRepr)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr, EnumIter)]
 pub enum TemperatureUnit {
     #[default]
     Celsius,
@@ -19,7 +59,8 @@ pub enum TemperatureUnit {
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, EnumString, Display, Hash, FromRepr)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr, EnumIter)]
+#[strum(ascii_case_insensitive)]
 pub enum RefreshSpeed {
     VerySlow,
     Slow,
@@ -39,58 +80,207 @@ impl RefreshSpeed {
             RefreshSpeed::VeryFast => 0.25,
         }
     }
+
+    pub fn faster(&self) -> Self {
+        Self::from_repr((*self as u8).saturating_add(1)).unwrap_or(*self)
+    }
+
+    pub fn slower(&self) -> Self {
+        Self::from_repr((*self as u8).saturating_sub(1)).unwrap_or(*self)
+    }
 }
 
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr, EnumIter)]
 pub enum SidebarMeterType {
-    #[default]
     ProgressBar,
+    #[default]
     Graph,
 }
 
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, EnumString, Display, Hash, FromRepr, EnumIter)]
+#[strum(ascii_case_insensitive)]
+pub enum KeymapPreset {
+    #[default]
+    Default,
+    /// Adds hjkl, `/` to filter and `q` to quit.
+    Vim,
+}
+
 pub const SETTINGS: Settings = Settings {};
 
 #[derive(Clone, Debug, Hash)]
 pub struct Settings {}
 
 impl Settings {
+    fn get(&self, section: &str, key: &str) -> Option<String> {
+        CONFIG
+            .read()
+            .ok()?
+            .get_from(Some(section), key)
+            .map(ToString::to_string)
+    }
+
+    fn set<S: Into<String>>(&self, section: &str, key: &str, value: S) -> Result<()> {
+        let mut config = CONFIG
+            .write()
+            .map_err(|_| anyhow::anyhow!("config is poisoned"))?;
+        config.with_section(Some(section)).set(key, value);
+
+        let path = CONFIG_PATH.as_ref().context("unable to find config dir")?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        config.write_to_file(path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> Option<&'static Path> {
+        CONFIG_PATH.as_deref()
+    }
+
+    /// Every key and value of `section`.
+    fn section(&self, section: &str) -> Vec<(String, String)> {
+        CONFIG
+            .read()
+            .ok()
+            .and_then(|config| {
+                config.section(Some(section)).map(|section| {
+                    section
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect()
+                })
+            })
+            .unwrap_or_default()
+    }
+
+    /// Falls back to the default if the value is missing or invalid.
+    fn get_parsed<T: FromStr + Default>(&self, section: &str, key: &str) -> T {
+        self.get(section, key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default()
+    }
+
     pub fn temperature_unit(&self) -> TemperatureUnit {
-        TemperatureUnit::default()
+        self.get_parsed("general", "temperature_unit")
     }
 
     pub fn set_temperature_unit(&self, value: TemperatureUnit) -> Result<()> {
-        Ok(())
+        self.set("general", "temperature_unit", value.to_string())
     }
 
     pub fn base(&self) -> Base {
-        Base::default()
+        self.get_parsed("general", "base")
     }
 
     pub fn set_base(&self, value: Base) -> Result<()> {
-        Ok(())
+        self.set("general", "base", value.to_string())
     }
+
+    pub fn last_viewed_page(&self) -> Option<String> {
+        self.get("general", "last_viewed_page")
+    }
+
     pub fn set_last_viewed_page<S: AsRef<str>>(&self, value: S) -> Result<()> {
-        Ok(())
+        self.set("general", "last_viewed_page", value.as_ref())
     }
 
     pub fn refresh_speed(&self) -> RefreshSpeed {
-        Default::default()
+        REFRESH_SPEED_OVERRIDE
+            .read()
+            .ok()
+            .and_then(|speed| *speed)
+            .unwrap_or_else(|| self.get_parsed("general", "refresh_speed"))
     }
 
     pub fn set_refresh_speed(&self, value: RefreshSpeed) -> Result<()> {
-        Ok(())
+        if let Ok(mut speed) = REFRESH_SPEED_OVERRIDE.write() {
+            speed.take();
+        }
+        self.set("general", "refresh_speed", value.to_string())
+    }
+
+    /// Changes the refresh speed of this session only, the config is untouched.
+    pub fn override_refresh_speed(&self, value: RefreshSpeed) {
+        if let Ok(mut speed) = REFRESH_SPEED_OVERRIDE.write() {
+            speed.replace(value);
+        }
+    }
+
+    /// The sampling interval in seconds of one resource type, read from the
+    /// `refresh` section, e.g. `battery = 10`.
+    pub fn refresh_interval(&self, type_name: &str) -> Option<f32> {
+        self.get("refresh", &type_name.to_lowercase())
+            .and_then(|value| value.parse::<f32>().ok())
+            .filter(|secs| secs.is_finite() && *secs > 0.)
     }
 
     pub fn sidebar_meter_type(&self) -> SidebarMeterType {
-        SidebarMeterType::default()
+        self.get_parsed("general", "sidebar_meter_type")
     }
 
     pub fn set_sidebar_meter_type(&self, value: SidebarMeterType) -> Result<()> {
-        Ok(())
+        self.set("general", "sidebar_meter_type", value.to_string())
+    }
+
+    /// The name of the color scheme.
+    pub fn theme(&self) -> Option<String> {
+        self.get("general", "theme")
+    }
+
+    pub fn set_theme<S: Into<String>>(&self, value: S) -> Result<()> {
+        self.set("general", "theme", value)
+    }
+
+    pub fn keymap(&self) -> KeymapPreset {
+        self.get_parsed("general", "keymap")
+    }
+
+    pub fn set_keymap(&self, value: KeymapPreset) -> Result<()> {
+        self.set("general", "keymap", value.to_string())
+    }
+
+    /// Action and keys of every entry in the `keys` section.
+    pub fn key_bindings(&self) -> Vec<(String, String)> {
+        self.section("keys")
     }
 
     pub fn network_bits(&self) -> bool {
-        false
+        self.get_parsed("general", "network_bits")
+    }
+
+    pub fn set_network_bits(&self, value: bool) -> Result<()> {
+        self.set("general", "network_bits", value.to_string())
+    }
+
+    /// Whether the CPU page may switch the governor and the energy
+    /// preference, off by default.
+    pub fn cpu_tuning(&self) -> bool {
+        self.get_parsed("cpu", "tuning")
+    }
+
+    pub fn set_cpu_tuning(&self, value: bool) -> Result<()> {
+        self.set("cpu", "tuning", value.to_string())
+    }
+
+    /// Comma separated names of the process table columns.
+    pub fn process_columns(&self) -> Option<String> {
+        self.get("process", "columns")
+    }
+
+    pub fn set_process_columns<S: Into<String>>(&self, value: S) -> Result<()> {
+        self.set("process", "columns", value)
+    }
+
+    /// Name and rule of every entry in the `alerts` section.
+    pub fn alert_rules(&self) -> Vec<(String, String)> {
+        self.section("alerts")
+    }
+
+    /// The shell command run when the alert `name` fires.
+    pub fn alert_command(&self, name: &str) -> Option<String> {
+        self.get("alert_commands", name)
     }
 }
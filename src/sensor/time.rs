@@ -4,6 +4,8 @@ use chrono::{DateTime, NaiveDateTime};
 use once_cell::sync::Lazy;
 use process_data::unix_as_millis;
 
+use super::{process::read_proc_uptime_secs, root};
+
 pub fn human_time() -> String {
     let fmt = "%Y-%m-%d %H:%M:%S";
 
@@ -12,20 +14,7 @@ pub fn human_time() -> String {
 
 static BOOT_TIMESTAMP: Lazy<Option<i64>> = Lazy::new(|| {
     let unix_timestamp = (unix_as_millis() / 1000) as i64;
-    std::fs::read_to_string("/proc/uptime")
-        .context("unable to read /proc/uptime")
-        .and_then(|procfs| {
-            procfs
-                .split(' ')
-                .next()
-                .map(str::to_string)
-                .context("unable to split /proc/uptime")
-        })
-        .and_then(|uptime_str| {
-            uptime_str
-                .parse::<f64>()
-                .context("unable to parse /proc/uptime")
-        })
+    read_proc_uptime_secs(&root())
         .map(|uptime_secs| unix_timestamp - uptime_secs as i64)
         .ok()
 });
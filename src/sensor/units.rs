@@ -1,3 +1,4 @@
+use anyhow::Context;
 use strum::IntoEnumIterator;
 use strum_macros::{Display, EnumIter, EnumString};
 
@@ -382,6 +383,27 @@ pub fn convert_energy(watthours: f64, integer: bool) -> String {
     }
 }
 
+/// `5`, `2.5%`, `512K`, `1G`, `1.5GiB`...
+pub fn parse_size(value: &str) -> anyhow::Result<f64> {
+    let lower = value.to_lowercase();
+    let trimmed = lower
+        .trim_end_matches('%')
+        .trim_end_matches("ib")
+        .trim_end_matches('b');
+    let (num, scale) = match trimmed.chars().last() {
+        Some('k') => (&trimmed[..trimmed.len() - 1], 1024f64),
+        Some('m') => (&trimmed[..trimmed.len() - 1], 1024f64.powi(2)),
+        Some('g') => (&trimmed[..trimmed.len() - 1], 1024f64.powi(3)),
+        Some('t') => (&trimmed[..trimmed.len() - 1], 1024f64.powi(4)),
+        _ => (trimmed, 1.),
+    };
+
+    num.parse::<f64>()
+        .map(|n| n * scale)
+        .ok()
+        .with_context(|| format!("invalid number `{}`", value))
+}
+
 #[cfg(test)]
 mod test {
     use crate::sensor::units::convert_seconds;
@@ -0,0 +1,127 @@
+use crossterm::event::KeyEvent;
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Stylize},
+    text::{Line, Text},
+    widgets::Clear,
+    Frame,
+};
+
+use crate::{
+    component::render_border,
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme,
+    },
+};
+
+/// Lists the key bindings of the contexts it was opened in, shown in place
+/// of the current page.
+#[derive(Debug, Default)]
+pub struct HelpPage {
+    /// The title and the bindings of every context.
+    sections: Vec<(String, Vec<(String, &'static str)>)>,
+    scroll: usize,
+}
+
+impl HelpPage {
+    /// `page` is the name of the focused page, `None` if the sidebar is
+    /// focused.
+    pub fn new(page: Option<(&str, Option<KeyContext>)>, replay: bool) -> Self {
+        let mut sections = vec![];
+        match page {
+            Some((name, context)) => {
+                sections.push(("Page".to_string(), keymap::bindings(KeyContext::Global)));
+                match context {
+                    Some(KeyContext::Filter) => {
+                        sections.push(("Filter".to_string(), Self::filter_bindings()))
+                    }
+                    Some(context) => sections.push((name.to_string(), keymap::bindings(context))),
+                    None => {}
+                }
+            }
+            None => sections.push(("Sidebar".to_string(), keymap::bindings(KeyContext::Global))),
+        }
+        if replay {
+            sections.push(("Replay".to_string(), keymap::bindings(KeyContext::Replay)));
+        }
+        Self {
+            sections,
+            scroll: 0,
+        }
+    }
+
+    /// The input takes every character, only a few keys are left.
+    fn filter_bindings() -> Vec<(String, &'static str)> {
+        vec![
+            (
+                "text".to_string(),
+                "e.g. user:postgres cpu>5 mem>1G name~^java",
+            ),
+            ("left, right".to_string(), "Move the cursor"),
+            ("home, end".to_string(), "Jump to the start or end"),
+            ("backspace".to_string(), "Delete a character"),
+            (keymap::keys(Action::Back), "Clear and close the filter"),
+            (keymap::keys(Action::Help), "This help"),
+        ]
+    }
+
+    /// Returns false when the page should be closed.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        match keymap::lookup(key, KeyContext::Global) {
+            Some(Action::Up) => self.scroll = self.scroll.saturating_sub(1),
+            Some(Action::Down) => self.scroll = self.scroll.saturating_add(1),
+            Some(Action::PageUp) => self.scroll = self.scroll.saturating_sub(10),
+            Some(Action::PageDown) => self.scroll = self.scroll.saturating_add(10),
+            Some(Action::Help | Action::Close | Action::Quit) => return false,
+            _ => {}
+        }
+        true
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, rect: Rect, active: bool) {
+        frame.render_widget(Clear, rect);
+
+        let palette = theme::palette();
+        let width = self
+            .sections
+            .iter()
+            .flat_map(|(_, bindings)| bindings.iter().map(|(keys, _)| keys.len()))
+            .max()
+            .unwrap_or(0)
+            + 2;
+
+        let mut lines = vec![];
+        for (title, bindings) in self.sections.iter() {
+            if !lines.is_empty() {
+                lines.push(Line::raw(""));
+            }
+            lines.push(
+                Line::raw(title.clone())
+                    .add_modifier(Modifier::BOLD)
+                    .patch_style(palette.header()),
+            );
+            for (keys, description) in bindings {
+                lines.push(Line::raw(format!("{:<width$}{}", keys, description)));
+            }
+        }
+
+        let inner = Rect {
+            x: rect.x.saturating_add(1),
+            y: rect.y.saturating_add(1),
+            width: rect.width.saturating_sub(2),
+            height: rect.height.saturating_sub(2),
+        };
+        let visible = inner.height.saturating_sub(1) as usize;
+        self.scroll = self.scroll.min(lines.len().saturating_sub(visible));
+
+        let mut lines: Vec<Line> = lines.into_iter().skip(self.scroll).take(visible).collect();
+        lines.push(
+            Line::raw(format!("↑/↓ scroll  {} close", keymap::keys(Action::Close)))
+                .style(palette.hint()),
+        );
+
+        frame.render_widget(Text::from(lines), inner);
+        render_border(active, rect, frame.buffer_mut());
+    }
+}
@@ -0,0 +1,499 @@
+//! The actions keys are bound to. The `keymap` setting picks a preset, the
+//! `keys` section of the config rebinds single actions, e.g.
+//!
+//! ```ini
+//! [general]
+//! keymap = Vim
+//!
+//! [keys]
+//! sort_cpu = alt+c f6
+//! freeze =
+//! ```
+//!
+//! Keys are separated by spaces, an empty value unbinds the action.
+
+use std::{
+    fmt::Display,
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{bail, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use once_cell::sync::Lazy;
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumIter, EnumString};
+
+use crate::sensor::settings::{KeymapPreset, SETTINGS};
+
+static KEYMAP: Lazy<RwLock<Arc<Keymap>>> = Lazy::new(|| RwLock::new(Arc::new(Keymap::load())));
+
+/// Where an action applies, the help lists the bindings by context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum KeyContext {
+    /// Always, unless the focused page takes the key first.
+    Global,
+    Process,
+    Cpu,
+    /// The filter input of the process page, only lists the keys.
+    Filter,
+    Replay,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumString, Display, EnumIter)]
+#[strum(serialize_all = "snake_case")]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    RefreshFaster,
+    RefreshSlower,
+    Freeze,
+    ScrubOlder,
+    ScrubNewer,
+    ScrubOlderFar,
+    ScrubNewerFar,
+    Settings,
+    Help,
+    Quit,
+    Close,
+    PageUp,
+    PageDown,
+
+    Back,
+    Filter,
+    Events,
+    SortPid,
+    SortCpu,
+    SortMem,
+    SortCmd,
+    Columns,
+    TreeView,
+    AppsView,
+    Fold,
+    Open,
+    Signal,
+    Renice,
+    Affinity,
+
+    Governor,
+    EnergyPreference,
+
+    PlayPause,
+    SeekForward,
+    SeekBack,
+    SeekForwardFar,
+    SeekBackFar,
+    ReplayFaster,
+    ReplaySlower,
+}
+
+impl Action {
+    pub fn context(&self) -> KeyContext {
+        match self {
+            Action::Up
+            | Action::Down
+            | Action::Left
+            | Action::Right
+            | Action::RefreshFaster
+            | Action::RefreshSlower
+            | Action::Freeze
+            | Action::ScrubOlder
+            | Action::ScrubNewer
+            | Action::ScrubOlderFar
+            | Action::ScrubNewerFar
+            | Action::Settings
+            | Action::Help
+            | Action::Quit
+            | Action::Close
+            | Action::PageUp
+            | Action::PageDown => KeyContext::Global,
+            Action::Back
+            | Action::Filter
+            | Action::Events
+            | Action::SortPid
+            | Action::SortCpu
+            | Action::SortMem
+            | Action::SortCmd
+            | Action::Columns
+            | Action::TreeView
+            | Action::AppsView
+            | Action::Fold
+            | Action::Open
+            | Action::Signal
+            | Action::Renice
+            | Action::Affinity => KeyContext::Process,
+            Action::Governor | Action::EnergyPreference => KeyContext::Cpu,
+            Action::PlayPause
+            | Action::SeekForward
+            | Action::SeekBack
+            | Action::SeekForwardFar
+            | Action::SeekBackFar
+            | Action::ReplayFaster
+            | Action::ReplaySlower => KeyContext::Replay,
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Up => "Previous block or line",
+            Action::Down => "Next block or line",
+            Action::Left => "Focus the sidebar",
+            Action::Right => "Focus the page",
+            Action::RefreshFaster => "Refresh faster",
+            Action::RefreshSlower => "Refresh slower",
+            Action::Freeze => "Freeze the histories",
+            Action::ScrubOlder => "Frozen: one interval back",
+            Action::ScrubNewer => "Frozen: one interval forward",
+            Action::ScrubOlderFar => "Frozen: ten intervals back",
+            Action::ScrubNewerFar => "Frozen: ten intervals forward",
+            Action::Settings => "Settings",
+            Action::Help => "This help",
+            Action::Quit => "Quit, ctrl+c always quits",
+            Action::Close => "Close the help or the settings",
+            Action::PageUp => "Scroll a page up",
+            Action::PageDown => "Scroll a page down",
+            Action::Back => "Close the filter, events, details or columns",
+            Action::Filter => "Filter the processes",
+            Action::Events => "Show process starts and exits",
+            Action::SortPid => "Sort by PID",
+            Action::SortCpu => "Sort by CPU",
+            Action::SortMem => "Sort by memory",
+            Action::SortCmd => "Sort by command",
+            Action::Columns => "Choose the columns",
+            Action::TreeView => "Toggle the tree view",
+            Action::AppsView => "Toggle the apps view",
+            Action::Fold => "Fold the subtree or app",
+            Action::Open => "Show the details",
+            Action::Signal => "Send a signal",
+            Action::Renice => "Change the nice value",
+            Action::Affinity => "Change the CPU affinity",
+            Action::Governor => "Switch to the next governor",
+            Action::EnergyPreference => "Switch to the next energy preference",
+            Action::PlayPause => "Play or pause",
+            Action::SeekForward => "10 seconds forward",
+            Action::SeekBack => "10 seconds back",
+            Action::SeekForwardFar => "1 minute forward",
+            Action::SeekBackFar => "1 minute back",
+            Action::ReplayFaster => "Play faster",
+            Action::ReplaySlower => "Play slower",
+        }
+    }
+
+    fn default_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["up"],
+            Action::Down => &["down"],
+            Action::Left => &["left"],
+            Action::Right => &["right"],
+            Action::RefreshFaster => &["+", "="],
+            Action::RefreshSlower => &["-"],
+            Action::Freeze => &["z", "pause"],
+            Action::ScrubOlder => &["["],
+            Action::ScrubNewer => &["]"],
+            Action::ScrubOlderFar => &["{"],
+            Action::ScrubNewerFar => &["}"],
+            Action::Settings => &["f10"],
+            Action::Help => &["?", "f1"],
+            Action::Quit => &[],
+            Action::Close => &["esc", "q"],
+            Action::PageUp => &["pageup"],
+            Action::PageDown => &["pagedown"],
+            Action::Back => &["esc", "backspace"],
+            Action::Filter => &["ctrl+s"],
+            Action::Events => &["alt+e"],
+            Action::SortPid => &["alt+p"],
+            Action::SortCpu => &["alt+c"],
+            Action::SortMem => &["alt+m"],
+            Action::SortCmd => &["alt+n"],
+            Action::Columns => &["f2", "alt+o"],
+            Action::TreeView => &["alt+t"],
+            Action::AppsView => &["alt+a"],
+            Action::Fold => &["space"],
+            Action::Open => &["enter"],
+            Action::Signal => &["delete", "alt+k"],
+            Action::Renice => &["alt+r"],
+            Action::Affinity => &["alt+f"],
+            Action::Governor => &["alt+g"],
+            Action::EnergyPreference => &["alt+e"],
+            Action::PlayPause => &["p"],
+            Action::SeekForward => &["."],
+            Action::SeekBack => &[","],
+            Action::SeekForwardFar => &[">"],
+            Action::SeekBackFar => &["<"],
+            Action::ReplayFaster => &["*"],
+            Action::ReplaySlower => &["/"],
+        }
+    }
+
+    /// Added to the defaults by the vim preset.
+    fn vim_keys(&self) -> &'static [&'static str] {
+        match self {
+            Action::Up => &["k"],
+            Action::Down => &["j"],
+            Action::Left => &["h"],
+            Action::Right => &["l"],
+            Action::Filter => &["/"],
+            Action::Quit => &["q"],
+            _ => &[],
+        }
+    }
+}
+
+/// A key with its modifiers, e.g. `alt+c`, `f2` or `space`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Shift is part of the character, `?` is typed with or without it.
+    pub fn matches(&self, key: &KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Char(_) => {
+                self.code == key.code && self.modifiers == key.modifiers - KeyModifiers::SHIFT
+            }
+            _ => self.code == key.code && self.modifiers == key.modifiers,
+        }
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut rest = s;
+        let mut modifiers = KeyModifiers::NONE;
+        loop {
+            let lower = rest.to_lowercase();
+            let (modifier, len) = if lower.starts_with("ctrl+") && rest.len() > 5 {
+                (KeyModifiers::CONTROL, 5)
+            } else if lower.starts_with("alt+") && rest.len() > 4 {
+                (KeyModifiers::ALT, 4)
+            } else {
+                break;
+            };
+            modifiers |= modifier;
+            rest = &rest[len..];
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_lowercase().as_str() {
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "enter" => KeyCode::Enter,
+                "esc" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" => KeyCode::Delete,
+                "insert" => KeyCode::Insert,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                "pause" => KeyCode::Pause,
+                "space" => KeyCode::Char(' '),
+                name => match name.strip_prefix('f').and_then(|e| e.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => bail!("unknown key `{}`", s),
+                },
+            },
+        };
+        Ok(Self { code, modifiers })
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Pause => write!(f, "pause"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Keymap {
+    bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl Keymap {
+    fn load() -> Self {
+        Self::new(SETTINGS.keymap(), &SETTINGS.key_bindings())
+    }
+
+    /// The keys of `preset` with `overrides` from the `keys` section.
+    fn new(preset: KeymapPreset, overrides: &[(String, String)]) -> Self {
+        let parse = |keys: &mut dyn Iterator<Item = &str>| -> Vec<KeyBinding> {
+            keys.filter_map(|key| match key.parse() {
+                Ok(key) => Some(key),
+                Err(err) => {
+                    tracing::error!("invalid key binding: {}", err);
+                    None
+                }
+            })
+            .collect()
+        };
+
+        let bindings = Action::iter()
+            .map(|action| {
+                let mut keys = action.default_keys().to_vec();
+                if preset == KeymapPreset::Vim {
+                    keys.extend(action.vim_keys());
+                }
+                let keys = match overrides.iter().find(|(k, _)| *k == action.to_string()) {
+                    Some((_, value)) => parse(&mut value.split_whitespace()),
+                    None => parse(&mut keys.into_iter()),
+                };
+                (action, keys)
+            })
+            .collect();
+
+        for (name, _) in overrides {
+            if Action::from_str(name).is_err() {
+                tracing::error!("unknown action {} in the keys section", name);
+            }
+        }
+
+        Self { bindings }
+    }
+
+    fn lookup(&self, key: &KeyEvent, context: KeyContext) -> Option<Action> {
+        self.bindings
+            .iter()
+            .filter(|(action, _)| action.context() == context)
+            .find(|(_, keys)| keys.iter().any(|e| e.matches(key)))
+            .map(|(action, _)| *action)
+    }
+
+    fn keys(&self, action: Action) -> &[KeyBinding] {
+        self.bindings
+            .iter()
+            .find(|(e, _)| *e == action)
+            .map_or(&[], |(_, keys)| keys.as_slice())
+    }
+}
+
+fn current() -> Arc<Keymap> {
+    KEYMAP
+        .read()
+        .map(|e| e.clone())
+        .unwrap_or_else(|_| Arc::new(Keymap::load()))
+}
+
+/// The action `key` is bound to in `context`.
+pub fn lookup(key: &KeyEvent, context: KeyContext) -> Option<Action> {
+    current().lookup(key, context)
+}
+
+/// E.g. `f2, alt+o`, empty if the action is unbound.
+pub fn keys(action: Action) -> String {
+    current()
+        .keys(action)
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// The bound actions of `context` with their keys.
+pub fn bindings(context: KeyContext) -> Vec<(String, &'static str)> {
+    Action::iter()
+        .filter(|e| e.context() == context)
+        .map(|e| (keys(e), e.description()))
+        .filter(|(keys, _)| !keys.is_empty())
+        .collect()
+}
+
+/// Reads the preset and the `keys` section again.
+pub fn reload() {
+    if let Ok(mut keymap) = KEYMAP.write() {
+        *keymap = Arc::new(Keymap::load());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::sensor::settings::KeymapPreset;
+
+    use super::{Action, KeyBinding, KeyContext, Keymap};
+
+    #[test]
+    fn test_keymap() {
+        let binding: KeyBinding = "alt+c".parse().unwrap();
+        assert!(binding.matches(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT)));
+        assert!(!binding.matches(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::NONE)));
+        assert_eq!(binding.to_string(), "alt+c");
+        let binding: KeyBinding = "?".parse().unwrap();
+        assert!(binding.matches(&KeyEvent::new(KeyCode::Char('?'), KeyModifiers::SHIFT)));
+        assert_eq!("F2".parse::<KeyBinding>().unwrap().to_string(), "f2");
+        assert_eq!(
+            "ctrl++".parse::<KeyBinding>().unwrap().to_string(),
+            "ctrl++"
+        );
+        assert!("hyper+x".parse::<KeyBinding>().is_err());
+        assert!("f13".parse::<KeyBinding>().is_err());
+
+        let j = KeyEvent::new(KeyCode::Char('j'), KeyModifiers::NONE);
+        let f6 = KeyEvent::new(KeyCode::F(6), KeyModifiers::NONE);
+        let alt_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::ALT);
+
+        let keymap = Keymap::new(KeymapPreset::Default, &[]);
+        assert_eq!(keymap.lookup(&j, KeyContext::Global), None);
+        assert_eq!(
+            keymap.lookup(&alt_c, KeyContext::Process),
+            Some(Action::SortCpu)
+        );
+        assert_eq!(keymap.lookup(&alt_c, KeyContext::Global), None);
+
+        let overrides = vec![
+            ("sort_cpu".to_string(), "f6".to_string()),
+            ("freeze".to_string(), "".to_string()),
+        ];
+        let keymap = Keymap::new(KeymapPreset::Vim, &overrides);
+        assert_eq!(keymap.lookup(&j, KeyContext::Global), Some(Action::Down));
+        assert_eq!(
+            keymap.lookup(&f6, KeyContext::Process),
+            Some(Action::SortCpu)
+        );
+        assert_eq!(keymap.lookup(&alt_c, KeyContext::Process), None);
+        assert!(keymap.keys(Action::Freeze).is_empty());
+
+        // q quits with vim keys and only closes the help or settings otherwise
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        assert_eq!(keymap.lookup(&q, KeyContext::Global), Some(Action::Quit));
+        let keymap = Keymap::new(KeymapPreset::Default, &[]);
+        assert_eq!(keymap.lookup(&q, KeyContext::Global), Some(Action::Close));
+    }
+}
@@ -1,3 +1,6 @@
+pub mod help_page;
+pub mod keymap;
+pub mod settings_page;
 pub mod sidebar_and_page;
 pub mod theme;
 
@@ -17,7 +20,8 @@ pub trait Navigator {
         focused: Option<usize>,
     );
 
-    fn handle_event<'a>(&mut self, event: &NavigatorEvent, args: NavigatorArgs<'a>);
+    /// Returns false if the event is left for the app.
+    fn handle_event<'a>(&mut self, event: &NavigatorEvent, args: NavigatorArgs<'a>) -> bool;
 
     fn focus_left(&mut self) {}
     fn focus_right(&mut self) {}
@@ -72,7 +76,7 @@ impl Navigator for LayoutType {
         }
     }
 
-    fn handle_event<'a>(&mut self, event: &NavigatorEvent, args: NavigatorArgs<'a>) {
+    fn handle_event<'a>(&mut self, event: &NavigatorEvent, args: NavigatorArgs<'a>) -> bool {
         match self {
             LayoutType::SidebarAndPage(sp) => sp.handle_event(event, args),
         }
@@ -96,4 +100,6 @@ pub enum NavigatorEvent {
 
 pub struct NavigatorArgs<'a> {
     pub resources: &'a mut Vec<ResourceType>,
+    /// Lists the replay controls in the help.
+    pub replay: bool,
 }
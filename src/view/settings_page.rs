@@ -0,0 +1,191 @@
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Stylize},
+    text::{Line, Text},
+    widgets::Clear,
+    Frame,
+};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+
+use crate::{
+    component::render_border,
+    sensor::settings::SETTINGS,
+    utils::is_only_char,
+    view::{
+        keymap::{self, Action, KeyContext},
+        theme,
+    },
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+enum SettingItem {
+    TemperatureUnit,
+    Base,
+    RefreshSpeed,
+    SidebarMeterType,
+    NetworkBits,
+    CpuTuning,
+    Theme,
+    Keymap,
+}
+
+/// The next (or previous) variant of `current`, wrapping around.
+fn cycle<T: IntoEnumIterator + PartialEq>(current: T, forward: bool) -> T {
+    let variants: Vec<T> = T::iter().collect();
+    let idx = variants.iter().position(|e| *e == current).unwrap_or(0);
+    let idx = if forward {
+        (idx + 1) % variants.len()
+    } else {
+        (idx + variants.len() - 1) % variants.len()
+    };
+    variants.into_iter().nth(idx).unwrap_or(current)
+}
+
+impl SettingItem {
+    fn label(&self) -> &'static str {
+        match self {
+            SettingItem::TemperatureUnit => "Temperature Unit",
+            SettingItem::Base => "Storage Prefixes",
+            SettingItem::RefreshSpeed => "Refresh Speed",
+            SettingItem::SidebarMeterType => "Sidebar Meter",
+            SettingItem::NetworkBits => "Network Speed in Bits",
+            SettingItem::CpuTuning => "CPU Governor Keys",
+            SettingItem::Theme => "Theme",
+            SettingItem::Keymap => "Key Bindings",
+        }
+    }
+
+    fn value(&self) -> String {
+        match self {
+            SettingItem::TemperatureUnit => SETTINGS.temperature_unit().to_string(),
+            SettingItem::Base => SETTINGS.base().to_string(),
+            SettingItem::RefreshSpeed => format!(
+                "{} ({}s)",
+                SETTINGS.refresh_speed(),
+                SETTINGS.refresh_speed().ui_refresh_interval()
+            ),
+            SettingItem::SidebarMeterType => SETTINGS.sidebar_meter_type().to_string(),
+            SettingItem::NetworkBits => SETTINGS.network_bits().to_string(),
+            SettingItem::CpuTuning => SETTINGS.cpu_tuning().to_string(),
+            SettingItem::Theme => theme::palette().name.clone(),
+            SettingItem::Keymap => SETTINGS.keymap().to_string(),
+        }
+    }
+
+    fn cycle(&self, forward: bool) -> Result<()> {
+        match self {
+            SettingItem::TemperatureUnit => {
+                SETTINGS.set_temperature_unit(cycle(SETTINGS.temperature_unit(), forward))
+            }
+            SettingItem::Base => SETTINGS.set_base(cycle(SETTINGS.base(), forward)),
+            SettingItem::RefreshSpeed => {
+                SETTINGS.set_refresh_speed(cycle(SETTINGS.refresh_speed(), forward))
+            }
+            SettingItem::SidebarMeterType => {
+                SETTINGS.set_sidebar_meter_type(cycle(SETTINGS.sidebar_meter_type(), forward))
+            }
+            SettingItem::NetworkBits => SETTINGS.set_network_bits(!SETTINGS.network_bits()),
+            SettingItem::CpuTuning => SETTINGS.set_cpu_tuning(!SETTINGS.cpu_tuning()),
+            SettingItem::Theme => {
+                let names = theme::names();
+                let current = theme::palette().name.clone();
+                let idx = names.iter().position(|e| *e == current).unwrap_or(0);
+                let idx = if forward {
+                    (idx + 1) % names.len()
+                } else {
+                    (idx + names.len() - 1) % names.len()
+                };
+                theme::switch(&names[idx])?;
+                SETTINGS.set_theme(names[idx].as_str())
+            }
+            SettingItem::Keymap => {
+                SETTINGS.set_keymap(cycle(SETTINGS.keymap(), forward))?;
+                keymap::reload();
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Edits the values of the config file, shown in place of the current page.
+/// Every change is written to the file at once.
+#[derive(Debug, Default)]
+pub struct SettingsPage {
+    cursor: usize,
+    error: Option<String>,
+}
+
+impl SettingsPage {
+    /// Returns false when the page should be closed.
+    pub fn handle_key(&mut self, key: &KeyEvent) -> bool {
+        let len = SettingItem::iter().count();
+        let forward = match keymap::lookup(key, KeyContext::Global) {
+            Some(Action::Up) => {
+                self.cursor = self.cursor.saturating_sub(1);
+                None
+            }
+            Some(Action::Down) => {
+                self.cursor = (self.cursor + 1).min(len - 1);
+                None
+            }
+            Some(Action::Left) => Some(false),
+            Some(Action::Right) => Some(true),
+            Some(Action::Settings | Action::Close | Action::Quit) => return false,
+            _ if key.code == KeyCode::Enter || is_only_char(key, ' ') => Some(true),
+            _ => None,
+        };
+
+        if let Some(forward) = forward {
+            if let Some(item) = SettingItem::iter().nth(self.cursor) {
+                match item.cycle(forward) {
+                    Ok(_) => {
+                        self.error.take();
+                    }
+                    Err(err) => {
+                        tracing::error!("unable to save settings: {}", err);
+                        self.error.replace(err.to_string());
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    pub fn render(&self, frame: &mut Frame, rect: Rect, active: bool) {
+        frame.render_widget(Clear, rect);
+
+        let palette = theme::palette();
+        let mut lines = vec![Line::raw("Settings").add_modifier(Modifier::BOLD)];
+        for (idx, item) in SettingItem::iter().enumerate() {
+            let line = Line::raw(format!("{:<24}< {} >", item.label(), item.value()));
+            lines.push(if idx == self.cursor {
+                line.patch_style(palette.selection())
+            } else {
+                line
+            });
+        }
+        lines.push(Line::raw(""));
+        if let Some(err) = self.error.as_ref() {
+            lines.push(Line::raw(err.clone()).style(palette.alert()));
+        }
+        if let Some(path) = SETTINGS.path() {
+            lines.push(Line::raw(format!("Saved to {}", path.display())).style(palette.hint()));
+        }
+        lines.push(
+            Line::raw(format!("←/→ change  {} close", keymap::keys(Action::Close)))
+                .style(palette.hint()),
+        );
+
+        let inner = Rect {
+            x: rect.x.saturating_add(1),
+            y: rect.y.saturating_add(1),
+            width: rect.width.saturating_sub(2),
+            height: rect.height.saturating_sub(2),
+        };
+        frame.render_widget(Text::from(lines), inner);
+        render_border(active, rect, frame.buffer_mut());
+    }
+}
@@ -1,5 +1,6 @@
+use std::time::Duration;
+
 use chrono::{DateTime, Local};
-use crossterm::event::KeyCode;
 use ratatui::{
     layout::{Constraint, Layout, Rect},
     style::{Style, Stylize},
@@ -7,9 +8,17 @@ use ratatui::{
     Frame,
 };
 
-use crate::{component::stateful_lines::StatefulGroupedLines, resource::ResourceType};
+use crate::{
+    component::stateful_lines::StatefulGroupedLines, resource::ResourceType, ring,
+    sensor::settings::SETTINGS,
+};
 
-use super::{Navigator, NavigatorArgs, OverviewArg, PageArg};
+use super::{
+    help_page::HelpPage,
+    keymap::{self, Action, KeyContext},
+    settings_page::SettingsPage,
+    theme, Navigator, NavigatorArgs, OverviewArg, PageArg,
+};
 
 #[derive(Debug, Default)]
 pub struct SidebarAndPage {
@@ -17,6 +26,11 @@ pub struct SidebarAndPage {
     pub sidebar_state: StatefulGroupedLines<'static>,
     pub page: Rect,
     pub page_focused: bool,
+    /// Drawn in place of the page while open.
+    pub settings: Option<SettingsPage>,
+    pub help: Option<HelpPage>,
+    /// Whether the page viewed last time was focused at the start.
+    pub restored: bool,
 }
 
 impl SidebarAndPage {
@@ -34,7 +48,21 @@ impl SidebarAndPage {
         if top.width > 22 {
             spans.push(Span::styled(" * ", Style::new()));
         }
+        if ring::is_frozen() {
+            let offset = ring::view_offset();
+            let label = if offset.is_zero() {
+                " FROZEN ".to_string()
+            } else {
+                format!(" FROZEN -{}s ", offset.as_secs_f32())
+            };
+            spans.push(Span::styled(label, theme::palette().status()));
+            spans.push(Span::raw(" "));
+        }
         spans.push(Span::styled(time.to_string(), Style::new()));
+        if top.width > 36 {
+            let interval = SETTINGS.refresh_speed().ui_refresh_interval();
+            spans.push(Span::styled(format!(" {}s", interval), Style::new().dim()));
+        }
 
         let header = Line::from(spans);
 
@@ -68,9 +96,35 @@ impl SidebarAndPage {
             }
         }
         self.sidebar_state.update_blocks(overviews);
+        if !self.restored && !resources.is_empty() {
+            self.restored = true;
+            let last = SETTINGS.last_viewed_page();
+            if let Some(index) = resources
+                .iter()
+                .position(|e| Some(e.get_id()) == last.as_deref())
+            {
+                self.sidebar_state.focus_index(index);
+            }
+        }
         self.sidebar_state
             .render(frame, rect.clone(), !self.page_focused);
     }
+
+    /// Saves the focused page to open it again at the next start.
+    fn remember_page(&self, resources: &[ResourceType]) {
+        let Some(rt) = self
+            .sidebar_state
+            .focused_index()
+            .and_then(|id| resources.get(id))
+        else {
+            return;
+        };
+        if SETTINGS.last_viewed_page().as_deref() != Some(rt.get_id()) {
+            if let Err(err) = SETTINGS.set_last_viewed_page(rt.get_id()) {
+                tracing::error!("unable to save the last viewed page: {}", err);
+            }
+        }
+    }
 }
 
 impl Navigator for SidebarAndPage {
@@ -111,7 +165,8 @@ impl Navigator for SidebarAndPage {
                 rt.cached_page_state().focus_prev();
             }
         } else {
-            self.sidebar_state.focus_prev()
+            self.sidebar_state.focus_prev();
+            self.remember_page(resources);
         }
     }
 
@@ -126,7 +181,8 @@ impl Navigator for SidebarAndPage {
                 rt.cached_page_state().focus_next();
             }
         } else {
-            self.sidebar_state.focus_next()
+            self.sidebar_state.focus_next();
+            self.remember_page(resources);
         }
     }
 
@@ -142,7 +198,11 @@ impl Navigator for SidebarAndPage {
 
         self.overview(frame, resources);
 
-        if let Some(rt) = self
+        if let Some(help) = self.help.as_mut() {
+            help.render(frame, self.page, self.page_focused);
+        } else if let Some(settings) = self.settings.as_ref() {
+            settings.render(frame, self.page, self.page_focused);
+        } else if let Some(rt) = self
             .sidebar_state
             .focused_index()
             .or_else(|| Some(0))
@@ -156,7 +216,21 @@ impl Navigator for SidebarAndPage {
         }
     }
 
-    fn handle_event<'a>(&mut self, event: &super::NavigatorEvent, args: NavigatorArgs<'a>) {
+    fn handle_event<'a>(&mut self, event: &super::NavigatorEvent, args: NavigatorArgs<'a>) -> bool {
+        let super::NavigatorEvent::KeyEvent(key) = event;
+        if let Some(help) = self.help.as_mut() {
+            if !help.handle_key(key) {
+                self.help.take();
+            }
+            return true;
+        }
+        if let Some(settings) = self.settings.as_mut() {
+            if !settings.handle_key(key) {
+                self.settings.take();
+            }
+            return true;
+        }
+
         if self.page_focused {
             if let Some(rt) = self
                 .sidebar_state
@@ -166,20 +240,52 @@ impl Navigator for SidebarAndPage {
             {
                 let handled = rt.handle_navi_event(event);
                 if handled {
-                    return;
+                    return true;
                 }
             }
         }
-        match event {
-            super::NavigatorEvent::KeyEvent(key) => {
-                match key.code {
-                    KeyCode::Up => self.focus_up(args.resources),
-                    KeyCode::Down => self.focus_down(args.resources),
-                    KeyCode::Left => self.focus_left(),
-                    KeyCode::Right => self.focus_right(),
-                    _ => {}
-                };
+        match keymap::lookup(key, KeyContext::Global) {
+            Some(Action::Up) => self.focus_up(args.resources),
+            Some(Action::Down) => self.focus_down(args.resources),
+            Some(Action::Left) => self.focus_left(),
+            Some(Action::Right) => self.focus_right(),
+            Some(Action::RefreshFaster) => {
+                SETTINGS.override_refresh_speed(SETTINGS.refresh_speed().faster())
             }
+            Some(Action::RefreshSlower) => {
+                SETTINGS.override_refresh_speed(SETTINGS.refresh_speed().slower())
+            }
+            Some(Action::Freeze) => {
+                ring::toggle_freeze();
+            }
+            Some(Action::ScrubOlder) => ring::scrub(1, scrub_step()),
+            Some(Action::ScrubNewer) => ring::scrub(-1, scrub_step()),
+            Some(Action::ScrubOlderFar) => ring::scrub(10, scrub_step()),
+            Some(Action::ScrubNewerFar) => ring::scrub(-10, scrub_step()),
+            Some(Action::Settings) => {
+                self.settings.replace(SettingsPage::default());
+                self.page_focused = true;
+            }
+            Some(Action::Help) => {
+                let page = self
+                    .page_focused
+                    .then(|| {
+                        self.sidebar_state
+                            .focused_index()
+                            .or(Some(0))
+                            .and_then(|id| args.resources.get(id))
+                    })
+                    .flatten()
+                    .map(|rt| (rt.get_type_name(), rt.key_context()));
+                self.help.replace(HelpPage::new(page, args.replay));
+            }
+            _ => return false,
         }
+        true
     }
 }
+
+/// Scrubbing moves the view one UI refresh at a time.
+fn scrub_step() -> Duration {
+    Duration::from_secs_f32(SETTINGS.refresh_speed().ui_refresh_interval())
+}
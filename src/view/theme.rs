@@ -1,27 +1,133 @@
-use std::sync::Arc;
+//! Color schemes. Besides the built-in ones, every section of `themes.ini`
+//! next to the config is a theme, e.g.
+//!
+//! ```ini
+//! [solarized]
+//! inherit = dark
+//! fg = #839496
+//! cpu = #dc322f
+//! gradient = #859900, #2aa198, #268bd2, #b58900, #d33682, #dc322f
+//! ```
+//!
+//! The keys are the fields of [`Palette`], colors are names like
+//! `lightblue`, `#rrggbb` or 256 color indexes. `NO_COLOR` starts with the
+//! monochrome scheme.
 
-use ratatui::style::{Color, Modifier, Style, Stylize};
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+};
+
+use anyhow::{bail, Context, Result};
+use ini::Ini;
+use once_cell::sync::Lazy;
+use ratatui::style::{Color, Modifier, Style};
+
+use crate::sensor::settings::SETTINGS;
 
 pub type SharedTheme = Arc<Theme>;
 
-#[derive(Debug, Clone)]
-pub struct Theme {
-    fg: Color,
-    label_fg: Color,
-}
+/// Percentages from which usage is drawn in `medium` and `high`.
+const MEDIUM_USAGE: f64 = 50.;
+const HIGH_USAGE: f64 = 80.;
 
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            fg: Color::Reset,
-            label_fg: Color::DarkGray,
+/// The built-in schemes followed by the ones of `themes.ini`.
+static PALETTES: Lazy<Vec<Arc<Palette>>> = Lazy::new(|| {
+    let mut palettes: Vec<Arc<Palette>> = vec![
+        Palette::dark(),
+        Palette::light(),
+        Palette::high_contrast(),
+        Palette::monochrome(),
+    ]
+    .into_iter()
+    .map(Arc::new)
+    .collect();
+
+    let Some(path) = SETTINGS
+        .path()
+        .and_then(|e| e.parent())
+        .map(|e| e.join("themes.ini"))
+        .filter(|e| e.exists())
+    else {
+        return palettes;
+    };
+    match Ini::load_from_file(&path) {
+        Ok(ini) => {
+            for (name, section) in ini.iter() {
+                let Some(name) = name else {
+                    continue;
+                };
+                let pairs = section.iter().collect::<Vec<_>>();
+                match Palette::from_pairs(name, &pairs, &palettes) {
+                    Ok(palette) => {
+                        palettes.retain(|e| e.name != palette.name);
+                        palettes.push(Arc::new(palette));
+                    }
+                    Err(err) => tracing::error!("invalid theme {}: {:?}", name, err),
+                }
+            }
         }
+        Err(err) => tracing::error!("unable to load themes {:?}: {}", path, err),
     }
+    palettes
+});
+
+static CURRENT: Lazy<RwLock<Arc<Palette>>> = Lazy::new(|| {
+    let name = if std::env::var("NO_COLOR").is_ok_and(|e| !e.is_empty()) {
+        Some("monochrome".to_string())
+    } else {
+        SETTINGS.theme()
+    };
+    let palette = name
+        .and_then(|name| {
+            let found = find(&name);
+            if found.is_none() {
+                tracing::error!("unknown theme {}", name);
+            }
+            found
+        })
+        .unwrap_or_else(|| PALETTES[0].clone());
+    RwLock::new(palette)
+});
+
+fn find(name: &str) -> Option<Arc<Palette>> {
+    PALETTES.iter().find(|e| e.name == name).cloned()
+}
+
+/// The palette in use, changes with [`switch`].
+pub fn palette() -> Arc<Palette> {
+    CURRENT
+        .read()
+        .map(|e| e.clone())
+        .unwrap_or_else(|_| PALETTES[0].clone())
+}
+
+/// Names of all themes, the built-in ones first.
+pub fn names() -> Vec<String> {
+    PALETTES.iter().map(|e| e.name.clone()).collect()
+}
+
+/// Uses the theme `name` from now on, the config is untouched.
+pub fn switch(name: &str) -> Result<()> {
+    let palette = find(name).with_context(|| format!("unknown theme {}", name))?;
+    let mut current = CURRENT
+        .write()
+        .map_err(|_| anyhow::anyhow!("theme is poisoned"))?;
+    *current = palette;
+    Ok(())
 }
 
+/// A handle to the current palette, every clone follows [`switch`].
+#[derive(Debug, Clone, Default)]
+pub struct Theme {}
+
 impl Theme {
+    pub fn palette(&self) -> Arc<Palette> {
+        palette()
+    }
+
     pub fn fg(&self) -> Color {
-        self.fg
+        palette().fg
     }
 
     pub fn title(&self, focused: bool) -> Style {
@@ -33,10 +139,409 @@ impl Theme {
     }
 
     pub fn value(&self, focused: bool) -> Style {
-        Style::default()
+        let palette = palette();
+        if focused {
+            Style::default().fg(palette.fg_focused)
+        } else {
+            Style::default().fg(palette.fg)
+        }
     }
 
     pub fn key(&self, focused: bool) -> Style {
-        Style::default().fg(self.label_fg)
+        let palette = palette();
+        if focused {
+            Style::default().fg(palette.label_focused)
+        } else {
+            Style::default().fg(palette.label)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Palette {
+    pub name: String,
+
+    pub fg: Color,
+    /// Values of the blocks, brighter in the focused one.
+    pub fg_focused: Color,
+    /// Keys of the blocks, brighter in the focused one.
+    pub label: Color,
+    pub label_focused: Color,
+    /// Key hints and other secondary text.
+    pub hint: Color,
+    pub border: Color,
+    pub border_focused: Color,
+    /// The header of tables.
+    pub header: Color,
+    /// `Reset` for both reverses the line instead.
+    pub selection_fg: Color,
+    pub selection_bg: Color,
+    /// Labels in the top bar like `FROZEN`, and `REPLAY` in the status line.
+    pub status_fg: Color,
+    pub status_bg: Color,
+    pub banner_bg: Color,
+    /// Firing alerts and errors, `Reset` reverses them instead.
+    pub alert: Color,
+
+    /// Usage below `MEDIUM_USAGE`, below `HIGH_USAGE` and above.
+    pub low: Color,
+    pub medium: Color,
+    pub high: Color,
+
+    pub cpu: Color,
+    pub memory: Color,
+    pub gpu: Color,
+    pub drive: Color,
+    pub receive: Color,
+    pub send: Color,
+    /// Values which are not known yet, e.g. the I/O of a new process.
+    pub unknown: Color,
+    /// Of the progress bars, from empty to full.
+    pub gradient: Vec<Color>,
+    /// Of the CPU time categories, from user to guest.
+    pub cpu_modes: Vec<Color>,
+}
+
+impl Palette {
+    pub fn dark() -> Self {
+        Self {
+            name: "dark".to_string(),
+            fg: Color::Reset,
+            fg_focused: Color::White,
+            label: Color::DarkGray,
+            label_focused: Color::Gray,
+            hint: Color::DarkGray,
+            border: Color::Reset,
+            border_focused: Color::Reset,
+            header: Color::Reset,
+            selection_fg: Color::Reset,
+            selection_bg: Color::Reset,
+            status_fg: Color::Black,
+            status_bg: Color::LightBlue,
+            banner_bg: Color::LightYellow,
+            alert: Color::Red,
+            low: Color::Green,
+            medium: Color::Yellow,
+            high: Color::Red,
+            cpu: Color::Red,
+            memory: Color::Magenta,
+            gpu: Color::Red,
+            drive: Color::Green,
+            receive: Color::Blue,
+            send: Color::Yellow,
+            unknown: Color::Blue,
+            gradient: vec![
+                Color::Green,
+                Color::Cyan,
+                Color::Blue,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Red,
+            ],
+            cpu_modes: vec![
+                Color::Green,
+                Color::Blue,
+                Color::Red,
+                Color::DarkGray,
+                Color::Yellow,
+                Color::Magenta,
+                Color::Cyan,
+                Color::LightCyan,
+            ],
+        }
+    }
+
+    /// Darker colors which stay readable on a white background.
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            fg_focused: Color::Black,
+            label: Color::Gray,
+            label_focused: Color::DarkGray,
+            hint: Color::Gray,
+            status_fg: Color::White,
+            status_bg: Color::Blue,
+            banner_bg: Color::Indexed(130),
+            medium: Color::Indexed(130),
+            send: Color::Indexed(130),
+            gradient: vec![
+                Color::Green,
+                Color::Cyan,
+                Color::Blue,
+                Color::Indexed(130),
+                Color::Magenta,
+                Color::Red,
+            ],
+            cpu_modes: vec![
+                Color::Green,
+                Color::Blue,
+                Color::Red,
+                Color::Gray,
+                Color::Indexed(130),
+                Color::Magenta,
+                Color::Cyan,
+                Color::DarkGray,
+            ],
+            ..Self::dark()
+        }
+    }
+
+    pub fn high_contrast() -> Self {
+        Self {
+            name: "high-contrast".to_string(),
+            fg: Color::White,
+            fg_focused: Color::LightYellow,
+            label: Color::Gray,
+            label_focused: Color::White,
+            hint: Color::Gray,
+            border: Color::White,
+            border_focused: Color::LightYellow,
+            header: Color::LightYellow,
+            selection_fg: Color::Black,
+            selection_bg: Color::LightYellow,
+            status_fg: Color::Black,
+            status_bg: Color::LightCyan,
+            banner_bg: Color::LightYellow,
+            alert: Color::LightRed,
+            low: Color::LightGreen,
+            medium: Color::LightYellow,
+            high: Color::LightRed,
+            cpu: Color::LightRed,
+            memory: Color::LightMagenta,
+            gpu: Color::LightRed,
+            drive: Color::LightGreen,
+            receive: Color::LightCyan,
+            send: Color::LightYellow,
+            unknown: Color::LightBlue,
+            gradient: vec![
+                Color::LightGreen,
+                Color::LightCyan,
+                Color::LightBlue,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::LightRed,
+            ],
+            cpu_modes: vec![
+                Color::LightGreen,
+                Color::LightBlue,
+                Color::LightRed,
+                Color::Gray,
+                Color::LightYellow,
+                Color::LightMagenta,
+                Color::LightCyan,
+                Color::White,
+            ],
+        }
+    }
+
+    /// Only the default colors of the terminal, used with `NO_COLOR`.
+    pub fn monochrome() -> Self {
+        Self {
+            name: "monochrome".to_string(),
+            fg: Color::Reset,
+            fg_focused: Color::Reset,
+            label: Color::Reset,
+            label_focused: Color::Reset,
+            hint: Color::Reset,
+            border: Color::Reset,
+            border_focused: Color::Reset,
+            header: Color::Reset,
+            selection_fg: Color::Reset,
+            selection_bg: Color::Reset,
+            status_fg: Color::Reset,
+            status_bg: Color::Reset,
+            banner_bg: Color::Reset,
+            alert: Color::Reset,
+            low: Color::Reset,
+            medium: Color::Reset,
+            high: Color::Reset,
+            cpu: Color::Reset,
+            memory: Color::Reset,
+            gpu: Color::Reset,
+            drive: Color::Reset,
+            receive: Color::Reset,
+            send: Color::Reset,
+            unknown: Color::Reset,
+            gradient: vec![Color::Reset],
+            cpu_modes: vec![Color::Reset],
+        }
+    }
+
+    /// A theme of `themes.ini`, missing keys come from `inherit` or `dark`.
+    fn from_pairs(name: &str, pairs: &[(&str, &str)], known: &[Arc<Palette>]) -> Result<Self> {
+        let base = pairs
+            .iter()
+            .find(|(k, _)| *k == "inherit")
+            .map(|(_, v)| v.trim());
+        let mut palette = match base {
+            Some(base) => known
+                .iter()
+                .find(|e| e.name == base)
+                .map(|e| e.as_ref().clone())
+                .with_context(|| format!("unknown theme {}", base))?,
+            None => Self::dark(),
+        };
+        palette.name = name.to_string();
+
+        for (key, value) in pairs {
+            if *key == "inherit" {
+                continue;
+            }
+            if *key == "gradient" || *key == "cpu_modes" {
+                let colors = value
+                    .split(',')
+                    .map(|e| parse_color(e.trim()))
+                    .collect::<Result<Vec<_>>>()?;
+                if colors.is_empty() {
+                    bail!("{} needs a color", key);
+                }
+                if *key == "gradient" {
+                    palette.gradient = colors;
+                } else {
+                    palette.cpu_modes = colors;
+                }
+                continue;
+            }
+
+            let color = parse_color(value.trim())?;
+            let field = match *key {
+                "fg" => &mut palette.fg,
+                "fg_focused" => &mut palette.fg_focused,
+                "label" => &mut palette.label,
+                "label_focused" => &mut palette.label_focused,
+                "hint" => &mut palette.hint,
+                "border" => &mut palette.border,
+                "border_focused" => &mut palette.border_focused,
+                "header" => &mut palette.header,
+                "selection_fg" => &mut palette.selection_fg,
+                "selection_bg" => &mut palette.selection_bg,
+                "status_fg" => &mut palette.status_fg,
+                "status_bg" => &mut palette.status_bg,
+                "banner_bg" => &mut palette.banner_bg,
+                "alert" => &mut palette.alert,
+                "low" => &mut palette.low,
+                "medium" => &mut palette.medium,
+                "high" => &mut palette.high,
+                "cpu" => &mut palette.cpu,
+                "memory" => &mut palette.memory,
+                "gpu" => &mut palette.gpu,
+                "drive" => &mut palette.drive,
+                "receive" => &mut palette.receive,
+                "send" => &mut palette.send,
+                "unknown" => &mut palette.unknown,
+                _ => bail!("unknown key {}", key),
+            };
+            *field = color;
+        }
+        Ok(palette)
+    }
+
+    pub fn hint(&self) -> Style {
+        Style::new().fg(self.hint)
+    }
+
+    pub fn header(&self) -> Style {
+        Style::new().fg(self.header)
+    }
+
+    pub fn border(&self, focused: bool) -> Style {
+        Style::new().fg(if focused {
+            self.border_focused
+        } else {
+            self.border
+        })
+    }
+
+    pub fn selection(&self) -> Style {
+        reversed_if_reset(self.selection_fg, self.selection_bg)
+    }
+
+    pub fn status(&self) -> Style {
+        reversed_if_reset(self.status_fg, self.status_bg)
+    }
+
+    pub fn banner(&self) -> Style {
+        reversed_if_reset(self.status_fg, self.banner_bg)
+    }
+
+    pub fn alert(&self) -> Style {
+        reversed_if_reset(self.alert, Color::Reset)
+    }
+
+    /// The color of a usage in percent.
+    pub fn usage(&self, percent: f64) -> Color {
+        if percent >= HIGH_USAGE {
+            self.high
+        } else if percent >= MEDIUM_USAGE {
+            self.medium
+        } else {
+            self.low
+        }
+    }
+
+    /// The color of the gradient for a fill level from 0 to 100.
+    pub fn gradient(&self, percent: f64) -> Color {
+        let idx = (percent.max(0.) / 100. * self.gradient.len() as f64) as usize;
+        self.gradient
+            .get(idx.min(self.gradient.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or(self.fg)
+    }
+
+    /// The color of the `idx`th CPU time category, the last one repeats.
+    pub fn cpu_mode(&self, idx: usize) -> Color {
+        self.cpu_modes
+            .get(idx.min(self.cpu_modes.len().saturating_sub(1)))
+            .copied()
+            .unwrap_or(self.fg)
+    }
+}
+
+/// Monochrome schemes reverse what would otherwise be told apart by color.
+fn reversed_if_reset(fg: Color, bg: Color) -> Style {
+    if fg == Color::Reset && bg == Color::Reset {
+        Style::new().add_modifier(Modifier::REVERSED)
+    } else {
+        Style::new().fg(fg).bg(bg)
+    }
+}
+
+fn parse_color(value: &str) -> Result<Color> {
+    Color::from_str(value).map_err(|_| anyhow::anyhow!("invalid color `{}`", value))
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use ratatui::style::Color;
+
+    use super::Palette;
+
+    #[test]
+    fn test_from_pairs() {
+        let known = vec![Arc::new(Palette::dark()), Arc::new(Palette::monochrome())];
+        let palette = Palette::from_pairs(
+            "mine",
+            &[
+                ("inherit", "monochrome"),
+                ("cpu", "#ff0000"),
+                ("gradient", "green, 208"),
+                ("cpu_modes", "blue"),
+            ],
+            &known,
+        )
+        .unwrap();
+        assert_eq!(palette.name, "mine");
+        assert_eq!(palette.cpu, Color::Rgb(255, 0, 0));
+        assert_eq!(palette.memory, Color::Reset);
+        assert_eq!(palette.gradient, vec![Color::Green, Color::Indexed(208)]);
+        assert_eq!(palette.gradient(10.), Color::Green);
+        assert_eq!(palette.gradient(100.), Color::Indexed(208));
+        assert_eq!(palette.cpu_mode(3), Color::Blue);
+
+        assert!(Palette::from_pairs("x", &[("cpu", "nocolor")], &known).is_err());
+        assert!(Palette::from_pairs("x", &[("inherit", "x")], &known).is_err());
+        assert!(Palette::from_pairs("x", &[("shade", "red")], &known).is_err());
     }
 }